@@ -0,0 +1,160 @@
+//! Compares the vectorizable elementwise kernels against equivalent scalar loops.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use minidx_core::layers::{Activation, RMSDiv, Softmax};
+use minidx_core::loss::DiffLoss;
+use minidx_core::{kernels, Module, RevModule};
+
+fn input<const I: usize>() -> [f32; I] {
+    std::array::from_fn(|i| ((i * 7919) % 200) as f32 / 50.0 - 2.0)
+}
+
+/// Scalar versions of the kernels, matching how the layers were written before
+/// they were vectorized.
+mod scalar {
+    pub fn sigmoid(x: f32) -> f32 {
+        1.0 / (1.0 + (-x).exp())
+    }
+
+    pub fn activation<const I: usize>(
+        a: &minidx_core::layers::Activation<f32>,
+        x: &[f32; I],
+    ) -> [f32; I] {
+        use minidx_core::layers::Activation;
+        let mut out = [0.0; I];
+        for (o, i) in out.iter_mut().zip(x.iter()) {
+            *o = match a {
+                Activation::Sigmoid => sigmoid(*i),
+                Activation::SiLU => *i * sigmoid(*i),
+                Activation::Relu => i.max(0.0),
+                Activation::Tanh => i.tanh(),
+                Activation::LeakyRelu(a) => {
+                    if *i > 0.0 {
+                        *i
+                    } else {
+                        a * i
+                    }
+                }
+                Activation::Softplus => (1.0 + i.exp()).ln(),
+                Activation::Sine => i.sin(),
+                Activation::Cosine => i.cos(),
+            };
+        }
+        out
+    }
+
+    pub fn softmax<const I: usize>(x: &[f32; I]) -> [f32; I] {
+        let max = x.iter().fold(f32::NEG_INFINITY, |l, r| l.max(*r));
+        let mut out = [0.0; I];
+        out.iter_mut()
+            .zip(x)
+            .for_each(|(o, x)| *o = (x - max).exp());
+        let sum = out.iter().fold(0.0, |a, x| a + x);
+        out.iter_mut().for_each(|o| *o /= sum);
+        out
+    }
+
+    pub fn softmax_backprop<const I: usize>(x: &[f32; I], g: &[f32; I]) -> [f32; I] {
+        let y = softmax(x);
+        let mut out = [0.0; I];
+        out.iter_mut().enumerate().for_each(|(i, o)| {
+            let mut sum = 0.0;
+            for j in 0..I {
+                let kronecker = if i == j { 1.0 } else { 0.0 };
+                sum += (kronecker - y[j]) * g[j];
+            }
+            *o = y[i] * sum;
+        });
+        out
+    }
+
+    pub fn mse<const I: usize>(a: &[f32; I], b: &[f32; I]) -> f32 {
+        a.iter().zip(b).fold(0.0, |a, (x, y)| a + (x - y) * (x - y)) / I as f32
+    }
+}
+
+fn bench_size<const I: usize>(c: &mut Criterion) {
+    let (x, g) = (input::<I>(), input::<I>().map(|v| v * 0.5));
+
+    let mut group = c.benchmark_group("activation");
+    for a in [Activation::Relu, Activation::Sigmoid, Activation::SiLU] {
+        let name = format!("{:?}", a);
+        group.bench_with_input(
+            BenchmarkId::new(format!("{}/scalar", name), I),
+            &x,
+            |b, x| b.iter(|| scalar::activation(&a, black_box(x))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("{}/kernel", name), I),
+            &x,
+            |b, x| b.iter(|| Module::forward(&a, black_box(x)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("{}/reverse", name), I),
+            &x,
+            |b, x| b.iter(|| RevModule::reverse(&a, black_box(x), &g)),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("softmax");
+    let softmax = Softmax::default();
+    group.bench_with_input(BenchmarkId::new("forward/scalar", I), &x, |b, x| {
+        b.iter(|| scalar::softmax(black_box(x)))
+    });
+    group.bench_with_input(BenchmarkId::new("forward/kernel", I), &x, |b, x| {
+        b.iter(|| Module::forward(&softmax, black_box(x)).unwrap())
+    });
+    if I <= 512 {
+        group.bench_with_input(BenchmarkId::new("reverse/scalar", I), &x, |b, x| {
+            b.iter(|| scalar::softmax_backprop(black_box(x), &g))
+        });
+    }
+    group.bench_with_input(BenchmarkId::new("reverse/kernel", I), &x, |b, x| {
+        b.iter(|| RevModule::reverse(&softmax, black_box(x), &g))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("rmsdiv");
+    let rmsdiv = RMSDiv::<f32, I>::default();
+    group.bench_with_input(BenchmarkId::new("forward", I), &x, |b, x| {
+        b.iter(|| Module::forward(&rmsdiv, black_box(x)).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("reverse", I), &x, |b, x| {
+        b.iter(|| RevModule::reverse(&rmsdiv, black_box(x), &g))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("reductions");
+    group.bench_with_input(BenchmarkId::new("mse/scalar", I), &x, |b, x| {
+        b.iter(|| scalar::mse(black_box(x), &g))
+    });
+    group.bench_with_input(BenchmarkId::new("mse/kernel", I), &x, |b, x| {
+        b.iter(|| black_box(x).mse(&g))
+    });
+    group.bench_with_input(BenchmarkId::new("sum/scalar", I), &x, |b, x| {
+        b.iter(|| black_box(x).iter().fold(0.0f32, |a, x| a + x))
+    });
+    group.bench_with_input(BenchmarkId::new("sum/kernel", I), &x, |b, x| {
+        b.iter(|| kernels::sum(black_box(x)))
+    });
+    group.bench_with_input(BenchmarkId::new("exp/scalar", I), &x, |b, x| {
+        b.iter(|| black_box(*x).map(f32::exp))
+    });
+    group.bench_with_input(BenchmarkId::new("exp/kernel", I), &x, |b, x| {
+        b.iter(|| {
+            let mut x = *black_box(x);
+            kernels::exp_f32(&mut x);
+            x
+        })
+    });
+    group.finish();
+}
+
+fn elementwise(c: &mut Criterion) {
+    bench_size::<64>(c);
+    bench_size::<512>(c);
+    bench_size::<4096>(c);
+}
+
+criterion_group!(benches, elementwise);
+criterion_main!(benches);
@@ -0,0 +1,135 @@
+//! Forward and backward passes of each layer, at several sizes.
+//!
+//! Benchmark names are the same with and without the `gemm` feature, so the two
+//! matmul implementations can be compared using criterion baselines:
+//!
+//! ```text
+//! cargo bench -p minidx-core --bench layers -- --save-baseline gemm
+//! cargo bench -p minidx-core --bench layers --no-default-features --features serde -- --baseline gemm
+//! ```
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use minidx_core::layers::*;
+use minidx_core::matmul::MatMulImpl;
+use minidx_core::quantize::{Granularity, Quantize};
+use minidx_core::{BackpropModule, Const, Module, ResetParams};
+use rand::SeedableRng;
+
+fn input<const I: usize>() -> [f32; I] {
+    std::array::from_fn(|i| ((i * 7919) % 200) as f32 / 50.0 - 2.0)
+}
+
+/// Benchmarks the forward pass, and the backward pass given a trace from the forward pass.
+fn bench_layer<const I: usize, const O: usize, L>(c: &mut Criterion, name: &str, mut layer: L)
+where
+    L: BackpropModule<[f32; I], Output = [f32; O]> + ResetParams,
+{
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    layer.rand_params(&mut rng, 1.0).unwrap();
+
+    let x = input::<I>();
+    let (out, trace) = layer.traced_forward(x).unwrap();
+    let grads = out.map(|g| g * 0.1);
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function(BenchmarkId::new("forward", I), |b| {
+        b.iter(|| layer.forward(black_box(&x)).unwrap())
+    });
+    group.bench_function(BenchmarkId::new("backward", I), |b| {
+        b.iter(|| layer.backprop(black_box(&trace), black_box(grads)))
+    });
+    group.finish();
+}
+
+/// Benchmarks the forward pass of a quantized layer.
+fn bench_quantized<const I: usize, const O: usize, L>(c: &mut Criterion, name: &str, mut layer: L)
+where
+    L: Quantize<[f32; I], Output = [f32; O]> + ResetParams,
+{
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    layer.rand_params(&mut rng, 1.0).unwrap();
+
+    let x = input::<I>();
+    let q = layer.quantize(&[x], Granularity::PerChannel).unwrap();
+    c.benchmark_group(name)
+        .bench_function(BenchmarkId::new("forward", I), |b| {
+            b.iter(|| q.forward(black_box(&x)).unwrap())
+        });
+}
+
+fn matmul<const N: usize>(c: &mut Criterion) {
+    let a: Vec<f32> = (0..N * N).map(|i| (i % 17) as f32 / 17.0).collect();
+    let mut out = vec![0.0f32; N * N];
+
+    let mut group = c.benchmark_group("matmul");
+    group.bench_function(BenchmarkId::new("vector", N), |b| {
+        b.iter(|| {
+            f32::matmul(
+                (1, N, N),
+                false,
+                black_box(a.as_ptr()),
+                [N, 1],
+                black_box(a.as_ptr()),
+                [N, 1],
+                out.as_mut_ptr(),
+                [N, 1],
+            )
+        })
+    });
+    group.bench_function(BenchmarkId::new("square", N), |b| {
+        b.iter(|| {
+            f32::matmul(
+                (N, N, N),
+                false,
+                black_box(a.as_ptr()),
+                [N, 1],
+                black_box(a.as_ptr()),
+                [N, 1],
+                out.as_mut_ptr(),
+                [N, 1],
+            )
+        })
+    });
+    group.finish();
+}
+
+fn elementwise<const I: usize>(c: &mut Criterion) {
+    bench_layer::<I, I, _>(c, "bias1d", Bias1d::<f32, I>::default());
+    bench_layer::<I, I, _>(c, "diag", Diag::<f32, I>::default());
+    bench_layer::<I, I, _>(c, "scalar_scale", ScalarScale::<f32>::default());
+    bench_layer::<I, I, _>(c, "relu", Activation::<f32>::Relu);
+    bench_layer::<I, I, _>(c, "sigmoid", Activation::<f32>::Sigmoid);
+    bench_layer::<I, I, _>(c, "tanh", Activation::<f32>::Tanh);
+    bench_layer::<I, I, _>(c, "softmax", Softmax::default());
+    bench_layer::<I, I, _>(c, "swish", Swish::<f32, I>::default());
+    bench_layer::<I, I, _>(c, "rmsdiv", RMSDiv::<f32, I>::default());
+}
+
+fn connected<const I: usize>(c: &mut Criterion) {
+    bench_layer(c, "dense", Dense::<f32, I, I>::default());
+    bench_layer(c, "glu", GLU::<f32, I, I>::default());
+    bench_layer(
+        c,
+        "residual_dense",
+        Residual::<f32, I, Dense<f32, I, I>>::default(),
+    );
+    bench_quantized(c, "qdense", Dense::<f32, I, I>::default());
+    matmul::<I>(c);
+}
+
+fn layers(c: &mut Criterion) {
+    elementwise::<16>(c);
+    elementwise::<64>(c);
+    elementwise::<256>(c);
+    elementwise::<1024>(c);
+
+    connected::<16>(c);
+    connected::<64>(c);
+    connected::<256>(c);
+
+    bench_layer(c, "conv1d", Conv1d::<f32, 8, 6, Const<3>>::default());
+    bench_layer(c, "conv1d", Conv1d::<f32, 16, 14, Const<3>>::default());
+    bench_quantized(c, "qconv1d", Conv1d::<f32, 16, 14, Const<3>>::default());
+}
+
+criterion_group!(benches, layers);
+criterion_main!(benches);
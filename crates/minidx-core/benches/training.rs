@@ -0,0 +1,123 @@
+//! Training steps of a small network, and saving and loading parameters.
+//!
+//! See the `layers` benchmark for comparing builds with and without the `gemm` feature.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use minidx_core::layers::{Activation, Bias1d, Dense};
+use minidx_core::loss::DiffLoss;
+use minidx_core::optimizers::TrainParams;
+use minidx_core::{train_batch, train_batch_parallel, BackpropModule, LoadableModule, ResetParams};
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+type Network = (
+    (Dense<f32, 16, 64>, Bias1d<f32, 64>),
+    Activation<f32>,
+    (Dense<f32, 64, 64>, Bias1d<f32, 64>),
+    Activation<f32>,
+    Dense<f32, 64, 4>,
+);
+
+fn new_network() -> Network {
+    let mut network = Network::default();
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    network.init_params(&mut rng).unwrap();
+    network
+}
+
+/// Returns a source of training samples, of a smooth function of the inputs.
+fn new_source() -> impl FnMut() -> ([f32; 16], [f32; 4]) {
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(2);
+    move || {
+        let x: [f32; 16] = std::array::from_fn(|_| rng.random_range(-1.0..1.0));
+        let y = std::array::from_fn(|i| x[i * 4..(i + 1) * 4].iter().sum::<f32>().sin());
+        (x, y)
+    }
+}
+
+fn train(c: &mut Criterion) {
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut group = c.benchmark_group("train");
+    for batch_size in [8, 64, 256] {
+        group.throughput(Throughput::Elements(batch_size as u64));
+
+        let mut network = new_network();
+        let mut updater = network.new_momentum(TrainParams::with_lr(1.0e-3), 0.9);
+        let mut source = new_source();
+        group.bench_function(BenchmarkId::new("batch", batch_size), |b| {
+            b.iter(|| {
+                train_batch(
+                    &mut updater,
+                    &mut network,
+                    |got, want| (got.mse(want), got.mse_input_grads(want)),
+                    &mut source,
+                    batch_size,
+                )
+            })
+        });
+
+        for threads in [1, 2, 4, 8].into_iter().filter(|t| *t <= max_threads) {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let mut network = new_network();
+            let mut updater = network.new_momentum(TrainParams::with_lr(1.0e-3), 0.9);
+            let mut source = new_source();
+            group.bench_function(
+                BenchmarkId::new(format!("parallel/{}-threads", threads), batch_size),
+                |b| {
+                    b.iter(|| {
+                        pool.install(|| {
+                            train_batch_parallel(
+                                &mut updater,
+                                &mut network,
+                                |got, want| (got.mse(want), got.mse_input_grads(want)),
+                                &mut source,
+                                batch_size,
+                            )
+                        })
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn load_save(c: &mut Criterion) {
+    let mut network = (
+        (
+            Dense::<f32, 256, 256>::default(),
+            Bias1d::<f32, 256>::default(),
+        ),
+        Activation::<f32>::Relu,
+        (
+            Dense::<f32, 256, 256>::default(),
+            Bias1d::<f32, 256>::default(),
+        ),
+    );
+    let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+    network.init_params(&mut rng).unwrap();
+
+    let mut saved = HashMap::new();
+    network.save("".into(), &mut saved).unwrap();
+    let params: usize = saved.values().map(|v| v.len()).sum();
+
+    let mut group = c.benchmark_group("params");
+    group.throughput(Throughput::Elements(params as u64));
+    group.bench_function("save", |b| {
+        b.iter(|| {
+            let mut dict = HashMap::new();
+            network.save("".into(), &mut dict).unwrap();
+            dict
+        })
+    });
+    group.bench_function("load", |b| {
+        b.iter(|| network.load("".into(), black_box(&saved)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, train, load_save);
+criterion_main!(benches);
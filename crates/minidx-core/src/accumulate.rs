@@ -0,0 +1,197 @@
+use crate::optimizers::{GradAdjuster, GradApplyer};
+use crate::{BackpropModule, Float, Gradients};
+use std::marker::PhantomData;
+
+/// Accumulates gradients and loss over several micro-batches, so that a single
+/// update can be made from all of them.
+///
+/// This allows large effective batch sizes without holding the whole batch in memory,
+/// and batches assembled from several data sources. Each micro-batch can be computed
+/// sequentially (like [train_batch](crate::train_batch)) or in parallel (like
+/// [train_batch_parallel](crate::train_batch_parallel)), and may differ in size: the
+/// update is averaged over all samples.
+///
+/// ```
+/// # use minidx_core::{layers::Dense, GradAccumulator};
+/// # use minidx_core::optimizers::TrainParams;
+/// # use minidx_core::loss::DiffLoss;
+/// let mut network = Dense::<f32, 2, 1>::default();
+/// let mut updater = TrainParams::with_lr(1.0e-3);
+///
+/// let mut acc = GradAccumulator::new();
+/// let loss = |got: &[f32; 1], want: &[f32; 1]| (got.mse(want), got.mse_input_grads(want));
+/// acc.accumulate_batch(&network, loss, &mut || ([1.0, 2.0], [0.5]), 8);
+/// acc.accumulate_batch_parallel(&network, loss, &mut || ([2.0, 1.0], [0.5]), 8);
+/// assert_eq!(acc.samples(), 16);
+///
+/// let avg_loss = acc.apply(&mut updater, &mut network);
+/// assert!(avg_loss.is_some());
+/// ```
+pub struct GradAccumulator<Input, Network: BackpropModule<Input>>
+where
+    Network::SelfGrads: Gradients,
+{
+    grads: Network::SelfGrads,
+    loss: f32,
+    samples: usize,
+    marker: PhantomData<fn(Input)>,
+}
+
+impl<Input, Network: BackpropModule<Input>> Default for GradAccumulator<Input, Network>
+where
+    Network::SelfGrads: Gradients,
+{
+    fn default() -> Self {
+        Self {
+            grads: Network::SelfGrads::empty(),
+            loss: 0.0,
+            samples: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<Input, Network: BackpropModule<Input>> GradAccumulator<Input, Network>
+where
+    Network::SelfGrads: Gradients,
+{
+    /// Constructs an empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of samples accumulated since the last update.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the average loss of the samples accumulated since the last update.
+    pub fn avg_loss(&self) -> Option<f32> {
+        if self.samples == 0 {
+            None
+        } else {
+            Some(self.loss / self.samples as f32)
+        }
+    }
+
+    /// Computes the gradients of a micro-batch of N input-output pairs, adding them
+    /// to the accumulator.
+    ///
+    /// The average loss over all samples in the micro-batch is returned.
+    pub fn accumulate_batch<LV: Float, S: FnMut() -> (Input, Network::Output)>(
+        &mut self,
+        network: &Network,
+        loss: impl Fn(&Network::Output, &Network::Output) -> (LV, Network::Output),
+        source: &mut S,
+        batch_size: usize,
+    ) -> f32 {
+        let (grads, lv) =
+            crate::batch_grads::<Input, LV, Network, S>(network, loss, source, batch_size);
+        self.add(grads, lv, batch_size)
+    }
+
+    /// Parallel version of [GradAccumulator::accumulate_batch].
+    pub fn accumulate_batch_parallel<LV: Float, S: FnMut() -> (Input, Network::Output)>(
+        &mut self,
+        network: &Network,
+        loss: impl Fn(&Network::Output, &Network::Output) -> (LV, Network::Output) + Sync,
+        source: &mut S,
+        batch_size: usize,
+    ) -> f32
+    where
+        Network: Sized + Sync + Send,
+        (Input, Network::Output): Sized + Sync + Send,
+        Network::SelfGrads: Sync + Send,
+    {
+        let (grads, lv) =
+            crate::batch_grads_parallel::<Input, LV, Network, S>(network, loss, source, batch_size);
+        self.add(grads, lv, batch_size)
+    }
+
+    fn add(&mut self, grads: Network::SelfGrads, loss: f32, samples: usize) -> f32 {
+        self.grads.add(grads);
+        self.loss += loss;
+        self.samples += samples;
+        loss / samples as f32
+    }
+
+    /// Updates the network using the averaged gradients of all accumulated samples, and
+    /// resets the accumulator.
+    ///
+    /// The average loss over all accumulated samples is returned, or None (and no
+    /// update is made) if no samples were accumulated.
+    pub fn apply<GA: GradAdjuster<Network::SelfGrads> + GradApplyer>(
+        &mut self,
+        ga: &mut GA,
+        network: &mut Network,
+    ) -> Option<f32> {
+        let lv = self.avg_loss()?;
+        let mut grads = std::mem::replace(&mut self.grads, Network::SelfGrads::empty());
+        grads.scale((self.samples as f32).recip());
+        (self.loss, self.samples) = (0.0, 0);
+
+        let gradient_updates = ga.adjust(grads, lv);
+        network.update(ga, gradient_updates).expect("update failed");
+        ga.advance_step();
+        Some(lv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Dense;
+    use crate::loss::DiffLoss;
+    use crate::optimizers::TrainParams;
+    use crate::{train_batch, LoadableModule};
+    use std::collections::HashMap;
+
+    fn weights(network: &Dense<f32, 2, 1>) -> Vec<f64> {
+        let mut store = HashMap::new();
+        network.save("".into(), &mut store).unwrap();
+        store.remove("").unwrap()
+    }
+
+    #[test]
+    fn test_matches_train_batch() {
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![0.5, -0.5]);
+        let mut a = Dense::<f32, 2, 1>::default();
+        a.load("".into(), &store).unwrap();
+        let mut b = a.clone();
+
+        let loss = |got: &[f32; 1], want: &[f32; 1]| (got.mse(want), got.mse_input_grads(want));
+        let samples = [
+            ([1.0f32, 2.0], [0.5f32]),
+            ([-1.0, 0.5], [1.0]),
+            ([0.0, 1.0], [0.0]),
+        ];
+
+        let mut i = 0;
+        let mut source = || {
+            i += 1;
+            samples[(i - 1) % samples.len()]
+        };
+        let lv_a = train_batch(&mut TrainParams::with_lr(0.1), &mut a, loss, &mut source, 3);
+
+        let mut acc = GradAccumulator::new();
+        let mut i = 0;
+        let mut source = || {
+            i += 1;
+            samples[(i - 1) % samples.len()]
+        };
+        acc.accumulate_batch(&b, loss, &mut source, 1);
+        acc.accumulate_batch_parallel(&b, loss, &mut source, 2);
+        assert_eq!(acc.samples(), 3);
+        let lv_b = acc.apply(&mut TrainParams::with_lr(0.1), &mut b).unwrap();
+
+        assert!((lv_a - lv_b).abs() < 1e-6);
+        weights(&a)
+            .iter()
+            .zip(weights(&b).iter())
+            .for_each(|(a, b)| assert!((a - b).abs() < 1e-6));
+
+        assert_eq!(acc.samples(), 0);
+        assert!(acc.apply(&mut TrainParams::with_lr(0.1), &mut b).is_none());
+    }
+}
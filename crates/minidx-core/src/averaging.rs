@@ -0,0 +1,235 @@
+//! Averaging of network parameters over the course of training.
+//!
+//! Averaged parameters often generalize better than the parameters at the
+//! end of training. [WeightEma] keeps an exponential moving average, while
+//! [Swa] (stochastic weight averaging) keeps an equal-weighted average over
+//! a range of steps.
+use crate::{LoadSaveError, LoadableModule};
+use std::collections::HashMap;
+use std::ops::Range;
+
+fn params<N: LoadableModule>(network: &N) -> Result<HashMap<String, Vec<f64>>, LoadSaveError> {
+    let mut params = HashMap::new();
+    network.save("".into(), &mut params)?;
+    Ok(params)
+}
+
+/// Moves each averaged parameter towards the corresponding parameter of the
+/// network, by the given fraction.
+fn blend(
+    avg: &mut HashMap<String, Vec<f64>>,
+    params: HashMap<String, Vec<f64>>,
+    fraction: f64,
+) -> Result<(), LoadSaveError> {
+    for (path, values) in params {
+        let Some(a) = avg.get_mut(&path) else {
+            return Err(LoadSaveError {
+                path,
+                err: "parameter missing from average".into(),
+            });
+        };
+        a.iter_mut()
+            .zip(values)
+            .for_each(|(a, v)| *a += (v - *a) * fraction);
+    }
+    Ok(())
+}
+
+/// Maintains an exponential moving average (EMA) of the parameters of a network.
+///
+/// Call [WeightEma::update] after each optimizer step, and use [WeightEma::network]
+/// (or [WeightEma::swap]) to evaluate or save the averaged network.
+#[derive(Clone, Debug)]
+pub struct WeightEma<N: LoadableModule + Clone> {
+    shadow: N,
+    avg: HashMap<String, Vec<f64>>,
+    decay: f32,
+    warmup: usize,
+    updates: usize,
+}
+
+impl<N: LoadableModule + Clone> WeightEma<N> {
+    /// Starts averaging from the current parameters of the network.
+    ///
+    /// Each update, the average moves `1 - decay` of the way towards the
+    /// parameters of the network. Typical values are 0.99 to 0.9999.
+    pub fn new(network: &N, decay: f32) -> Result<Self, LoadSaveError> {
+        assert!((0.0..=1.0).contains(&decay), "decay must be in [0,1]");
+        Ok(Self {
+            shadow: network.clone(),
+            avg: params(network)?,
+            decay,
+            warmup: 0,
+            updates: 0,
+        })
+    }
+
+    /// Linearly ramps the decay up from zero over the given number of updates, so
+    /// the average is not dominated by the (typically random) initial parameters.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn and_warmup(mut self, updates: usize) -> Self {
+        self.warmup = updates;
+        self
+    }
+
+    /// Returns the decay which will be used by the next update.
+    pub fn current_decay(&self) -> f32 {
+        if self.updates < self.warmup {
+            self.decay * self.updates as f32 / self.warmup as f32
+        } else {
+            self.decay
+        }
+    }
+
+    /// Returns the number of updates made to the average.
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+
+    /// Updates the average with the current parameters of the network.
+    pub fn update(&mut self, network: &N) -> Result<(), LoadSaveError> {
+        let fraction = 1.0 - self.current_decay() as f64;
+        blend(&mut self.avg, params(network)?, fraction)?;
+        self.updates += 1;
+        self.shadow.load("".into(), &self.avg)
+    }
+
+    /// Returns a network with the averaged parameters.
+    pub fn network(&self) -> &N {
+        &self.shadow
+    }
+
+    /// Consumes the average, returning a network with the averaged parameters.
+    pub fn into_network(self) -> N {
+        self.shadow
+    }
+
+    /// Swaps the given network with the network holding the averaged parameters.
+    ///
+    /// Swapping again restores the original network. Updates should not be made
+    /// while the networks are swapped.
+    pub fn swap(&mut self, network: &mut N) {
+        std::mem::swap(&mut self.shadow, network);
+    }
+}
+
+/// Maintains an equal-weighted average of the parameters of a network over a range
+/// of steps, known as stochastic weight averaging (SWA).
+///
+/// SWA is typically used towards the end of training, with a constant or cyclic
+/// learning rate.
+#[derive(Clone, Debug)]
+pub struct Swa<N: LoadableModule + Clone> {
+    shadow: Option<N>,
+    avg: HashMap<String, Vec<f64>>,
+    steps: Range<usize>,
+    every: usize,
+    count: usize,
+}
+
+impl<N: LoadableModule + Clone> Swa<N> {
+    /// Averages the parameters of the network at each step within the given range.
+    pub fn new(steps: Range<usize>) -> Self {
+        Self {
+            shadow: None,
+            avg: HashMap::new(),
+            steps,
+            every: 1,
+            count: 0,
+        }
+    }
+
+    /// Only averages the parameters every N steps within the range.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn and_every(mut self, every: usize) -> Self {
+        self.every = every.max(1);
+        self
+    }
+
+    /// Returns the number of snapshots of the parameters which have been averaged.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Offers the parameters of the network at the given step to the average.
+    ///
+    /// Returns true if the parameters were included in the average.
+    pub fn update(&mut self, step: usize, network: &N) -> Result<bool, LoadSaveError> {
+        if !self.steps.contains(&step) || (step - self.steps.start) % self.every != 0 {
+            return Ok(false);
+        }
+
+        self.count += 1;
+        match self.shadow.as_mut() {
+            None => {
+                self.avg = params(network)?;
+                self.shadow = Some(network.clone());
+            }
+            Some(shadow) => {
+                blend(&mut self.avg, params(network)?, 1.0 / self.count as f64)?;
+                shadow.load("".into(), &self.avg)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns a network with the averaged parameters, if any steps were averaged.
+    pub fn network(&self) -> Option<&N> {
+        self.shadow.as_ref()
+    }
+
+    /// Consumes the average, returning a network with the averaged parameters
+    /// if any steps were averaged.
+    pub fn into_network(self) -> Option<N> {
+        self.shadow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Bias1d;
+
+    fn network(v: f64) -> Bias1d<f32, 2> {
+        let mut network = Bias1d::default();
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![v, -v]);
+        network.load("".into(), &store).unwrap();
+        network
+    }
+
+    #[test]
+    fn test_ema() {
+        let mut ema = WeightEma::new(&network(0.0), 0.5).unwrap();
+        ema.update(&network(2.0)).unwrap();
+        assert_eq!(params(ema.network()).unwrap()[""], vec![1.0, -1.0]);
+        ema.update(&network(2.0)).unwrap();
+        assert_eq!(params(ema.network()).unwrap()[""], vec![1.5, -1.5]);
+
+        let mut ema = WeightEma::new(&network(0.0), 0.5).unwrap().and_warmup(2);
+        assert_eq!(ema.current_decay(), 0.0);
+        ema.update(&network(2.0)).unwrap();
+        assert_eq!(ema.current_decay(), 0.25);
+        assert_eq!(params(ema.network()).unwrap()[""], vec![2.0, -2.0]);
+
+        let mut n = network(4.0);
+        ema.swap(&mut n);
+        assert_eq!(params(&n).unwrap()[""], vec![2.0, -2.0]);
+        ema.swap(&mut n);
+        assert_eq!(params(&n).unwrap()[""], vec![4.0, -4.0]);
+    }
+
+    #[test]
+    fn test_swa() {
+        let mut swa = Swa::new(2..6).and_every(2);
+        assert!(swa.network().is_none());
+        for step in 0..8 {
+            swa.update(step, &network(step as f64)).unwrap();
+        }
+        // Steps 2 and 4
+        assert_eq!(swa.count(), 2);
+        assert_eq!(params(swa.network().unwrap()).unwrap()[""], vec![3.0, -3.0]);
+    }
+}
@@ -45,6 +45,33 @@ unit!(i64, 1);
 unit!(u128, 1);
 unit!(i128, 1);
 unit!(bool, true);
+#[cfg(feature = "half")]
+unit!(half::f16, half::f16::ONE);
+#[cfg(feature = "half")]
+unit!(half::bf16, half::bf16::ONE);
+
+impl<T: SafeZeros, const N: usize> SafeZeros for [T; N] {}
+
+/// Allocates a zeroed value directly on the heap.
+///
+/// Unlike `Box::new(T::default())`, the value is never built on the stack, so this
+/// is safe to use for arrays which are larger than the stack of a thread.
+pub(crate) fn boxed_zeros<T: SafeZeros>() -> Box<T> {
+    let layout = std::alloc::Layout::new::<T>();
+    if layout.size() == 0 {
+        // SAFETY: a dangling pointer is a valid box of a zero-sized type.
+        return unsafe { Box::from_raw(std::ptr::NonNull::<T>::dangling().as_ptr()) };
+    }
+    // SAFETY: the layout has a non-zero size, and SafeZeros guarantees all-zero
+    // bits are a valid value of T.
+    unsafe {
+        let ptr = std::alloc::alloc_zeroed(layout) as *mut T;
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        Box::from_raw(ptr)
+    }
+}
 
 /// Represents something that has a [Unit].
 #[allow(dead_code)]
@@ -84,6 +111,10 @@ impl Dtype for u32 {}
 impl Dtype for u64 {}
 impl Dtype for u128 {}
 impl Dtype for usize {}
+#[cfg(feature = "half")]
+impl Dtype for half::f16 {}
+#[cfg(feature = "half")]
+impl Dtype for half::bf16 {}
 
 /// Represents something that has a [Dtype].
 #[allow(dead_code)]
@@ -122,6 +153,14 @@ pub trait Float: Dtype + std::ops::Neg<Output = Self> {
     fn max(self, other: Self) -> Self;
     fn sin(self) -> Self;
     fn cos(self) -> Self;
+
+    /// Computes `exp` of each value in place.
+    ///
+    /// Implementations may use a vectorized approximation, see [crate::kernels].
+    #[inline]
+    fn exp_slice(x: &mut [Self]) {
+        x.iter_mut().for_each(|v| *v = v.exp());
+    }
 }
 
 impl Float for f32 {
@@ -164,6 +203,10 @@ impl Float for f32 {
     fn cos(self) -> Self {
         f32::cos(self)
     }
+    #[inline]
+    fn exp_slice(x: &mut [Self]) {
+        crate::kernels::exp_f32(x)
+    }
 }
 impl Float for f64 {
     const SMOL: f64 = 1.0e-20;
@@ -206,3 +249,57 @@ impl Float for f64 {
         f64::cos(self)
     }
 }
+
+/// Implements [Float] for a half-precision type, computing in f32.
+#[cfg(feature = "half")]
+macro_rules! half_float {
+    ($type:ty, $smol:expr) => {
+        impl Float for $type {
+            const SMOL: Self = $smol;
+            const NEG_INFINITY: Self = <$type>::NEG_INFINITY;
+
+            #[inline(always)]
+            fn exp(self) -> Self {
+                Self::from_f32(self.to_f32().exp())
+            }
+            #[inline(always)]
+            fn ln(self) -> Self {
+                Self::from_f32(self.to_f32().ln())
+            }
+            #[inline(always)]
+            fn sqrt(self) -> Self {
+                Self::from_f32(self.to_f32().sqrt())
+            }
+            #[inline(always)]
+            fn abs(self) -> Self {
+                Self::from_f32(self.to_f32().abs())
+            }
+            #[inline(always)]
+            fn tanh(self) -> Self {
+                Self::from_f32(self.to_f32().tanh())
+            }
+            #[inline(always)]
+            fn min(self, other: Self) -> Self {
+                Self::from_f32(self.to_f32().min(other.to_f32()))
+            }
+            #[inline(always)]
+            fn max(self, other: Self) -> Self {
+                Self::from_f32(self.to_f32().max(other.to_f32()))
+            }
+            #[inline(always)]
+            fn sin(self) -> Self {
+                Self::from_f32(self.to_f32().sin())
+            }
+            #[inline(always)]
+            fn cos(self) -> Self {
+                Self::from_f32(self.to_f32().cos())
+            }
+        }
+    };
+}
+
+// 1e-20 underflows in f16, so the smallest normal value is used instead.
+#[cfg(feature = "half")]
+half_float!(half::f16, half::f16::MIN_POSITIVE);
+#[cfg(feature = "half")]
+half_float!(half::bf16, half::bf16::from_f32_const(1.0e-20));
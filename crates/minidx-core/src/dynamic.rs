@@ -0,0 +1,792 @@
+//! Networks whose architecture is chosen at runtime, such as from a config file.
+//!
+//! A [DynNetwork] is built from a [NetworkSpec], which can be deserialized
+//! from any format supported by serde:
+//!
+//! ```
+//! use minidx_core::dynamic::{DynNetwork, NetworkSpec};
+//! use minidx_core::Module;
+//!
+//! let spec: NetworkSpec = serde_json::from_str(r#"{
+//!     "inputs": 2,
+//!     "layers": [
+//!         {"kind": "linear", "outputs": 3},
+//!         {"kind": "relu"},
+//!         {"kind": "linear", "outputs": 1}
+//!     ]
+//! }"#).unwrap();
+//!
+//! let network = DynNetwork::<f32>::new(&spec).unwrap();
+//! assert_eq!(network.forward(&vec![1.0, 2.0]).unwrap().len(), 1);
+//! ```
+//!
+//! Layers compute the same math as their counterparts in [layers](crate::layers),
+//! and their parameters are saved under the same paths as the equivalent tuple of
+//! layers, so parameters can be moved between static and dynamic networks.
+use crate::gradients::GradClass;
+use crate::layers::{
+    bias_rand_params, dense_forward, dense_input_grads, dense_rand_params, dense_weight_grads,
+};
+use crate::layers::{Activation, Softmax};
+use crate::matmul::MatMulImpl;
+use crate::optimizers::GradApplyer;
+use crate::summary::LayerSummary;
+use crate::{kernels, Error, Float, Gradients, LoadSaveError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Describes a layer of a [DynNetwork].
+///
+/// The number of inputs of each layer is the number of outputs of the layer before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LayerSpec {
+    /// Fully-connected layer without bias, like [Dense](crate::layers::Dense).
+    Dense {
+        outputs: usize,
+    },
+    /// Fully-connected layer followed by a bias, saved as a pair of layers
+    /// like `(Dense, Bias1d)`.
+    Linear {
+        outputs: usize,
+    },
+    /// A learnable bias on each element, like [Bias1d](crate::layers::Bias1d).
+    Bias,
+    Relu,
+    Sigmoid,
+    #[serde(rename = "silu")]
+    SiLU,
+    Tanh,
+    LeakyRelu {
+        slope: f32,
+    },
+    Softplus,
+    Sine,
+    Cosine,
+    /// A softmax, like [Softmax](crate::layers::Softmax).
+    Softmax {
+        #[serde(default = "one")]
+        temperature: f32,
+    },
+    /// A sequence of layers, saved like a tuple of layers.
+    Sequential {
+        layers: Vec<LayerSpec>,
+    },
+}
+
+/// Describes the architecture of a [DynNetwork].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSpec {
+    /// The number of inputs to the network.
+    pub inputs: usize,
+    /// The layers of the network, in the order they are evaluated.
+    pub layers: Vec<LayerSpec>,
+}
+
+/// An error building a [DynNetwork] from a [NetworkSpec].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    /// The path of the offending layer, matching its parameter path.
+    pub path: String,
+    pub err: String,
+}
+
+/// The parameters (or gradients) of a layer of a [DynNetwork], sized at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynParams<E: Float> {
+    pub values: Vec<E>,
+    pub class: GradClass,
+}
+
+impl<E: Float> DynParams<E> {
+    fn zeros(len: usize, class: GradClass) -> Self {
+        Self {
+            values: vec![E::default(); len],
+            class,
+        }
+    }
+}
+
+impl<E: Float> Gradients for DynParams<E> {
+    type Concrete = E;
+
+    fn grad_iter(&self) -> impl Iterator<Item = &Self::Concrete> {
+        self.values.iter()
+    }
+
+    fn grad_iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Concrete> {
+        self.values.iter_mut()
+    }
+
+    fn grad_iter_mut_with_class(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut Self::Concrete, GradClass)> {
+        let class = self.class.clone();
+        self.values.iter_mut().map(move |g| (g, class.clone()))
+    }
+
+    fn into_grads(self) -> impl Iterator<Item = Self::Concrete> {
+        self.values.into_iter()
+    }
+
+    fn empty() -> Self {
+        Self::zeros(0, GradClass::Other)
+    }
+
+    fn zeros_like(&self) -> Self {
+        Self::zeros(self.values.len(), self.class.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum DynLayer<E: Float + MatMulImpl> {
+    Dense {
+        inputs: usize,
+        weights: DynParams<E>,
+    },
+    Bias(DynParams<E>),
+    Activation(Activation<E>),
+    Softmax(Softmax),
+    Sequential(Vec<DynLayer<E>>),
+}
+
+impl<E: Float + MatMulImpl> DynLayer<E> {
+    /// Builds the layer described by the spec, returning it and its number of outputs.
+    fn new(spec: &LayerSpec, inputs: usize, path: String) -> Result<(Self, usize), SpecError> {
+        use LayerSpec::*;
+        if inputs == 0 {
+            return Err(SpecError {
+                path,
+                err: "Layer has no inputs".into(),
+            });
+        }
+
+        let activation = |a| Ok((DynLayer::Activation(a), inputs));
+        match spec {
+            Dense { outputs: 0 } | Linear { outputs: 0 } => Err(SpecError {
+                path,
+                err: "Layer has no outputs".into(),
+            }),
+            Dense { outputs } => Ok((
+                DynLayer::Dense {
+                    inputs,
+                    weights: DynParams::zeros(inputs * outputs, GradClass::Connective),
+                },
+                *outputs,
+            )),
+            Linear { outputs } => {
+                let layers = [Dense { outputs: *outputs }, Bias];
+                Self::new(
+                    &Sequential {
+                        layers: layers.into(),
+                    },
+                    inputs,
+                    path,
+                )
+            }
+            Bias => Ok((
+                DynLayer::Bias(DynParams::zeros(inputs, GradClass::Bias)),
+                inputs,
+            )),
+            Relu => activation(Activation::Relu),
+            Sigmoid => activation(Activation::Sigmoid),
+            SiLU => activation(Activation::SiLU),
+            Tanh => activation(Activation::Tanh),
+            LeakyRelu { slope } => activation(Activation::LeakyRelu(E::from_f32(*slope).unwrap())),
+            Softplus => activation(Activation::Softplus),
+            Sine => activation(Activation::Sine),
+            Cosine => activation(Activation::Cosine),
+            Softmax { temperature } => Ok((
+                DynLayer::Softmax(crate::layers::Softmax(*temperature)),
+                inputs,
+            )),
+            Sequential { layers } => {
+                let mut width = inputs;
+                let mut built = Vec::with_capacity(layers.len());
+                for (i, l) in layers.iter().enumerate() {
+                    let (layer, outputs) = Self::new(l, width, format!("{}.{}", path, i))?;
+                    built.push(layer);
+                    width = outputs;
+                }
+                Ok((DynLayer::Sequential(built), width))
+            }
+        }
+    }
+
+    /// Computes the output of the layer, pushing the input of each leaf layer
+    /// onto the trace if one is given.
+    fn forward(&self, x: Vec<E>, trace: &mut Option<&mut Vec<Vec<E>>>) -> Vec<E> {
+        if let DynLayer::Sequential(layers) = self {
+            return layers.iter().fold(x, |x, l| l.forward(x, trace));
+        }
+
+        let out = match self {
+            DynLayer::Dense { inputs, weights } => {
+                let mut out = vec![E::default(); weights.values.len() / inputs];
+                dense_forward(&weights.values, &x, &mut out);
+                out
+            }
+            DynLayer::Bias(bias) => {
+                let mut out = bias.values.clone();
+                kernels::add_assign(&mut out, &x);
+                out
+            }
+            DynLayer::Activation(a) => {
+                let mut out = vec![E::default(); x.len()];
+                a.forward_into(&x, &mut out);
+                out
+            }
+            DynLayer::Softmax(s) => {
+                let mut out = vec![E::default(); x.len()];
+                s.forward_into(&x, &mut out);
+                out
+            }
+            DynLayer::Sequential(_) => unreachable!(),
+        };
+        if let Some(trace) = trace {
+            trace.push(x);
+        }
+        out
+    }
+
+    /// Computes the gradients of the input of the layer, taking the inputs of
+    /// each leaf layer from the back of the trace, and pushing the gradients of
+    /// any parameters in reverse order.
+    fn backprop(
+        &self,
+        trace: &mut std::slice::Iter<'_, Vec<E>>,
+        grads_wrt_output: Vec<E>,
+        grads: &mut Vec<DynParams<E>>,
+    ) -> Vec<E> {
+        if let DynLayer::Sequential(layers) = self {
+            return layers
+                .iter()
+                .rev()
+                .fold(grads_wrt_output, |g, l| l.backprop(trace, g, grads));
+        }
+
+        let x = trace.next_back().expect("trace is missing layer inputs");
+        match self {
+            DynLayer::Dense { weights, .. } => {
+                let mut w_grads = weights.zeros_like();
+                dense_weight_grads(x, &grads_wrt_output, &mut w_grads.values);
+                grads.push(w_grads);
+
+                let mut out = vec![E::default(); x.len()];
+                dense_input_grads(&weights.values, &grads_wrt_output, &mut out);
+                out
+            }
+            DynLayer::Bias(_) => {
+                grads.push(DynParams {
+                    values: grads_wrt_output.clone(),
+                    class: GradClass::Bias,
+                });
+                grads_wrt_output
+            }
+            DynLayer::Activation(a) => {
+                let mut out = vec![E::default(); x.len()];
+                a.backward_into(x, &mut out);
+                kernels::mul_assign(&mut out, &grads_wrt_output);
+                out
+            }
+            DynLayer::Softmax(s) => {
+                let mut output = vec![E::default(); x.len()];
+                s.forward_into(x, &mut output);
+                let mut out = vec![E::default(); x.len()];
+                s.backprop_into(&output, &grads_wrt_output, &mut out);
+                out
+            }
+            DynLayer::Sequential(_) => unreachable!(),
+        }
+    }
+
+    /// Calls the given function on the parameters of each leaf layer, in order.
+    fn for_params_mut<R>(
+        &mut self,
+        f: &mut impl FnMut(&mut DynParams<E>) -> Result<(), R>,
+    ) -> Result<(), R> {
+        match self {
+            DynLayer::Dense { weights, .. } => f(weights),
+            DynLayer::Bias(bias) => f(bias),
+            DynLayer::Sequential(layers) => layers.iter_mut().try_for_each(|l| l.for_params_mut(f)),
+            DynLayer::Activation(_) | DynLayer::Softmax(_) => Ok(()),
+        }
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        match self {
+            DynLayer::Activation(a) => crate::ResetParams::preceding_init(a),
+            DynLayer::Sequential(layers) => layers
+                .iter()
+                .find(|l| l.preceding_init().is_some() || l.blocks_init())
+                .and_then(|l| l.preceding_init()),
+            _ => None,
+        }
+    }
+
+    fn blocks_init(&self) -> bool {
+        match self {
+            DynLayer::Dense { .. } => true,
+            DynLayer::Sequential(layers) => layers.iter().any(|l| l.blocks_init()),
+            _ => false,
+        }
+    }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+        scale: Option<f32>,
+    ) {
+        match self {
+            DynLayer::Dense { inputs, weights } => {
+                let outputs = weights.values.len() / *inputs;
+                match scale {
+                    Some(scale) => dense_rand_params(rng, *inputs, scale, &mut weights.values),
+                    None => init.unwrap_or_default().fill(
+                        rng,
+                        *inputs,
+                        outputs,
+                        weights.values.iter_mut(),
+                    ),
+                }
+            }
+            DynLayer::Bias(bias) => bias_rand_params(rng, scale.unwrap_or(1.0), &mut bias.values),
+            DynLayer::Sequential(layers) => {
+                // Each layer uses the scheme preferred by the nearest following
+                // layer with a preference, up to the next layer which blocks it,
+                // or otherwise the given scheme.
+                let mut hint = init;
+                let mut hints = vec![init; layers.len()];
+                for i in (0..layers.len()).rev() {
+                    hints[i] = hint;
+                    let passed = if layers[i].blocks_init() { init } else { hint };
+                    hint = layers[i].preceding_init().or(passed);
+                }
+                for (l, hint) in layers.iter_mut().zip(hints) {
+                    l.init_params_with(rng, hint, scale);
+                }
+            }
+            DynLayer::Activation(_) | DynLayer::Softmax(_) => {}
+        }
+    }
+
+    fn save(&self, path: String, dict: &mut HashMap<String, Vec<f64>>) {
+        match self {
+            DynLayer::Dense {
+                weights: params, ..
+            }
+            | DynLayer::Bias(params) => {
+                dict.insert(
+                    path,
+                    params.values.iter().map(|f| f.to_f64().unwrap()).collect(),
+                );
+            }
+            DynLayer::Sequential(layers) => {
+                for (i, l) in layers.iter().enumerate() {
+                    l.save(format!("{}.{}", path, i), dict);
+                }
+            }
+            DynLayer::Activation(_) | DynLayer::Softmax(_) => {}
+        }
+    }
+
+    fn load(
+        &mut self,
+        path: String,
+        dict: &HashMap<String, Vec<f64>>,
+    ) -> Result<(), LoadSaveError> {
+        match self {
+            DynLayer::Dense {
+                weights: params, ..
+            }
+            | DynLayer::Bias(params) => {
+                let values = dict.get(&path).ok_or(LoadSaveError {
+                    path: path.clone(),
+                    err: "Parameters missing".into(),
+                })?;
+                if values.len() != params.values.len() {
+                    return Err(LoadSaveError {
+                        path,
+                        err: format!(
+                            "Parameters have wrong size: got {}, want {}",
+                            values.len(),
+                            params.values.len()
+                        ),
+                    });
+                }
+                for (a, b) in params.values.iter_mut().zip(values) {
+                    *a = E::from_f64(*b).unwrap();
+                }
+                Ok(())
+            }
+            DynLayer::Sequential(layers) => {
+                for (i, l) in layers.iter_mut().enumerate() {
+                    l.load(format!("{}.{}", path, i), dict)?;
+                }
+                Ok(())
+            }
+            DynLayer::Activation(_) | DynLayer::Softmax(_) => Ok(()),
+        }
+    }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        match self {
+            DynLayer::Dense { .. } | DynLayer::Bias(_) => paths.push(path),
+            DynLayer::Sequential(layers) => {
+                for (i, l) in layers.iter().enumerate() {
+                    l.trained_paths(format!("{}.{}", path, i), paths);
+                }
+            }
+            DynLayer::Activation(_) | DynLayer::Softmax(_) => {}
+        }
+    }
+
+    fn describe(&self, inputs: usize) -> LayerSummary {
+        match self {
+            DynLayer::Dense { weights, .. } => {
+                let n = weights.values.len();
+                LayerSummary::leaf::<E>("dense", inputs, n / inputs, n)
+            }
+            DynLayer::Bias(bias) => {
+                LayerSummary::leaf::<E>("bias1d", inputs, inputs, bias.values.len())
+            }
+            DynLayer::Activation(_) => LayerSummary::leaf::<E>("activation", inputs, inputs, 0),
+            DynLayer::Softmax(_) => LayerSummary::leaf::<E>("softmax", inputs, inputs, 0),
+            DynLayer::Sequential(layers) => {
+                let mut width = inputs;
+                let children = layers
+                    .iter()
+                    .map(|l| {
+                        let s = l.describe(width);
+                        width = s.output;
+                        s
+                    })
+                    .collect();
+                LayerSummary::composite("sequential", children)
+            }
+        }
+    }
+}
+
+/// A network built at runtime from a [NetworkSpec], with parameters stored on the heap.
+///
+/// Inputs and outputs are vectors, and a [DynNetwork] can be trained, saved and
+/// summarized just like networks composed from [layers](crate::layers).
+#[derive(Clone, Debug)]
+pub struct DynNetwork<E: Float + MatMulImpl> {
+    spec: NetworkSpec,
+    outputs: usize,
+    layers: DynLayer<E>,
+}
+
+impl<E: Float + MatMulImpl> DynNetwork<E> {
+    /// Builds a network with the given architecture. Parameters are initialized to zero.
+    pub fn new(spec: &NetworkSpec) -> Result<Self, SpecError> {
+        let layers = LayerSpec::Sequential {
+            layers: spec.layers.clone(),
+        };
+        let (layers, outputs) = DynLayer::new(&layers, spec.inputs, "".into())?;
+        Ok(Self {
+            spec: spec.clone(),
+            outputs,
+            layers,
+        })
+    }
+
+    /// Returns the spec the network was built from.
+    pub fn spec(&self) -> &NetworkSpec {
+        &self.spec
+    }
+
+    /// Returns the number of inputs to the network.
+    pub fn inputs(&self) -> usize {
+        self.spec.inputs
+    }
+
+    /// Returns the number of outputs of the network.
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::Module<Vec<E>> for DynNetwork<E> {
+    type Output = Vec<E>;
+
+    /// Computes the output of the network, or errors if the input has the wrong size.
+    fn forward(&self, x: &Vec<E>) -> Result<Self::Output, Error> {
+        if x.len() != self.spec.inputs {
+            return Err(());
+        }
+        Ok(self.layers.forward(x.clone(), &mut None))
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::TracedModule<Vec<E>> for DynNetwork<E> {
+    /// The inputs of each layer with no sub-layers, in the order they were evaluated.
+    type Trace = Vec<Vec<E>>;
+
+    fn traced_forward(&self, x: Vec<E>) -> Result<(Self::Output, Self::Trace), Error> {
+        if x.len() != self.spec.inputs {
+            return Err(());
+        }
+        let mut trace = Vec::new();
+        let out = self.layers.forward(x, &mut Some(&mut trace));
+        Ok((out, trace))
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::BackpropModule<Vec<E>> for DynNetwork<E> {
+    /// The gradients of the parameters of each layer, in the order they are saved.
+    type SelfGrads = Vec<DynParams<E>>;
+
+    fn backprop(
+        &self,
+        trace: &Self::Trace,
+        grads_wrt_output: Self::Output,
+    ) -> (Vec<E>, Self::SelfGrads) {
+        let mut grads = Vec::new();
+        let input_grads = self
+            .layers
+            .backprop(&mut trace.iter(), grads_wrt_output, &mut grads);
+        grads.reverse();
+        (input_grads, grads)
+    }
+
+    fn update(
+        &mut self,
+        applyer: &mut impl GradApplyer,
+        updates: Self::SelfGrads,
+    ) -> Result<(), Error> {
+        let mut updates = updates.into_iter();
+        self.layers.for_params_mut(&mut |params| {
+            let u = updates.next().ok_or(())?;
+            applyer.apply(u, params)
+        })
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::ResetParams for DynNetwork<E> {
+    fn rand_params<RNG: rand::Rng>(&mut self, rng: &mut RNG, scale: f32) -> Result<(), Error> {
+        self.layers.init_params_with(rng, None, Some(scale));
+        Ok(())
+    }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), Error> {
+        self.layers.init_params_with(rng, init, None);
+        Ok(())
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        self.layers.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.layers.blocks_init()
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::LoadableModule for DynNetwork<E> {
+    fn save(
+        &self,
+        path: String,
+        dict: &mut HashMap<String, Vec<f64>>,
+    ) -> Result<(), LoadSaveError> {
+        self.layers.save(path, dict);
+        Ok(())
+    }
+
+    fn load(
+        &mut self,
+        path: String,
+        dict: &HashMap<String, Vec<f64>>,
+    ) -> Result<(), LoadSaveError> {
+        self.layers.load(path, dict)
+    }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        self.layers.trained_paths(path, paths)
+    }
+}
+
+impl<E: Float + MatMulImpl> crate::Summarize<Vec<E>> for DynNetwork<E> {
+    fn describe(&self) -> LayerSummary {
+        self.layers.describe(self.spec.inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::{Bias1d, Dense};
+    use crate::loss::DiffLoss;
+    use crate::optimizers::TrainParams;
+    use crate::{BackpropModule, LoadableModule, Module, ResetParams, Summarize, TracedModule};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    fn spec() -> NetworkSpec {
+        toml::from_str(
+            r#"
+            inputs = 2
+
+            [[layers]]
+            kind = "linear"
+            outputs = 3
+
+            [[layers]]
+            kind = "leaky_relu"
+            slope = 0.1
+
+            [[layers]]
+            kind = "dense"
+            outputs = 2
+
+            [[layers]]
+            kind = "softmax"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spec() {
+        let spec = spec();
+        assert_eq!(spec.layers[0], LayerSpec::Linear { outputs: 3 });
+        assert_eq!(spec.layers[3], LayerSpec::Softmax { temperature: 1.0 });
+        let json = serde_json::to_string(&spec).unwrap();
+        assert_eq!(serde_json::from_str::<NetworkSpec>(&json).unwrap(), spec);
+
+        let network = DynNetwork::<f32>::new(&spec).unwrap();
+        assert_eq!((network.inputs(), network.outputs()), (2, 2));
+        assert_eq!(network.summary().network.params, 6 + 3 + 6);
+        assert!(network.forward(&vec![1.0]).is_err());
+
+        let bad = NetworkSpec {
+            inputs: 2,
+            layers: vec![LayerSpec::Sequential {
+                layers: vec![LayerSpec::Relu, LayerSpec::Dense { outputs: 0 }],
+            }],
+        };
+        assert_eq!(DynNetwork::<f32>::new(&bad).unwrap_err().path, ".0.1");
+    }
+
+    #[test]
+    fn test_matches_static() {
+        let mut static_network = (
+            (Dense::<f32, 2, 3>::default(), Bias1d::<f32, 3>::default()),
+            Activation::LeakyRelu(0.1),
+            Dense::<f32, 3, 2>::default(),
+            Softmax::default(),
+        );
+        let mut rng = SmallRng::seed_from_u64(5);
+        static_network.rand_params(&mut rng, 1.0).unwrap();
+
+        let mut params = HashMap::new();
+        static_network.save("".into(), &mut params).unwrap();
+        let mut network = DynNetwork::<f32>::new(&spec()).unwrap();
+        network.load("".into(), &params).unwrap();
+
+        let x = [0.5, -1.5];
+        let want = static_network.forward(&x).unwrap();
+        assert_eq!(network.forward(&x.to_vec()).unwrap(), want.to_vec());
+
+        // Backprop produces the same gradients, in the same order.
+        let target = [1.0, 0.0];
+        let (out, trace) = static_network.traced_forward(x).unwrap();
+        let (want_inputs, want_grads) =
+            static_network.backprop(&trace, out.mse_input_grads(&target));
+        let (out, trace) = network.traced_forward(x.to_vec()).unwrap();
+        let (inputs, grads) = network.backprop(&trace, out.mse_input_grads(&target.to_vec()));
+        assert_eq!(inputs, want_inputs.to_vec());
+        assert_eq!(
+            grads.grad_iter().collect::<Vec<_>>(),
+            want_grads.grad_iter().collect::<Vec<_>>()
+        );
+
+        // Parameters saved from the dynamic network load into the static one.
+        let mut saved = HashMap::new();
+        network.save("".into(), &mut saved).unwrap();
+        assert_eq!(saved, params);
+    }
+
+    #[test]
+    fn test_init_matches_static() {
+        let mut static_network = (
+            Dense::<f32, 2, 3>::default(),
+            Bias1d::<f32, 3>::default(),
+            Dense::<f32, 3, 3>::default(),
+            Activation::<f32>::Relu,
+        );
+        static_network
+            .init_params(&mut SmallRng::seed_from_u64(3))
+            .unwrap();
+        let spec = NetworkSpec {
+            inputs: 2,
+            layers: vec![
+                LayerSpec::Dense { outputs: 3 },
+                LayerSpec::Bias,
+                LayerSpec::Dense { outputs: 3 },
+                LayerSpec::Relu,
+            ],
+        };
+        let mut network = DynNetwork::<f32>::new(&spec).unwrap();
+        network
+            .init_params(&mut SmallRng::seed_from_u64(3))
+            .unwrap();
+
+        let mut want = HashMap::new();
+        static_network.save("".into(), &mut want).unwrap();
+        let mut got = HashMap::new();
+        network.save("".into(), &mut got).unwrap();
+        assert_eq!(got, want);
+
+        // As does random initialization.
+        static_network
+            .rand_params(&mut SmallRng::seed_from_u64(4), 0.5)
+            .unwrap();
+        network
+            .rand_params(&mut SmallRng::seed_from_u64(4), 0.5)
+            .unwrap();
+        static_network.save("".into(), &mut want).unwrap();
+        network.save("".into(), &mut got).unwrap();
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn test_train() {
+        let spec = NetworkSpec {
+            inputs: 1,
+            layers: vec![
+                LayerSpec::Linear { outputs: 8 },
+                LayerSpec::Tanh,
+                LayerSpec::Linear { outputs: 1 },
+            ],
+        };
+        let mut network = DynNetwork::<f32>::new(&spec).unwrap();
+        let mut rng = SmallRng::seed_from_u64(1);
+        network.init_params(&mut rng).unwrap();
+
+        let mut updater = network.new_rmsprop_with_momentum(TrainParams::with_lr(0.02), 0.5, 0.9);
+        let mut x = 0.0f32;
+        let mut source = || {
+            x = (x + 0.37) % 2.0;
+            (vec![x - 1.0], vec![(x - 1.0) * 0.5])
+        };
+        let loss = |got: &Vec<f32>, want: &Vec<f32>| (got.mse(want), got.mse_input_grads(want));
+
+        let first = crate::train_batch(&mut updater, &mut network, loss, &mut source, 16);
+        let mut last = first;
+        for _ in 0..200 {
+            last = crate::train_batch(&mut updater, &mut network, loss, &mut source, 16);
+        }
+        assert!(last < first / 10.0, "first={}, last={}", first, last);
+    }
+}
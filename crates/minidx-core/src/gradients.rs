@@ -1,8 +1,11 @@
 use crate::{Dtype, Unit};
 use num_traits::FromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// What kind of parameter the gradient represents.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum GradClass {
     /// A gradient relating to the weight of a connection between two neurons.
     Connective,
@@ -63,6 +66,11 @@ pub trait Gradients: Clone + std::fmt::Debug {
     }
 
     fn add(&mut self, other: Self) {
+        // Gradients sized at runtime are empty until the first are added.
+        if self.grad_iter().next().is_none() {
+            *self = other;
+            return;
+        }
         self.grad_iter_mut()
             .zip(other.into_grads())
             .for_each(|(g, o)| {
@@ -104,8 +112,45 @@ pub trait Gradients: Clone + std::fmt::Debug {
         Some(dot_product / (magnitude_a * magnitude_b))
     }
 
+    /// Returns the L2 norm of all gradients.
+    fn l2_norm(&self) -> f32 {
+        use num_traits::ToPrimitive;
+        self.grad_iter()
+            .map(|x| (*x * *x).to_f32().unwrap())
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// Scales all gradients so their combined L2 norm is at most `max_norm`,
+    /// preserving the direction of the update.
+    ///
+    /// Returns the L2 norm before clipping.
+    fn clip_norm(&mut self, max_norm: f32) -> f32 {
+        let norm = self.l2_norm();
+        if norm > max_norm && norm.is_finite() {
+            self.scale(max_norm / norm);
+        }
+        norm
+    }
+
+    /// Like [Gradients::clip_norm], but clips the gradients of each parameter
+    /// tensor (such as the weights or bias of a layer) independently.
+    ///
+    /// Returns the L2 norm of all gradients before clipping.
+    fn clip_norm_per_layer(&mut self, max_norm: f32) -> f32 {
+        self.clip_norm(max_norm)
+    }
+
     /// Returns an empty gradient object
     fn empty() -> Self;
+
+    /// Returns zeroed gradients of the same size as these ones.
+    ///
+    /// Gradients sized at runtime have no elements when constructed by [Gradients::empty],
+    /// so optimizers use this to size their state from the first update.
+    fn zeros_like(&self) -> Self {
+        Self::empty()
+    }
 }
 
 impl Gradients for () {
@@ -176,6 +221,35 @@ impl<E: Dtype, const L1: usize, const L2: usize> Gradients for [[E; L2]; L1] {
     }
 }
 
+/// Matrices of parameters are boxed by larger layers, so they are never built or
+/// moved on the stack.
+impl<E: Dtype, const L1: usize, const L2: usize> Gradients for Box<[[E; L2]; L1]> {
+    type Concrete = E;
+
+    fn grad_iter(&self) -> impl Iterator<Item = &Self::Concrete> {
+        (**self).grad_iter()
+    }
+
+    fn grad_iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Concrete> {
+        (**self).grad_iter_mut()
+    }
+
+    fn grad_iter_mut_with_class(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut Self::Concrete, GradClass)> {
+        (**self).grad_iter_mut_with_class()
+    }
+
+    fn into_grads(self) -> impl Iterator<Item = Self::Concrete> {
+        let rows: Box<[[E; L2]]> = self;
+        rows.into_vec().into_iter().flatten()
+    }
+
+    fn empty() -> Self {
+        crate::boxed_zeros()
+    }
+}
+
 macro_rules! tuple_impls {
     ([$($name:ident),+] [$($idx:tt),*], $last:ident, [$($rev_tail:ident),*]) => {
         impl<
@@ -210,6 +284,12 @@ macro_rules! tuple_impls {
 		        $(.chain(self.$idx.into_grads()))*
 		    }
 
+            fn clip_norm_per_layer(&mut self, max_norm: f32) -> f32 {
+                let sq = self.0.clip_norm_per_layer(max_norm).powi(2);
+                $(let sq = sq + self.$idx.clip_norm_per_layer(max_norm).powi(2);)*
+                sq.sqrt()
+            }
+
             fn empty() -> Self {
                 (
                     $($name::empty(),)*
@@ -227,6 +307,45 @@ tuple_impls!([M1, M2, M3, M4, M5] [1, 2, 3, 4], M5, [M4, M3, M2, M1]);
 tuple_impls!([M1, M2, M3, M4, M5, M6] [1, 2, 3, 4, 5], M6, [M5, M4, M3, M2, M1]);
 tuple_impls!([M1, M2, M3, M4, M5, M6, M7] [1, 2, 3, 4, 5, 6], M7, [M6, M5, M4, M3, M2, M1]);
 
+/// Gradients of parameters sized at runtime, such as those of a
+/// [DynNetwork](crate::dynamic::DynNetwork).
+impl<G: Gradients> Gradients for Vec<G> {
+    type Concrete = G::Concrete;
+
+    fn grad_iter(&self) -> impl Iterator<Item = &Self::Concrete> {
+        self.iter().flat_map(|g| g.grad_iter())
+    }
+
+    fn grad_iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Concrete> {
+        self.iter_mut().flat_map(|g| g.grad_iter_mut())
+    }
+
+    fn grad_iter_mut_with_class(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut Self::Concrete, GradClass)> {
+        self.iter_mut().flat_map(|g| g.grad_iter_mut_with_class())
+    }
+
+    fn into_grads(self) -> impl Iterator<Item = Self::Concrete> {
+        self.into_iter().flat_map(|g| g.into_grads())
+    }
+
+    fn clip_norm_per_layer(&mut self, max_norm: f32) -> f32 {
+        self.iter_mut()
+            .map(|g| g.clip_norm_per_layer(max_norm).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    fn empty() -> Self {
+        Vec::new()
+    }
+
+    fn zeros_like(&self) -> Self {
+        self.iter().map(|g| g.zeros_like()).collect()
+    }
+}
+
 /// Marker for gradients which represent bias parameters.
 #[derive(Clone, Debug)]
 pub struct ClassBias;
@@ -308,6 +427,10 @@ impl<G: Gradients, M: ClassMarker> Gradients for ClassWrapper<G, M> {
         self.g.into_grads()
     }
 
+    fn clip_norm_per_layer(&mut self, max_norm: f32) -> f32 {
+        self.g.clip_norm_per_layer(max_norm)
+    }
+
     fn empty() -> Self {
         Self {
             g: G::empty(),
@@ -369,6 +492,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_clip_norm() {
+        let mut grads = ([3.0f32], [4.0f32, 0.0]);
+        assert_eq!(grads.clip_norm(10.0), 5.0);
+        assert_eq!(grads, ([3.0], [4.0, 0.0]));
+        assert_eq!(grads.clip_norm(1.0), 5.0);
+        assert!((grads.0[0] - 0.6).abs() < 1e-6);
+        assert!((grads.1[0] - 0.8).abs() < 1e-6);
+
+        let mut grads = ([3.0f32], [4.0f32, 0.0]);
+        assert_eq!(grads.clip_norm_per_layer(3.5), 5.0);
+        assert_eq!(grads.0, [3.0]);
+        assert!((grads.1[0] - 3.5).abs() < 1e-6);
+    }
+
     #[test]
     fn test_grad_merge() {
         let mut grads = [1.0f32; 2];
@@ -0,0 +1,417 @@
+//! Detection of, and recovery from, non-finite losses and gradients during training.
+//!
+//! Wrap an optimizer in a [NonFiniteGuard] and pass the guard to the training
+//! functions in its place:
+//!
+//! ```
+//! # use minidx_core::{layers::Dense, train_batch, BackpropModule};
+//! # use minidx_core::guard::{NonFiniteGuard, NonFinitePolicy};
+//! # use minidx_core::optimizers::TrainParams;
+//! # use minidx_core::loss::DiffLoss;
+//! let mut network = Dense::<f32, 2, 1>::default();
+//! let updater = network.new_momentum(TrainParams::with_lr(1.0e-3), 0.9);
+//! let mut guard = NonFiniteGuard::new(updater, NonFinitePolicy::Rollback);
+//!
+//! train_batch(
+//!     &mut guard,
+//!     &mut network,
+//!     |got, want| (got.mse(want), got.mse_input_grads(want)),
+//!     &mut || ([f32::NAN, 1.0], [0.5]),
+//!     4,
+//! );
+//! assert!(guard.check().is_err());
+//! ```
+use crate::optimizers::{GradAdjuster, GradApplyer, ParamOverride};
+use crate::Gradients;
+use num_traits::{FromPrimitive, ToPrimitive};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What a [NonFiniteGuard] does when a training step produces a non-finite loss or gradient.
+///
+/// In all cases the update from the bad step is discarded. Non-finite gradients
+/// never reach the wrapped optimizer, but if the optimizer itself overflows on
+/// finite gradients, only [NonFinitePolicy::Rollback] restores its state.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NonFinitePolicy {
+    /// Leave the parameters unchanged.
+    Skip,
+    /// Restore the parameters and the state of the optimizer from the end of the
+    /// last good step.
+    ///
+    /// This costs a copy of all parameters and the optimizer state every step.
+    Rollback,
+    /// Multiply the learning rate of all future updates by the given factor.
+    CutLr(f32),
+}
+
+/// Describes a training step which produced a non-finite loss or gradient.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NonFinite {
+    /// The 0-indexed count of steps seen by the guard.
+    pub step: usize,
+    /// The loss of the step.
+    pub loss: f32,
+    /// The number of gradients or updates which were NaN or infinite.
+    pub non_finite_grads: usize,
+    /// The number of bad steps in a row, including this one.
+    pub consecutive: usize,
+    /// The action which was taken.
+    pub policy: NonFinitePolicy,
+}
+
+impl std::fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "non-finite training step {} (loss={}, {} non-finite gradients, {} in a row): {:?}",
+            self.step, self.loss, self.non_finite_grads, self.consecutive, self.policy
+        )
+    }
+}
+
+impl std::error::Error for NonFinite {}
+
+/// Wraps an optimizer, discarding any update which has a non-finite loss or gradient.
+///
+/// The loss and gradients are checked before they reach the wrapped optimizer, so
+/// its state (such as momentum) is not corrupted by bad gradients. See
+/// [NonFinitePolicy] for how the optimizer overflowing is handled.
+#[derive(Clone, Debug)]
+pub struct NonFiniteGuard<GA> {
+    inner: GA,
+    policy: NonFinitePolicy,
+
+    step: usize,
+    bad_step: bool,
+    consecutive: usize,
+    total: usize,
+    lr_scale: f32,
+
+    // Parameters at the end of the last good step, one entry per call to apply().
+    good: Vec<Vec<f64>>,
+    tensor: usize,
+
+    pending: Option<NonFinite>,
+}
+
+impl<GA> NonFiniteGuard<GA> {
+    /// Wraps the given optimizer, recovering from bad steps using the given policy.
+    pub fn new(inner: GA, policy: NonFinitePolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            step: 0,
+            bad_step: false,
+            consecutive: 0,
+            total: 0,
+            lr_scale: 1.0,
+            good: Vec::new(),
+            tensor: 0,
+            pending: None,
+        }
+    }
+
+    /// Returns the wrapped optimizer.
+    pub fn inner(&self) -> &GA {
+        &self.inner
+    }
+
+    /// Returns the wrapped optimizer, mutably.
+    pub fn inner_mut(&mut self) -> &mut GA {
+        &mut self.inner
+    }
+
+    /// Unwraps the guard, returning the optimizer.
+    pub fn into_inner(self) -> GA {
+        self.inner
+    }
+
+    /// Returns the total number of bad steps seen.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the factor applied to the learning rate by [NonFinitePolicy::CutLr].
+    pub fn lr_scale(&self) -> f32 {
+        self.lr_scale
+    }
+
+    /// Returns an error describing the most recent bad step, if any occurred
+    /// since the last call.
+    pub fn check(&mut self) -> Result<(), NonFinite> {
+        match self.pending.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn flag(&mut self, loss: f32, non_finite_grads: usize) {
+        self.bad_step = true;
+        self.consecutive += 1;
+        self.total += 1;
+        if let NonFinitePolicy::CutLr(factor) = self.policy {
+            self.lr_scale *= factor;
+        }
+
+        self.pending = Some(NonFinite {
+            step: self.step,
+            loss,
+            non_finite_grads,
+            consecutive: self.consecutive,
+            policy: self.policy.clone(),
+        });
+    }
+}
+
+fn count_non_finite<G: Gradients>(g: &G) -> usize {
+    g.grad_iter()
+        .filter(|v| !v.to_f64().unwrap().is_finite())
+        .count()
+}
+
+impl<G: Gradients, GA: GradAdjuster<G> + Clone> GradAdjuster<G> for NonFiniteGuard<GA> {
+    fn adjust(&mut self, gradient_updates: G, loss: f32) -> G {
+        self.tensor = 0;
+        self.bad_step = false;
+
+        let bad = count_non_finite(&gradient_updates);
+        if bad > 0 || !loss.is_finite() {
+            self.flag(loss, bad);
+            return gradient_updates;
+        }
+
+        // The optimizer itself can overflow on finite gradients, in which case its
+        // state from before the step is restored when rolling back.
+        let good = (self.policy == NonFinitePolicy::Rollback).then(|| self.inner.clone());
+        let mut updates = self.inner.adjust(gradient_updates, loss);
+        if self.lr_scale != 1.0 {
+            updates.scale(self.lr_scale);
+        }
+        let bad = count_non_finite(&updates);
+        if bad > 0 {
+            if let Some(good) = good {
+                self.inner = good;
+            }
+            self.flag(loss, bad);
+        }
+        updates
+    }
+}
+
+impl<GA: GradApplyer> GradApplyer for NonFiniteGuard<GA> {
+    fn apply<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+    ) -> Result<(), crate::Error> {
+        self.apply_with_override(gradient_updates, weights, &ParamOverride::default())
+    }
+
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        let idx = self.tensor;
+        self.tensor += 1;
+
+        if self.bad_step {
+            if let Some(good) = self.good.get(idx) {
+                weights
+                    .grad_iter_mut()
+                    .zip(good.iter())
+                    .for_each(|(w, g)| *w = G::Concrete::from_f64(*g).unwrap());
+            }
+            return Ok(());
+        }
+
+        self.inner
+            .apply_with_override(gradient_updates, weights, overrides)?;
+        if self.policy == NonFinitePolicy::Rollback {
+            if self.good.len() <= idx {
+                self.good.resize(idx + 1, Vec::new());
+            }
+            // The snapshot is overwritten in place, only growing on the first step.
+            let good = &mut self.good[idx];
+            let mut weights = weights.grad_iter().map(|w| w.to_f64().unwrap());
+            good.iter_mut().zip(&mut weights).for_each(|(g, w)| *g = w);
+            good.extend(weights);
+        }
+        Ok(())
+    }
+
+    fn advance_step(&mut self) {
+        if !self.bad_step {
+            self.inner.advance_step();
+            self.consecutive = 0;
+        }
+        self.bad_step = false;
+        self.step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Dense;
+    use crate::loss::DiffLoss;
+    use crate::optimizers::TrainParams;
+    use crate::{train_batch, LoadableModule};
+    use std::collections::HashMap;
+
+    fn network() -> Dense<f32, 2, 1> {
+        let mut network = Dense::<f32, 2, 1>::default();
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![0.5, -0.5]);
+        network.load("".into(), &store).unwrap();
+        network
+    }
+
+    fn weights(network: &Dense<f32, 2, 1>) -> Vec<f64> {
+        let mut store = HashMap::new();
+        network.save("".into(), &mut store).unwrap();
+        store.remove("").unwrap()
+    }
+
+    fn step(guard: &mut NonFiniteGuard<TrainParams>, network: &mut Dense<f32, 2, 1>, x: f32) {
+        train_batch(
+            guard,
+            network,
+            |got, want| (got.mse(want), got.mse_input_grads(want)),
+            &mut || ([x, 1.0], [0.25]),
+            2,
+        );
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut network = network();
+        let mut guard = NonFiniteGuard::new(TrainParams::with_lr(0.1), NonFinitePolicy::Skip);
+
+        step(&mut guard, &mut network, 1.0);
+        assert!(guard.check().is_ok());
+        let before = weights(&network);
+
+        step(&mut guard, &mut network, f32::NAN);
+        step(&mut guard, &mut network, f32::INFINITY);
+        assert_eq!(weights(&network), before);
+        let e = guard.check().unwrap_err();
+        assert_eq!(e.step, 2);
+        assert_eq!(e.consecutive, 2);
+        assert!(guard.check().is_ok());
+        assert_eq!(guard.total(), 2);
+
+        // Steps which were skipped don't count towards the optimizer.
+        assert_eq!(guard.inner().train_params().current_lr(), 0.1);
+        step(&mut guard, &mut network, 1.0);
+        assert_ne!(weights(&network), before);
+        assert!(guard.check().is_ok());
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut network = network();
+        let mut guard = NonFiniteGuard::new(TrainParams::with_lr(0.1), NonFinitePolicy::Rollback);
+
+        step(&mut guard, &mut network, 1.0);
+        let start = weights(&network);
+        step(&mut guard, &mut network, 1.0);
+        let good = weights(&network);
+        assert_ne!(good, start);
+
+        // Simulate the parameters going bad, which produces a non-finite loss.
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![f64::NAN, 0.0]);
+        network.load("".into(), &store).unwrap();
+        step(&mut guard, &mut network, 1.0);
+
+        // The last good step is kept.
+        assert!(guard.check().is_err());
+        assert_eq!(weights(&network), good);
+    }
+
+    #[test]
+    fn test_optimizer_overflow() {
+        let mut network = network();
+        let updater =
+            crate::BackpropModule::new_momentum(&network, TrainParams::with_lr(0.1), f32::MAX);
+        let mut guard = NonFiniteGuard::new(updater, NonFinitePolicy::Rollback);
+        let run = |guard: &mut NonFiniteGuard<_>, network: &mut Dense<f32, 2, 1>| {
+            train_batch(
+                guard,
+                network,
+                |got, want| (got.mse(want), got.mse_input_grads(want)),
+                &mut || ([10.0, 1.0], [0.25]),
+                2,
+            )
+        };
+
+        run(&mut guard, &mut network);
+        assert!(guard.check().is_ok());
+        let state = format!("{:?}", guard.inner());
+        let before = weights(&network);
+
+        // The gradients are finite, but the velocity overflows.
+        let loss = run(&mut guard, &mut network);
+        assert!(loss.is_finite());
+        assert!(guard.check().is_err());
+        assert_eq!(weights(&network), before);
+        assert_eq!(format!("{:?}", guard.inner()), state);
+    }
+
+    #[test]
+    fn test_rollback_momentum() {
+        let run = |guard: &mut NonFiniteGuard<_>, network: &mut Dense<f32, 2, 1>, x: f32| {
+            train_batch(
+                guard,
+                network,
+                |got, want| (got.mse(want), got.mse_input_grads(want)),
+                &mut || ([x, 1.0], [0.25]),
+                2,
+            )
+        };
+        let new_guard = |network: &Dense<f32, 2, 1>| {
+            let updater =
+                crate::BackpropModule::new_momentum(network, TrainParams::with_lr(0.1), 0.9);
+            NonFiniteGuard::new(updater, NonFinitePolicy::Rollback)
+        };
+
+        // Good steps either side of a bad one train the same as without the bad step.
+        let mut network = network();
+        let mut guard = new_guard(&network);
+        let mut want_network = network.clone();
+        let mut want_guard = new_guard(&network);
+        for x in [1.0, 2.0] {
+            run(&mut guard, &mut network, x);
+            run(&mut want_guard, &mut want_network, x);
+        }
+
+        run(&mut guard, &mut network, f32::NAN);
+        assert!(guard.check().is_err());
+        assert_eq!(weights(&network), weights(&want_network));
+        assert_eq!(
+            format!("{:?}", guard.inner()),
+            format!("{:?}", want_guard.inner())
+        );
+
+        run(&mut guard, &mut network, 3.0);
+        run(&mut want_guard, &mut want_network, 3.0);
+        assert!(guard.check().is_ok());
+        assert_eq!(weights(&network), weights(&want_network));
+    }
+
+    #[test]
+    fn test_cut_lr() {
+        let mut network = network();
+        let mut guard = NonFiniteGuard::new(TrainParams::with_lr(0.1), NonFinitePolicy::CutLr(0.5));
+
+        step(&mut guard, &mut network, f32::NAN);
+        step(&mut guard, &mut network, f32::NAN);
+        assert_eq!(guard.lr_scale(), 0.25);
+        assert!(guard.check().is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! Schemes for initializing the learnable parameters of a layer.
+//!
+//! Use [ResetParams::init_params](crate::ResetParams::init_params) to initialize a
+//! network, where each layer picks a scheme suited to the activation which follows it:
+//!
+//! ```
+//! # use minidx_core::layers::{Activation, Dense};
+//! # use minidx_core::ResetParams;
+//! # use rand::SeedableRng;
+//! let mut network = (
+//!     Dense::<f32, 4, 8>::default(),
+//!     Activation::<f32>::Relu, // Kaiming/He initialization for the dense layer before
+//!     Dense::<f32, 8, 2>::default(),
+//!     Activation::<f32>::Tanh, // Xavier/Glorot initialization for the dense layer before
+//! );
+//! let mut rng = rand::rngs::SmallRng::seed_from_u64(42);
+//! network.init_params(&mut rng).unwrap();
+//! ```
+use crate::Dtype;
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A scheme for initializing the weights of a layer, with a magnitude computed from
+/// the fan-in (the number of inputs to each output) and fan-out (the number of
+/// outputs from each input) of the layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Init {
+    /// Xavier/Glorot initialization, sampling from `U(-a, a)` where
+    /// `a = sqrt(6 / (fan_in + fan_out))`. Suited to tanh and sigmoid activations.
+    XavierUniform,
+    /// Xavier/Glorot initialization, sampling from a normal distribution with
+    /// variance `2 / (fan_in + fan_out)`. Suited to tanh and sigmoid activations.
+    #[default]
+    XavierNormal,
+    /// Kaiming/He initialization, sampling from `U(-a, a)` where `a = sqrt(6 / fan_in)`.
+    /// Suited to ReLU-like activations.
+    KaimingUniform,
+    /// Kaiming/He initialization, sampling from a normal distribution with
+    /// variance `2 / fan_in`. Suited to ReLU-like activations.
+    KaimingNormal,
+    /// LeCun initialization, sampling from `U(-a, a)` where `a = sqrt(3 / fan_in)`.
+    LeCunUniform,
+    /// LeCun initialization, sampling from a normal distribution with variance `1 / fan_in`.
+    /// Suited to layers with no activation, and to SELU.
+    LeCunNormal,
+    /// A random (semi-)orthogonal weight matrix: either its rows or its columns
+    /// are orthonormal, whichever there are fewer of.
+    Orthogonal,
+}
+
+impl Init {
+    /// Fills the given parameters using this scheme.
+    ///
+    /// The parameters are treated as a row-major matrix with `fan_in` columns, which
+    /// only matters for [Init::Orthogonal].
+    pub fn fill<'a, E: Dtype + 'a, RNG: Rng>(
+        &self,
+        rng: &mut RNG,
+        fan_in: usize,
+        fan_out: usize,
+        params: impl IntoIterator<Item = &'a mut E>,
+    ) {
+        let params: Vec<&mut E> = params.into_iter().collect();
+        let (fan_in, fan_out) = (fan_in.max(1) as f64, fan_out.max(1) as f64);
+
+        let values: Vec<f64> = match self {
+            Init::Orthogonal => {
+                let cols = fan_in as usize;
+                orthogonal(rng, params.len().div_ceil(cols), cols)
+            }
+            Init::XavierUniform | Init::KaimingUniform | Init::LeCunUniform => {
+                let a = match self {
+                    Init::XavierUniform => (6.0 / (fan_in + fan_out)).sqrt(),
+                    Init::KaimingUniform => (6.0 / fan_in).sqrt(),
+                    _ => (3.0 / fan_in).sqrt(),
+                };
+                (0..params.len())
+                    .map(|_| rng.random_range(-a..=a))
+                    .collect()
+            }
+            Init::XavierNormal | Init::KaimingNormal | Init::LeCunNormal => {
+                let stddev = match self {
+                    Init::XavierNormal => (2.0 / (fan_in + fan_out)).sqrt(),
+                    Init::KaimingNormal => (2.0 / fan_in).sqrt(),
+                    _ => (1.0 / fan_in).sqrt(),
+                };
+                let normal = rand_distr::Normal::new(0.0, stddev).unwrap();
+                (0..params.len()).map(|_| rng.sample(normal)).collect()
+            }
+        };
+
+        params
+            .into_iter()
+            .zip(values)
+            .for_each(|(p, v)| *p = E::from_f64(v).unwrap());
+    }
+}
+
+/// Returns a random row-major `rows x cols` matrix with orthonormal rows or columns,
+/// using Gram-Schmidt orthogonalization of a gaussian matrix.
+fn orthogonal<RNG: Rng>(rng: &mut RNG, rows: usize, cols: usize) -> Vec<f64> {
+    let normal = rand_distr::Normal::new(0.0, 1.0).unwrap();
+    // Orthonormalize whichever of the rows or columns are fewer (and longer).
+    let (n, len) = (rows.min(cols), rows.max(cols));
+
+    let mut vecs: Vec<Vec<f64>> = Vec::with_capacity(n);
+    while vecs.len() < n {
+        let mut v: Vec<f64> = (0..len).map(|_| rng.sample(normal)).collect();
+        for u in vecs.iter() {
+            let d: f64 = v.iter().zip(u.iter()).map(|(a, b)| a * b).sum();
+            v.iter_mut().zip(u.iter()).for_each(|(a, b)| *a -= d * b);
+        }
+        let norm = v.iter().map(|a| a * a).sum::<f64>().sqrt();
+        // Resample in the (vanishingly unlikely) case of a degenerate vector.
+        if norm > 1e-6 {
+            v.iter_mut().for_each(|a| *a /= norm);
+            vecs.push(v);
+        }
+    }
+
+    let mut out = vec![0.0; rows * cols];
+    for (i, v) in vecs.iter().enumerate() {
+        for (j, x) in v.iter().enumerate() {
+            if rows <= cols {
+                out[i * cols + j] = *x;
+            } else {
+                out[j * cols + i] = *x;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_fill_variance() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+        let mut params = vec![0.0f32; 20000];
+        for (init, want) in [
+            (Init::XavierNormal, 2.0 / 150.0),
+            (Init::XavierUniform, 2.0 / 150.0),
+            (Init::KaimingNormal, 2.0 / 100.0),
+            (Init::KaimingUniform, 2.0 / 100.0),
+            (Init::LeCunNormal, 1.0 / 100.0),
+            (Init::LeCunUniform, 1.0 / 100.0),
+        ] {
+            init.fill(&mut rng, 100, 50, params.iter_mut());
+            let var = params.iter().map(|p| (p * p) as f64).sum::<f64>() / params.len() as f64;
+            assert!(
+                (var - want).abs() / want < 0.05,
+                "{:?}: {} vs {}",
+                init,
+                var,
+                want
+            );
+        }
+    }
+
+    #[test]
+    fn test_orthogonal() {
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+        for (rows, cols) in [(3, 5), (5, 3), (4, 4)] {
+            let m = orthogonal(&mut rng, rows, cols);
+            // Either W * W^T = I (orthonormal rows) or W^T * W = I (orthonormal columns).
+            let (n, len) = (rows.min(cols), rows.max(cols));
+            let at = |v: usize, k: usize| {
+                if rows <= cols {
+                    m[v * cols + k]
+                } else {
+                    m[k * cols + v]
+                }
+            };
+            for a in 0..n {
+                for b in 0..n {
+                    let d: f64 = (0..len).map(|k| at(a, k) * at(b, k)).sum();
+                    let want = if a == b { 1.0 } else { 0.0 };
+                    assert!(
+                        (d - want).abs() < 1e-9,
+                        "{}x{}: {} vs {}",
+                        rows,
+                        cols,
+                        d,
+                        want
+                    );
+                }
+            }
+        }
+    }
+}
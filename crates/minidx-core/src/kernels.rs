@@ -0,0 +1,218 @@
+//! Elementwise operations and reductions over slices, used by layers and loss functions.
+//!
+//! These are written as portable code which the compiler can auto-vectorize on
+//! stable Rust: reductions keep [LANES] independent accumulators (floating-point
+//! addition is not associative, so a single accumulator cannot be vectorized),
+//! and loops avoid branching on anything other than the element.
+use crate::{Dtype, Float};
+
+/// The number of independent accumulators used by reductions.
+///
+/// Eight lanes covers a 256-bit register of f32, and two 128-bit registers.
+pub const LANES: usize = 8;
+
+/// Reduces the slice using `f` into [LANES] accumulators, which are then combined.
+#[inline(always)]
+fn reduce<E: Dtype>(x: &[E], init: E, f: impl Fn(E, E) -> E) -> E {
+    let mut acc = [init; LANES];
+    let chunks = x.chunks_exact(LANES);
+    let rem = chunks.remainder();
+    for c in chunks {
+        for (a, v) in acc.iter_mut().zip(c) {
+            *a = f(*a, *v);
+        }
+    }
+    acc.into_iter().chain(rem.iter().copied()).fold(init, f)
+}
+
+/// Returns the sum of all values.
+#[inline]
+pub fn sum<E: Dtype>(x: &[E]) -> E {
+    reduce(x, E::default(), |a, v| a + v)
+}
+
+/// Returns the dot product of the two slices, which must be the same length.
+#[inline]
+pub fn dot<E: Dtype>(a: &[E], b: &[E]) -> E {
+    debug_assert_eq!(a.len(), b.len());
+    let mut acc = [E::default(); LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let rem = ca.remainder().iter().zip(cb.remainder());
+    for (ca, cb) in ca.zip(cb) {
+        for ((acc, a), b) in acc.iter_mut().zip(ca).zip(cb) {
+            *acc += *a * *b;
+        }
+    }
+    acc.into_iter()
+        .chain(rem.map(|(a, b)| *a * *b))
+        .fold(E::default(), |a, v| a + v)
+}
+
+/// Returns the sum of the squares of all values.
+#[inline]
+pub fn sum_sq<E: Dtype>(x: &[E]) -> E {
+    dot(x, x)
+}
+
+/// Returns the sum of the squared differences between the two slices.
+#[inline]
+pub fn sum_sq_diff<E: Dtype>(a: &[E], b: &[E]) -> E {
+    debug_assert_eq!(a.len(), b.len());
+    let mut acc = [E::default(); LANES];
+    let (ca, cb) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let rem = ca.remainder().iter().zip(cb.remainder());
+    for (ca, cb) in ca.zip(cb) {
+        for ((acc, a), b) in acc.iter_mut().zip(ca).zip(cb) {
+            let d = *a - *b;
+            *acc += d * d;
+        }
+    }
+    acc.into_iter()
+        .chain(rem.map(|(a, b)| (*a - *b) * (*a - *b)))
+        .fold(E::default(), |a, v| a + v)
+}
+
+/// Returns the largest value, or negative infinity if the slice is empty.
+#[inline]
+pub fn max<E: Float>(x: &[E]) -> E {
+    reduce(x, E::NEG_INFINITY, E::max)
+}
+
+/// Adds `b` to `a`, elementwise.
+#[inline]
+pub fn add_assign<E: Dtype>(a: &mut [E], b: &[E]) {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a += *b);
+}
+
+/// Multiplies `a` by `b`, elementwise.
+#[inline]
+pub fn mul_assign<E: Dtype>(a: &mut [E], b: &[E]) {
+    a.iter_mut().zip(b).for_each(|(a, b)| *a *= *b);
+}
+
+/// Writes the elementwise product of `a` and `b` to `out`.
+#[inline]
+pub fn mul<E: Dtype>(out: &mut [E], a: &[E], b: &[E]) {
+    out.iter_mut()
+        .zip(a.iter().zip(b))
+        .for_each(|(o, (a, b))| *o = *a * *b);
+}
+
+/// Multiplies all values by `s`.
+#[inline]
+pub fn scale<E: Dtype>(x: &mut [E], s: E) {
+    x.iter_mut().for_each(|x| *x *= s);
+}
+
+/// Writes `sigmoid(s * x)` of each value to `out`, where `s` is an optional
+/// per-element scale.
+#[inline]
+pub fn sigmoid<E: Float>(out: &mut [E], x: &[E], s: Option<&[E]>) {
+    match s {
+        Some(s) => out
+            .iter_mut()
+            .zip(x.iter().zip(s))
+            .for_each(|(o, (x, s))| *o = -(*x * *s)),
+        None => out.iter_mut().zip(x).for_each(|(o, x)| *o = -*x),
+    }
+    E::exp_slice(out);
+    out.iter_mut().for_each(|o| *o = E::ONE / (E::ONE + *o));
+}
+
+/// Computes `exp` of each value in place.
+///
+/// This is a polynomial approximation with a relative error of a few ULP, which
+/// (unlike calls to `f32::exp`) can be vectorized.
+#[inline]
+pub fn exp_f32(x: &mut [f32]) {
+    const LOG2_E: f32 = std::f32::consts::LOG2_E;
+    // Beyond these, exp overflows to infinity or underflows to zero. Clamping
+    // (rather than branching) keeps the loop vectorizable, and propagates NaN.
+    const HI: f32 = 89.0;
+    const LO: f32 = -104.0;
+    const LN2_HI: f32 = 0.693_359_4;
+    const LN2_LO: f32 = -2.121_944_4e-4;
+    // Adding 1.5 * 2^23 rounds to the nearest integer, which is then held in the
+    // low bits of the mantissa.
+    const ROUND: f32 = 12_582_912.0;
+
+    x.iter_mut().for_each(|v| {
+        let x = v.clamp(LO, HI);
+        let t = x * LOG2_E + ROUND;
+        let n = t - ROUND;
+        let r = x - n * LN2_HI - n * LN2_LO;
+
+        // Minimax polynomial for exp(r) on [-ln(2)/2, ln(2)/2], from Cephes.
+        let p = 1.987_569_1e-4;
+        let p = p * r + 1.398_199_9e-3;
+        let p = p * r + 8.333_452e-3;
+        let p = p * r + 4.166_579_6e-2;
+        let p = p * r + 1.666_666_5e-1;
+        let p = p * r + 0.5;
+        let p = p * r * r + r + 1.0;
+
+        // Scale by 2^n. n is in [-150, 128], so split it to keep both halves normal.
+        let n = t.to_bits() as i32 - ROUND.to_bits() as i32;
+        let (n1, n2) = (n >> 1, n - (n >> 1));
+        let s1 = f32::from_bits(((n1 + 127) as u32) << 23);
+        let s2 = f32::from_bits(((n2 + 127) as u32) << 23);
+        *v = p * s1 * s2;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reductions() {
+        let x: Vec<f32> = (0..19).map(|i| i as f32 - 4.0).collect();
+        let y: Vec<f32> = (0..19).map(|i| (i % 3) as f32).collect();
+        assert_eq!(sum(&x), x.iter().sum::<f32>());
+        assert_eq!(
+            dot(&x, &y),
+            x.iter().zip(&y).map(|(x, y)| x * y).sum::<f32>()
+        );
+        assert_eq!(sum_sq(&x), x.iter().map(|x| x * x).sum::<f32>());
+        assert_eq!(
+            sum_sq_diff(&x, &y),
+            x.iter()
+                .zip(&y)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f32>()
+        );
+        assert_eq!(max(&x), 14.0);
+        assert_eq!(max::<f32>(&[]), f32::NEG_INFINITY);
+        assert_eq!(sum::<f32>(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_exp() {
+        let mut x: Vec<f32> = (-1740..1770).map(|i| i as f32 * 0.05).collect();
+        let want: Vec<f32> = x.iter().map(|x| x.exp()).collect();
+        exp_f32(&mut x);
+        for (got, want) in x.iter().zip(want) {
+            assert!(
+                (got - want).abs() <= want * 4.0 * f32::EPSILON,
+                "{} != {}",
+                got,
+                want
+            );
+        }
+
+        let mut x = [
+            f32::NEG_INFINITY,
+            -100.0,
+            0.0,
+            100.0,
+            f32::INFINITY,
+            f32::NAN,
+        ];
+        exp_f32(&mut x);
+        assert_eq!(x[2], 1.0);
+        assert_eq!(x[0], 0.0);
+        assert!(x[1] > 0.0 && x[1] < 1.0e-43);
+        assert_eq!(&x[3..5], &[f32::INFINITY, f32::INFINITY]);
+        assert!(x[5].is_nan());
+    }
+}
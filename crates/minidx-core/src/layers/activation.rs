@@ -1,8 +1,4 @@
-use crate::Float;
-
-pub(crate) fn sigmoid<E: Float>(i: E) -> E {
-    E::ONE / (E::ONE + i.neg().exp())
-}
+use crate::{kernels, Float};
 
 /// An element-wise activation function with no trainable parameters.
 #[derive(Clone, Debug, Default)]
@@ -39,68 +35,87 @@ pub enum Activation<E: Float> {
 }
 
 impl<E: Float> Activation<E> {
+    // The activation is matched outside of the loops over elements, so that each
+    // loop is simple enough to be vectorized.
+
+    /// Computes the activation of each input.
+    #[inline]
+    pub(crate) fn forward_into(&self, input: &[E], out: &mut [E]) {
+        match self {
+            Activation::Sigmoid => kernels::sigmoid(out, input, None),
+            Activation::SiLU => {
+                kernels::sigmoid(out, input, None);
+                kernels::mul_assign(out, input);
+            }
+            Activation::Tanh => out.iter_mut().zip(input).for_each(|(o, i)| *o = i.tanh()),
+            Activation::Relu => out
+                .iter_mut()
+                .zip(input)
+                .for_each(|(o, i)| *o = E::default().max(*i)),
+            Activation::LeakyRelu(a) => out.iter_mut().zip(input).for_each(|(o, i)| {
+                *o = if i < &E::default() { *a * *i } else { *i };
+            }),
+            Activation::Softplus => {
+                out.copy_from_slice(input);
+                E::exp_slice(out);
+                out.iter_mut().for_each(|o| *o = (*o + E::ONE).ln());
+            }
+            Activation::Sine => out.iter_mut().zip(input).for_each(|(o, i)| *o = i.sin()),
+            Activation::Cosine => out.iter_mut().zip(input).for_each(|(o, i)| *o = i.cos()),
+        }
+    }
+
+    /// Computes the derivative of the activation at each input.
+    #[inline]
+    pub(crate) fn backward_into(&self, input: &[E], out: &mut [E]) {
+        match self {
+            Activation::Sigmoid => {
+                // TODO: Do we need to compute sigmoid, can we just use i?
+                // Thats what dfdx does: https://github.com/coreylowman/dfdx/blob/main/dfdx-core/src/tensor_ops/sigmoid/cpu_kernel.rs#L12
+                kernels::sigmoid(out, input, None);
+                out.iter_mut()
+                    .for_each(|sig| *sig = *sig * E::ONE.sub(*sig));
+            }
+            Activation::SiLU => {
+                kernels::sigmoid(out, input, None);
+                out.iter_mut()
+                    .zip(input)
+                    .for_each(|(sig, i)| *sig = *sig * (E::ONE + *i * E::ONE.sub(*sig)));
+            }
+            Activation::Tanh => out.iter_mut().zip(input).for_each(|(o, i)| {
+                let tanh = i.tanh();
+                *o = E::ONE.sub(tanh * tanh);
+            }),
+            Activation::Relu => out.iter_mut().zip(input).for_each(|(o, i)| {
+                *o = if i > &E::default() {
+                    E::ONE
+                } else {
+                    E::default()
+                };
+            }),
+            Activation::LeakyRelu(a) => out.iter_mut().zip(input).for_each(|(o, i)| {
+                *o = if i < &E::default() { *a } else { E::ONE };
+            }),
+            Activation::Softplus => kernels::sigmoid(out, input, None),
+            Activation::Sine => out.iter_mut().zip(input).for_each(|(o, i)| *o = (*i).cos()),
+            Activation::Cosine => out
+                .iter_mut()
+                .zip(input)
+                .for_each(|(o, i)| *o = -(*i).sin()),
+        }
+    }
+
     #[inline]
     fn forward<const I: usize>(&self, input: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-        for (o, i) in out.iter_mut().zip(input.iter()) {
-            *o = match self {
-                Activation::Sigmoid => sigmoid(*i),
-                Activation::SiLU => *i * sigmoid(*i),
-                Activation::Tanh => i.tanh(),
-                Activation::Relu => E::default().max(*i),
-                Activation::LeakyRelu(a) => {
-                    if i < &E::default() {
-                        *a * *i
-                    } else {
-                        *i
-                    }
-                }
-                Activation::Softplus => (i.exp() + E::ONE).ln(),
-                Activation::Sine => i.sin(),
-                Activation::Cosine => i.cos(),
-            };
-        }
+        self.forward_into(input, &mut out);
         out
     }
 
     #[inline]
     fn backward<const I: usize>(&self, input: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-        for (o, i) in out.iter_mut().zip(input.iter()) {
-            *o = match self {
-                Activation::Sigmoid => {
-                    let sig = sigmoid(*i);
-                    sig * E::ONE.sub(sig)
-                    // TODO: Do we need to compute sigmoid, can we just use i?
-                    // Thats what dfdx does: https://github.com/coreylowman/dfdx/blob/main/dfdx-core/src/tensor_ops/sigmoid/cpu_kernel.rs#L12
-                }
-                Activation::SiLU => {
-                    let sig = sigmoid(*i);
-                    sig * (E::ONE + *i * E::ONE.sub(sig))
-                }
-                Activation::Tanh => {
-                    let tanh = i.tanh();
-                    E::ONE.sub(tanh * tanh)
-                }
-                Activation::Relu => {
-                    if i > &E::default() {
-                        E::ONE
-                    } else {
-                        E::default()
-                    }
-                }
-                Activation::LeakyRelu(a) => {
-                    if i < &E::default() {
-                        *a
-                    } else {
-                        E::ONE
-                    }
-                }
-                Activation::Softplus => sigmoid(*i),
-                Activation::Sine => (*i).cos(),
-                Activation::Cosine => -(*i).sin(),
-            };
-        }
+        self.backward_into(input, &mut out);
         out
     }
 }
@@ -120,10 +135,7 @@ impl<E: Float, const I: usize> crate::RevModule<[E; I]> for Activation<E> {
 
     fn reverse(&self, inputs: &[E; I], grads_wrt_output: &[E; I]) -> ([E; I], Self::SelfGrads) {
         let mut grads = self.backward(inputs);
-        grads
-            .iter_mut()
-            .zip(grads_wrt_output)
-            .for_each(|(ga, go)| *ga *= *go);
+        kernels::mul_assign(&mut grads, grads_wrt_output);
 
         (grads, ())
     }
@@ -145,6 +157,15 @@ impl<E: Float> crate::ResetParams for Activation<E> {
     ) -> Result<(), crate::Error> {
         Ok(())
     }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        use crate::init::Init;
+        use Activation::*;
+        Some(match self {
+            Relu | LeakyRelu(_) | SiLU | Softplus => Init::KaimingNormal,
+            Sigmoid | Tanh | Sine | Cosine => Init::XavierNormal,
+        })
+    }
 }
 
 impl<E: Float> crate::LoadableModule for Activation<E> {
@@ -163,6 +184,8 @@ impl<E: Float> crate::LoadableModule for Activation<E> {
     ) -> Result<(), crate::LoadSaveError> {
         Ok(())
     }
+
+    fn trained_paths(&self, _path: String, _paths: &mut Vec<String>) {}
 }
 
 impl<E: Float> crate::VisualizableUnit for Activation<E> {
@@ -173,6 +196,18 @@ impl<E: Float> crate::VisualizableUnit for Activation<E> {
     }
 }
 
+impl<E: Float, const I: usize> crate::quantize::Quantize<[E; I]> for Activation<E> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
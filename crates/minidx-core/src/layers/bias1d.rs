@@ -1,5 +1,5 @@
 use crate::gradients::{ClassBias, ClassWrapper, Gradients};
-use crate::Dtype;
+use crate::{kernels, Dtype};
 
 /// A learnable bias on each element.
 #[derive(Clone, Debug)]
@@ -17,14 +17,26 @@ impl<E: Dtype, const I: usize> Default for Bias1d<E, I> {
 
 impl<E: Dtype, const I: usize> Bias1d<E, I> {
     fn forward(&self, input: &[E; I]) -> [E; I] {
-        let mut out: [E; I] = self.bias.raw_grads_ref().clone();
-        for (o, i) in out.iter_mut().zip(input.iter()) {
-            *o += *i;
-        }
+        let mut out: [E; I] = *self.bias.raw_grads_ref();
+        kernels::add_assign(&mut out, input);
         out
     }
 }
 
+/// Randomly initializes a bias, as done by [ResetParams::rand_params](crate::ResetParams::rand_params).
+pub(crate) fn bias_rand_params<E: Dtype, RNG: rand::Rng>(rng: &mut RNG, scale: f32, bias: &mut [E]) {
+    // Xavier/Glorot initialization vibes, but scaled down a ton for bias initialization.
+    // (Unlike dense layers, biases are still learned from zero parameters)
+    let i = bias.len();
+    let stddev = 1.0 / ((i * i) as f32 * 64.0).sqrt();
+    let normal = rand_distr::Normal::new(0.0, stddev).unwrap();
+
+    bias.iter_mut().for_each(|b| {
+        let s: f32 = rng.sample::<f32, _>(normal) * scale;
+        *b = E::from_f32(s).unwrap();
+    });
+}
+
 impl<E: Dtype, const I: usize> crate::BaseModule for Bias1d<E, I> {}
 
 impl<E: Dtype, const I: usize> crate::Module<[E; I]> for Bias1d<E, I> {
@@ -60,15 +72,7 @@ impl<E: Dtype, const I: usize> crate::ResetParams for Bias1d<E, I> {
         rng: &mut RNG,
         scale: f32,
     ) -> Result<(), crate::Error> {
-        // Xavier/Glorot initialization vibes, but scaled down a ton for bias initialization.
-        // (Unlike dense layers, biases are still learned from zero parameters)
-        let stddev = 1.0 / ((I * I) as f32 * 64.0).sqrt();
-        let normal = rand_distr::Normal::new(0.0, stddev).unwrap();
-
-        self.bias.grad_iter_mut().for_each(|b| {
-            let s: f32 = rng.sample::<f32, _>(normal) * scale;
-            *b = E::from_f32(s).unwrap();
-        });
+        bias_rand_params(rng, scale, self.bias.raw_grads_mut());
         Ok(())
     }
 }
@@ -120,6 +124,22 @@ impl<E: Dtype, const I: usize> crate::LoadableModule for Bias1d<E, I> {
         }
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
+}
+
+impl<E: Dtype, const I: usize> crate::quantize::Quantize<[E; I]> for Bias1d<E, I> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
 }
 
 #[cfg(test)]
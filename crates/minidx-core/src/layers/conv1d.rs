@@ -121,6 +121,10 @@ impl<
         )
     }
 
+    fn reverse_inputs(&self, _inputs: &[E; I], grads_wrt_output: &[E; O]) -> [E; I] {
+        Conv1d::gradients_wrt_input(self, grads_wrt_output)
+    }
+
     fn apply(
         &mut self,
         applyer: &mut impl crate::optimizers::GradApplyer,
@@ -178,6 +182,10 @@ impl<
         }
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
 }
 
 impl<
@@ -203,6 +211,23 @@ impl<
         });
         Ok(())
     }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        // Each output sums over the kernel, and each input feeds (up to) a kernel's
+        // width of outputs.
+        let k = self.weights.grad_iter().count();
+        init.unwrap_or_default()
+            .fill(rng, k, k, self.weights.grad_iter_mut());
+        Ok(())
+    }
+
+    fn blocks_init(&self) -> bool {
+        true
+    }
 }
 
 impl<
@@ -1,4 +1,4 @@
-use crate::Dtype;
+use crate::{kernels, Dtype};
 
 /// A layer which learns a per-channel scaling factor.
 ///
@@ -21,36 +21,21 @@ impl<E: Dtype, const I: usize> Diag<E, I> {
     #[inline]
     fn forward(&self, input: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-
-        out.iter_mut()
-            .zip(input.iter())
-            .zip(self.weights.iter())
-            .for_each(|((o, i), w)| *o = *i * *w);
-
+        kernels::mul(&mut out, input, &self.weights);
         out
     }
 
     #[inline]
     fn gradients_wrt_input(&self, output_gradients: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-
-        out.iter_mut()
-            .zip(output_gradients.iter())
-            .zip(self.weights.iter())
-            .for_each(|((o, i), w)| *o = *i * *w);
-
+        kernels::mul(&mut out, output_gradients, &self.weights);
         out
     }
 
     #[inline]
     fn gradients_wrt_weights(&self, input: &[E; I], output_gradients: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-
-        out.iter_mut()
-            .zip(input.iter())
-            .zip(output_gradients.iter())
-            .for_each(|((o, i), w)| *o = *i * *w);
-
+        kernels::mul(&mut out, input, output_gradients);
         out
     }
 }
@@ -142,4 +127,20 @@ impl<E: Dtype, const I: usize> crate::LoadableModule for Diag<E, I> {
         }
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
+}
+
+impl<E: Dtype, const I: usize> crate::quantize::Quantize<[E; I]> for Diag<E, I> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
 }
@@ -0,0 +1,164 @@
+use crate::Dtype;
+
+/// A wrapper which freezes the parameters of the wrapped layer(s).
+///
+/// Gradients still flow through to earlier layers, but the wrapped
+/// parameters are never updated and no optimizer state is kept for them.
+/// Parameters are saved and loaded under the same paths as the unwrapped
+/// layer(s), so pretrained parameters can be loaded directly.
+#[derive(Clone, Debug, Default)]
+pub struct Frozen<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> {
+    pub module: M,
+    pub dt: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> crate::Module<[E; I]>
+    for Frozen<E, I, M>
+{
+    type Output = M::Output;
+
+    fn forward(&self, x: &[E; I]) -> Result<Self::Output, crate::Error> {
+        self.module.forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::TracedModule<[E; I]>,
+    > crate::TracedModule<[E; I]> for Frozen<E, I, M>
+{
+    type Trace = M::Trace;
+
+    fn traced_forward(
+        &self,
+        x: [E; I],
+    ) -> Result<(<Self as crate::Module<[E; I]>>::Output, Self::Trace), crate::Error> {
+        self.module.traced_forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default
+            + crate::Module<[E; I]>
+            + crate::TracedModule<[E; I]>
+            + crate::BackpropModule<[E; I]>,
+    > crate::BackpropModule<[E; I]> for Frozen<E, I, M>
+{
+    type SelfGrads = ();
+
+    fn backprop(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> ([E; I], Self::SelfGrads) {
+        (self.module.backprop_inputs(trace, grads_wrt_output), ())
+    }
+
+    fn backprop_inputs(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        self.module.backprop_inputs(trace, grads_wrt_output)
+    }
+
+    fn update(
+        &mut self,
+        _applyer: &mut impl crate::optimizers::GradApplyer,
+        _updates: Self::SelfGrads,
+    ) -> Result<(), crate::Error> {
+        Ok(())
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::ResetParams>
+    crate::ResetParams for Frozen<E, I, M>
+{
+    fn rand_params<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        scale: f32,
+    ) -> Result<(), crate::Error> {
+        self.module.rand_params(rng, scale)
+    }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        self.module.init_params_with(rng, init)
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        self.module.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.module.blocks_init()
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::LoadableModule>
+    crate::LoadableModule for Frozen<E, I, M>
+{
+    fn save(
+        &self,
+        path: String,
+        dict: &mut std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.save(path, dict)
+    }
+
+    fn load(
+        &mut self,
+        path: String,
+        dict: &std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.load(path, dict)
+    }
+
+    fn trained_paths(&self, _path: String, _paths: &mut Vec<String>) {
+        // Parameters are saved, but never updated.
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::VisualizableUnit>
+    crate::VisualizableUnit for Frozen<E, I, M>
+{
+    const KIND: &'static str = M::KIND;
+    type Params = M::Params;
+    fn params(&self) -> &Self::Params {
+        self.module.params()
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::quantize::Quantize<[E; I]>,
+    > crate::quantize::Quantize<[E; I]> for Frozen<E, I, M>
+{
+    type Quantized = M::Quantized;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        self.module.quantize(calibration, granularity)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Summarize<[E; I]>>
+    crate::Summarize<[E; I]> for Frozen<E, I, M>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        let mut s = self.module.describe().frozen();
+        s.kind += " (frozen)";
+        s
+    }
+}
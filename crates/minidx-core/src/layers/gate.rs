@@ -56,9 +56,9 @@ impl<
     #[doc(hidden)]
     pub fn connection_params(&self) -> (&[[E; I]; O], &[E; O], &[[E; I]; O], &[E; O]) {
         (
-            &self.gate_connections.weights,
+            &*self.gate_connections.weights,
             self.gate_bias.bias.raw_grads_ref(),
-            &self.sig_connections.weights,
+            &*self.sig_connections.weights,
             self.sig_bias.bias.raw_grads_ref(),
         )
     }
@@ -210,6 +210,39 @@ impl<
         (out, ((gc_grads, gb_grads, ga_grads), (sc_grads, sb_grads)))
     }
 
+    fn backprop_inputs(
+        &self,
+        trace: &<Self as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <Self as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        let mut sig_grads_wrt_output = grads_wrt_output;
+        sig_grads_wrt_output
+            .iter_mut()
+            .zip(trace.2 .0)
+            .for_each(|(g, o)| *g *= o);
+        let sig_grads = self
+            .sig_bias
+            .backprop_inputs(&trace.1 .1, sig_grads_wrt_output);
+        let sig_grads = self.sig_connections.backprop_inputs(&trace.1 .0, sig_grads);
+
+        let mut gate_grads_wrt_output = grads_wrt_output;
+        gate_grads_wrt_output
+            .iter_mut()
+            .zip(trace.2 .1)
+            .for_each(|(g, o)| *g *= o);
+        let gate_grads = self
+            .activation
+            .backprop_inputs(&trace.0 .2, gate_grads_wrt_output);
+        let gate_grads = self.gate_bias.backprop_inputs(&trace.0 .1, gate_grads);
+        let gate_grads = self
+            .gate_connections
+            .backprop_inputs(&trace.0 .0, gate_grads);
+
+        let mut out = sig_grads;
+        out.iter_mut().zip(gate_grads).for_each(|(o, x)| *o += x);
+        out
+    }
+
     fn update(
         &mut self,
         applyer: &mut impl crate::optimizers::GradApplyer,
@@ -266,6 +299,19 @@ impl<
 
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        self.gate_connections
+            .trained_paths(path.clone() + ".gate_connections", paths);
+        self.gate_bias
+            .trained_paths(path.clone() + ".gate_bias", paths);
+        self.activation
+            .trained_paths(path.clone() + ".activation", paths);
+
+        self.sig_connections
+            .trained_paths(path.clone() + ".sig_connections", paths);
+        self.sig_bias.trained_paths(path + ".sig_bias", paths);
+    }
 }
 
 impl<
@@ -287,6 +333,65 @@ impl<
         self.activation.rand_params(rng, scale)?;
         Ok(())
     }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        let gate_init = self.activation.preceding_init().or(init);
+        self.gate_connections.init_params_with(rng, gate_init)?;
+        self.gate_bias.init_params_with(rng, None)?;
+        self.sig_connections.init_params_with(rng, init)?;
+        self.sig_bias.init_params_with(rng, None)?;
+        self.activation.init_params_with(rng, None)?;
+        Ok(())
+    }
+
+    fn blocks_init(&self) -> bool {
+        true
+    }
+}
+
+/// Gated linear units are not quantized, and remain in floating point.
+impl<
+        E: Dtype + Float + MatMulImpl,
+        const I: usize,
+        const O: usize,
+        A: crate::Module<[E; O], Output = [E; O]> + TracedModule<[E; O]> + Default + Clone,
+    > crate::quantize::Quantize<[E; I]> for GLU<E, I, O, A>
+{
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
+}
+
+impl<
+        E: Dtype + Float + MatMulImpl,
+        const I: usize,
+        const O: usize,
+        A: crate::Module<[E; O], Output = [E; O]>
+            + TracedModule<[E; O]>
+            + crate::Summarize<[E; O]>
+            + Default,
+    > crate::Summarize<[E; I]> for GLU<E, I, O, A>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        // Dense connections and bias for both the gate and the signal.
+        let mut s = crate::summary::LayerSummary::leaf::<E>("glu", I, O, 2 * (I * O + O));
+        let a = self.activation.describe();
+        s.params += a.params;
+        s.trainable += a.trainable;
+        s.param_bytes += a.param_bytes;
+        s.grad_bytes += a.grad_bytes;
+        s
+    }
 }
 
 #[cfg(test)]
@@ -324,9 +429,9 @@ mod tests {
     #[test]
     fn test_backward_simple() {
         let mut g = GLU::<f32, 2, 1>::default();
-        g.gate_connections.weights = [[2.0, 1.0]];
+        *g.gate_connections.weights = [[2.0, 1.0]];
         g.gate_bias.bias.raw_grads_mut()[0] = -2.0;
-        g.sig_connections.weights = [[1.0, -1.0]];
+        *g.sig_connections.weights = [[1.0, -1.0]];
         g.sig_bias.bias.raw_grads_mut()[0] = 1.0;
 
         let (out, trace) = g.traced_forward([1.0, 2.0]).unwrap();
@@ -341,11 +446,11 @@ mod tests {
 
         assert_eq!(
             (grads.0 .0, grads.0 .1.raw_grads(), grads.0 .2),
-            ([[0.0, 0.0]], [0.0], ())
+            (Box::new([[0.0, 0.0]]), [0.0], ())
         ); // no gradient for gate as sig was inactive
         assert_eq!(
             (grads.1 .0, grads.1 .1.raw_grads()),
-            ([[-2.0, -4.0]], [-2.0])
+            (Box::new([[-2.0, -4.0]]), [-2.0])
         ); // activation was non-zero so gradients for gate
     }
 }
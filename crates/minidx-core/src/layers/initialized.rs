@@ -0,0 +1,166 @@
+use crate::init::Init;
+use crate::{Dtype, Gradients};
+
+/// A wrapper which selects the initialization scheme of the wrapped layer(s),
+/// used by [ResetParams::init_params](crate::ResetParams::init_params).
+///
+/// The scheme applies to any wrapped layers which are not followed by an
+/// activation within the wrapped module. Parameters are saved and loaded under
+/// the same paths as the unwrapped layer(s).
+#[derive(Clone, Debug, Default)]
+pub struct Initialized<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> {
+    pub module: M,
+    pub init: Init,
+    pub dt: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> crate::Module<[E; I]>
+    for Initialized<E, I, M>
+{
+    type Output = M::Output;
+
+    fn forward(&self, x: &[E; I]) -> Result<Self::Output, crate::Error> {
+        self.module.forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::TracedModule<[E; I]>,
+    > crate::TracedModule<[E; I]> for Initialized<E, I, M>
+{
+    type Trace = M::Trace;
+
+    fn traced_forward(
+        &self,
+        x: [E; I],
+    ) -> Result<(<Self as crate::Module<[E; I]>>::Output, Self::Trace), crate::Error> {
+        self.module.traced_forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default
+            + crate::Module<[E; I]>
+            + crate::TracedModule<[E; I]>
+            + crate::BackpropModule<[E; I]>,
+    > crate::BackpropModule<[E; I]> for Initialized<E, I, M>
+where
+    M::SelfGrads: Gradients,
+{
+    type SelfGrads = M::SelfGrads;
+
+    fn backprop(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> ([E; I], Self::SelfGrads) {
+        self.module.backprop(trace, grads_wrt_output)
+    }
+
+    fn backprop_inputs(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        self.module.backprop_inputs(trace, grads_wrt_output)
+    }
+
+    fn update(
+        &mut self,
+        applyer: &mut impl crate::optimizers::GradApplyer,
+        updates: Self::SelfGrads,
+    ) -> Result<(), crate::Error> {
+        self.module.update(applyer, updates)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::ResetParams>
+    crate::ResetParams for Initialized<E, I, M>
+{
+    fn rand_params<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        scale: f32,
+    ) -> Result<(), crate::Error> {
+        self.module.rand_params(rng, scale)
+    }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        _init: Option<Init>,
+    ) -> Result<(), crate::Error> {
+        self.module.init_params_with(rng, Some(self.init))
+    }
+
+    fn preceding_init(&self) -> Option<Init> {
+        self.module.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.module.blocks_init()
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::LoadableModule>
+    crate::LoadableModule for Initialized<E, I, M>
+{
+    fn save(
+        &self,
+        path: String,
+        dict: &mut std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.save(path, dict)
+    }
+
+    fn load(
+        &mut self,
+        path: String,
+        dict: &std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.load(path, dict)
+    }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        self.module.trained_paths(path, paths)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::VisualizableUnit>
+    crate::VisualizableUnit for Initialized<E, I, M>
+{
+    const KIND: &'static str = M::KIND;
+    type Params = M::Params;
+    fn params(&self) -> &Self::Params {
+        self.module.params()
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::quantize::Quantize<[E; I]>,
+    > crate::quantize::Quantize<[E; I]> for Initialized<E, I, M>
+{
+    type Quantized = M::Quantized;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        self.module.quantize(calibration, granularity)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Summarize<[E; I]>>
+    crate::Summarize<[E; I]> for Initialized<E, I, M>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        self.module.describe()
+    }
+}
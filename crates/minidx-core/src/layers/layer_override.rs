@@ -0,0 +1,170 @@
+use crate::optimizers::{OverrideApplyer, ParamOverride};
+use crate::{Dtype, Gradients};
+
+/// A wrapper which overrides the training parameters (learning rate, weight
+/// decay and regularization) of the wrapped layer(s).
+///
+/// Parameters are saved and loaded under the same paths as the unwrapped layer(s).
+#[derive(Clone, Debug, Default)]
+pub struct LayerOverride<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> {
+    pub module: M,
+    pub overrides: ParamOverride,
+    pub dt: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> crate::Module<[E; I]>
+    for LayerOverride<E, I, M>
+{
+    type Output = M::Output;
+
+    fn forward(&self, x: &[E; I]) -> Result<Self::Output, crate::Error> {
+        self.module.forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::TracedModule<[E; I]>,
+    > crate::TracedModule<[E; I]> for LayerOverride<E, I, M>
+{
+    type Trace = M::Trace;
+
+    fn traced_forward(
+        &self,
+        x: [E; I],
+    ) -> Result<(<Self as crate::Module<[E; I]>>::Output, Self::Trace), crate::Error> {
+        self.module.traced_forward(x)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default
+            + crate::Module<[E; I]>
+            + crate::TracedModule<[E; I]>
+            + crate::BackpropModule<[E; I]>,
+    > crate::BackpropModule<[E; I]> for LayerOverride<E, I, M>
+where
+    M::SelfGrads: Gradients,
+{
+    type SelfGrads = M::SelfGrads;
+
+    fn backprop(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> ([E; I], Self::SelfGrads) {
+        self.module.backprop(trace, grads_wrt_output)
+    }
+
+    fn backprop_inputs(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        self.module.backprop_inputs(trace, grads_wrt_output)
+    }
+
+    fn update(
+        &mut self,
+        applyer: &mut impl crate::optimizers::GradApplyer,
+        updates: Self::SelfGrads,
+    ) -> Result<(), crate::Error> {
+        let mut applyer = OverrideApplyer {
+            inner: applyer,
+            overrides: &self.overrides,
+        };
+        self.module.update(&mut applyer, updates)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::ResetParams>
+    crate::ResetParams for LayerOverride<E, I, M>
+{
+    fn rand_params<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        scale: f32,
+    ) -> Result<(), crate::Error> {
+        self.module.rand_params(rng, scale)
+    }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        self.module.init_params_with(rng, init)
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        self.module.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.module.blocks_init()
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::LoadableModule>
+    crate::LoadableModule for LayerOverride<E, I, M>
+{
+    fn save(
+        &self,
+        path: String,
+        dict: &mut std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.save(path, dict)
+    }
+
+    fn load(
+        &mut self,
+        path: String,
+        dict: &std::collections::HashMap<String, Vec<f64>>,
+    ) -> Result<(), crate::LoadSaveError> {
+        self.module.load(path, dict)
+    }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        self.module.trained_paths(path, paths)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::VisualizableUnit>
+    crate::VisualizableUnit for LayerOverride<E, I, M>
+{
+    const KIND: &'static str = M::KIND;
+    type Params = M::Params;
+    fn params(&self) -> &Self::Params {
+        self.module.params()
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::quantize::Quantize<[E; I]>,
+    > crate::quantize::Quantize<[E; I]> for LayerOverride<E, I, M>
+{
+    type Quantized = M::Quantized;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        self.module.quantize(calibration, granularity)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Summarize<[E; I]>>
+    crate::Summarize<[E; I]> for LayerOverride<E, I, M>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        let mut s = self.module.describe();
+        s.kind += " (override)";
+        s
+    }
+}
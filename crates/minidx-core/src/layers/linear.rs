@@ -2,77 +2,138 @@ use crate::matmul::MatMulImpl;
 use crate::{Dtype, Shape};
 
 /// A fully-connected layer with a fixed number of inputs and outputs. No bias.
+///
+/// Weights and their gradients are stored on the heap, so large layers can be
+/// constructed and trained without overflowing the stack.
 #[derive(Clone, Debug)]
 pub struct Dense<E: Dtype + MatMulImpl, const I: usize, const O: usize> {
-    pub(crate) weights: [[E; I]; O],
+    pub(crate) weights: Box<[[E; I]; O]>,
 }
 
 impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> Default for Dense<E, I, O> {
     fn default() -> Self {
         Dense {
-            weights: [[E::default(); I]; O],
+            weights: crate::boxed_zeros(),
         }
     }
 }
 
+/// Computes the outputs of dense connections, given weights laid out as `[[E; I]; O]`.
+///
+/// The number of inputs and outputs are taken from the lengths of `input` and `out`.
+#[inline]
+pub(crate) fn dense_forward<E: Dtype + MatMulImpl>(weights: &[E], input: &[E], out: &mut [E]) {
+    let (i, o) = (input.len(), out.len());
+    debug_assert_eq!(weights.len(), i * o);
+    E::matmul(
+        (1, i, o),
+        true, // I think this needs to be false?
+        input.as_ptr(),
+        Shape::strides(&(1, i)),
+        weights.as_ptr(),
+        Shape::strides(&(i, o)),
+        out.as_mut_ptr(),
+        Shape::strides(&(1, o)),
+    );
+}
+
+/// Computes the gradients of the inputs of dense connections, given the gradients
+/// of the outputs.
+#[inline]
+pub(crate) fn dense_input_grads<E: Dtype + MatMulImpl>(
+    weights: &[E],
+    output_gradients: &[E],
+    out: &mut [E],
+) {
+    let (i, o) = (out.len(), output_gradients.len());
+    debug_assert_eq!(weights.len(), i * o);
+    E::matmul(
+        (1, o, i),
+        true,
+        output_gradients.as_ptr(),
+        Shape::strides(&(1, o)),
+        weights.as_ptr(),
+        Shape::strides(&(o, i)),
+        out.as_mut_ptr(),
+        Shape::strides(&(1, i)),
+    );
+}
+
+/// Computes the gradients of the weights of dense connections, given their
+/// inputs and the gradients of the outputs.
+#[inline]
+pub(crate) fn dense_weight_grads<E: Dtype + MatMulImpl>(
+    input: &[E],
+    output_gradients: &[E],
+    out: &mut [E],
+) {
+    let (i, o) = (input.len(), output_gradients.len());
+    debug_assert_eq!(out.len(), i * o);
+    E::matmul(
+        (i, 1, o),
+        false, // Just flipped this to false and it still works?
+        input.as_ptr(),
+        Shape::strides(&(i, 1)),
+        output_gradients.as_ptr(),
+        Shape::strides(&(1, o)),
+        out.as_mut_ptr(),
+        Shape::strides(&(i, o)),
+    );
+}
+
+/// Randomly initializes the weights of dense connections with the given number of
+/// inputs, as done by [ResetParams::rand_params](crate::ResetParams::rand_params).
+pub(crate) fn dense_rand_params<E: Dtype, RNG: rand::Rng>(
+    rng: &mut RNG,
+    inputs: usize,
+    scale: f32,
+    weights: &mut [E],
+) {
+    let outputs = weights.len() / inputs;
+    // Xavier/Glorot Initialization: initial values from a distribution with
+    // zero mean and a variance of 2 / (inp + outp).
+    // Can use either normal or uniform distribution, we use normal for now.
+    let normal = rand_distr::Normal::new(0.0, 2.0 / (inputs as f32 + outputs as f32).sqrt()).unwrap();
+
+    weights.iter_mut().for_each(|w| {
+        let s: f32 = rng.sample::<f32, _>(normal) * scale;
+        *w = E::from_f32(s).unwrap();
+    });
+}
+
+/// Views a matrix of weights as a slice, in the order they are laid out in memory.
+#[inline]
+fn flat<E, const I: usize, const O: usize>(w: &[[E; I]; O]) -> &[E] {
+    // SAFETY: Nested arrays are laid out contiguously, without padding.
+    unsafe { std::slice::from_raw_parts(w.as_ptr() as *const E, I * O) }
+}
+
+/// Views a matrix of weights as a mutable slice, in the order they are laid out in memory.
+#[inline]
+fn flat_mut<E, const I: usize, const O: usize>(w: &mut [[E; I]; O]) -> &mut [E] {
+    // SAFETY: Nested arrays are laid out contiguously, without padding.
+    unsafe { std::slice::from_raw_parts_mut(w.as_mut_ptr() as *mut E, I * O) }
+}
+
 impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> Dense<E, I, O> {
     #[inline]
     fn forward(&self, input: &[E; I]) -> [E; O] {
         let mut out: [E; O] = [E::default(); O];
-
-        let (m, k) = (1, I);
-        let n = O;
-        E::matmul(
-            (m, k, n),
-            true, // I think this needs to be false?
-            input.as_ptr(),
-            Shape::strides(&(1, I)),
-            self.weights.as_ptr() as *const E,
-            Shape::strides(&(I, O)),
-            out.as_mut_ptr(),
-            Shape::strides(&(1, O)),
-        );
-
+        dense_forward(flat(&self.weights), input, &mut out);
         out
     }
 
     #[inline]
     fn gradients_wrt_input(&self, output_gradients: &[E; O]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-
-        let (m, k) = (1, I);
-        let n = O;
-        let strides = (m, n).strides();
-        E::matmul(
-            (m, n, k),
-            true,
-            output_gradients.as_ptr(),
-            strides,
-            self.weights.as_ptr() as *const E,
-            Shape::strides(&(O, I)),
-            out.as_mut_ptr(),
-            Shape::strides(&(1, I)),
-        );
+        dense_input_grads(flat(&self.weights), output_gradients, &mut out);
         out
     }
 
     #[inline]
-    fn gradients_wrt_weights(&self, input: &[E; I], output_gradients: &[E; O]) -> [[E; I]; O] {
-        let mut out: [[E; I]; O] = [[E::default(); I]; O];
-
-        let (m, k) = (1, I);
-        let n = O;
-        let strides = (m, n).strides();
-        E::matmul(
-            (k, m, n),
-            false, // Just flipped this to false and it still works?
-            input.as_ptr(),
-            Shape::strides(&(I, 1)),
-            output_gradients.as_ptr(),
-            strides,
-            out.as_mut_ptr() as *mut E,
-            Shape::strides(&(I, O)),
-        );
+    fn gradients_wrt_weights(&self, input: &[E; I], output_gradients: &[E; O]) -> Box<[[E; I]; O]> {
+        let mut out: Box<[[E; I]; O]> = crate::boxed_zeros();
+        dense_weight_grads(input, output_gradients, flat_mut(&mut out));
         out
     }
 }
@@ -92,7 +153,7 @@ impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::Module<[E; I]
 impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::RevModule<[E; I]>
     for Dense<E, I, O>
 {
-    type SelfGrads = [[E; I]; O];
+    type SelfGrads = Box<[[E; I]; O]>;
 
     fn reverse(&self, inputs: &[E; I], grads_wrt_output: &[E; O]) -> ([E; I], Self::SelfGrads) {
         (
@@ -101,6 +162,10 @@ impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::RevModule<[E;
         )
     }
 
+    fn reverse_inputs(&self, _inputs: &[E; I], grads_wrt_output: &[E; O]) -> [E; I] {
+        Dense::gradients_wrt_input(self, grads_wrt_output)
+    }
+
     fn apply(
         &mut self,
         applyer: &mut impl crate::optimizers::GradApplyer,
@@ -116,19 +181,23 @@ impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::ResetParams f
         rng: &mut RNG,
         scale: f32,
     ) -> Result<(), crate::Error> {
-        // Xavier/Glorot Initialization: initial values from a distribution with
-        // zero mean and a variance of 2 / (inp + outp).
-        // Can use either normal or uniform distribution, we use normal for now.
-        let normal = rand_distr::Normal::new(0.0, 2.0 / (I as f32 + O as f32).sqrt()).unwrap();
-
-        self.weights.iter_mut().for_each(|r| {
-            r.iter_mut().for_each(|w| {
-                let s: f32 = rng.sample::<f32, _>(normal) * scale;
-                *w = E::from_f32(s).unwrap();
-            })
-        });
+        dense_rand_params(rng, I, scale, flat_mut(&mut self.weights));
         Ok(())
     }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        init.unwrap_or_default()
+            .fill(rng, I, O, self.weights.iter_mut().flatten());
+        Ok(())
+    }
+
+    fn blocks_init(&self) -> bool {
+        true
+    }
 }
 
 impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::LoadableModule
@@ -176,6 +245,10 @@ impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::LoadableModul
         }
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
 }
 
 impl<E: Dtype + MatMulImpl, const I: usize, const O: usize> crate::VisualizableUnit
@@ -201,29 +274,29 @@ mod tests {
     #[test]
     fn test_1_2() {
         let layer = Dense::<f32, 1, 2> {
-            weights: [[0.5], [1.0]],
+            weights: Box::new([[0.5], [1.0]]),
         };
         assert_eq!(layer.forward(&[1.0]), [0.5, 1.0],);
         assert_eq!(layer.gradients_wrt_input(&[2.0, 0.0]), [1.0],);
 
         assert_eq!(
             layer.gradients_wrt_weights(&[1.0], &[2.0, 0.0]),
-            [[2.0], [0.0]],
+            Box::new([[2.0], [0.0]]),
         );
         assert_eq!(
             layer.gradients_wrt_weights(&[1.0], &[0.0, 2.0]),
-            [[0.0], [2.0]],
+            Box::new([[0.0], [2.0]]),
         );
         assert_eq!(
             layer.gradients_wrt_weights(&[1.0], &[1.0, 2.0]),
-            [[1.0], [2.0]],
+            Box::new([[1.0], [2.0]]),
         );
     }
 
     #[test]
     fn test_2_1() {
         let layer = Dense::<f32, 2, 1> {
-            weights: [[0.05, 0.5]],
+            weights: Box::new([[0.05, 0.5]]),
         };
         assert_eq!(layer.forward(&[1.0, 2.0]), [1.05],);
         assert_eq!(layer.gradients_wrt_input(&[2.0]), [0.1, 1.0],);
@@ -232,7 +305,7 @@ mod tests {
     #[test]
     fn test_2_2() {
         let layer = Dense::<f32, 2, 2> {
-            weights: [[0.1, 0.4], [0.5, 0.2]],
+            weights: Box::new([[0.1, 0.4], [0.5, 0.2]]),
         };
         assert_eq!(layer.forward(&[1.0, 2.0]), [1.1, 0.8],);
         assert_eq!(layer.gradients_wrt_input(&[0.0, 1.0]), [0.5, 0.2],);
@@ -241,7 +314,7 @@ mod tests {
     #[test]
     fn grad_wrt_input() {
         let layer = Dense::<f32, 2, 2> {
-            weights: [[0.0, 1.0], [1.0, 0.0]],
+            weights: Box::new([[0.0, 1.0], [1.0, 0.0]]),
         };
         assert_eq!(layer.forward(&[1.0, 0.0]), [0.0, 1.0],);
         assert_eq!(layer.gradients_wrt_input(&[1.0, -1.0]), [-1.0, 1.0],);
@@ -55,6 +55,14 @@ where
         self.module.backprop(trace, grads_wrt_output)
     }
 
+    fn backprop_inputs(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        self.module.backprop_inputs(trace, grads_wrt_output)
+    }
+
     fn update(
         &mut self,
         applyer: &mut impl crate::optimizers::GradApplyer,
@@ -78,6 +86,22 @@ impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Reset
     ) -> Result<(), crate::Error> {
         self.module.rand_params(rng, scale)
     }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        self.module.init_params_with(rng, init)
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        self.module.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.module.blocks_init()
+    }
 }
 
 impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> crate::LoadableModule
@@ -98,6 +122,7 @@ impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]>> crate::Loadab
     ) -> Result<(), crate::LoadSaveError> {
         Ok(())
     }
+    fn trained_paths(&self, _path: String, _paths: &mut Vec<String>) {}
 }
 
 impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::VisualizableUnit>
@@ -109,3 +134,30 @@ impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Visua
         self.module.params()
     }
 }
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I]> + crate::quantize::Quantize<[E; I]>,
+    > crate::quantize::Quantize<[E; I]> for LR<E, I, M>
+{
+    type Quantized = M::Quantized;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        self.module.quantize(calibration, granularity)
+    }
+}
+
+impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Summarize<[E; I]>>
+    crate::Summarize<[E; I]> for LR<E, I, M>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        let mut s = self.module.describe();
+        s.kind += &format!(" (lr x{})", self.update_multiplier);
+        s
+    }
+}
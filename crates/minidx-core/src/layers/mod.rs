@@ -1,31 +1,51 @@
 //! Composable neural-network layers
 
-mod activation;
-pub(crate) use activation::sigmoid;
-pub use activation::Activation;
-mod bias1d;
-pub use bias1d::Bias1d;
-mod linear;
-pub use linear::Dense;
-mod softmax;
-pub use softmax::Softmax;
-mod swish;
-pub use swish::Swish;
-mod residual;
-pub use residual::Residual;
-mod gate;
-pub use gate::GLU;
-
-mod conv1d;
-pub use conv1d::{Conv1d, Conv1dKernel};
-
-mod lr_modifier;
-pub use lr_modifier::LR;
-
-mod diag;
-pub use diag::Diag;
-mod scalar_scale;
-pub use scalar_scale::ScalarScale;
-
-mod rmsdiv;
-pub use rmsdiv::RMSDiv;
+/// Declares each layer module and re-exports its layers, so that the list of
+/// layers can also be handed to other macros through [for_each_layer](crate::for_each_layer).
+macro_rules! layers {
+    ($($module:ident: $($layer:ident),+;)+) => {
+        $(
+            mod $module;
+            pub use $module::{$($layer),+};
+        )+
+
+        /// Invokes the given macro with the names of every layer in [layers](crate::layers).
+        ///
+        /// Crates which implement a trait for every layer can use this to check at
+        /// compile time that no layer is missed.
+        #[doc(hidden)]
+        #[macro_export]
+        macro_rules! for_each_layer {
+            ($cb:ident) => {
+                $cb!($($($layer),+),+);
+            };
+        }
+    };
+}
+
+layers! {
+    activation: Activation;
+    bias1d: Bias1d;
+    linear: Dense;
+    softmax: Softmax;
+    swish: Swish;
+    residual: Residual;
+    gate: GLU;
+
+    conv1d: Conv1d;
+    quantized: QConv1d, QDense;
+
+    lr_modifier: LR;
+    frozen: Frozen;
+    layer_override: LayerOverride;
+    initialized: Initialized;
+
+    diag: Diag;
+    scalar_scale: ScalarScale;
+
+    rmsdiv: RMSDiv;
+}
+
+pub(crate) use bias1d::bias_rand_params;
+pub use conv1d::Conv1dKernel;
+pub(crate) use linear::{dense_forward, dense_input_grads, dense_rand_params, dense_weight_grads};
@@ -0,0 +1,253 @@
+use crate::layers::{Conv1d, Conv1dKernel, Dense};
+use crate::matmul::MatMulImpl;
+use crate::quantize::{max_abs, quantize, scale_for, Granularity, Quantize};
+use crate::{Const, Float};
+
+/// Quantizes the inputs of a layer using the given scale, into a buffer of int8 values.
+#[inline]
+fn quantize_input<E: Float, const I: usize>(x: &[E; I], scale: f32) -> [i8; I] {
+    let mut out = [0i8; I];
+    out.iter_mut()
+        .zip(x.iter())
+        .for_each(|(q, x)| *q = quantize(x.to_f32().unwrap(), scale));
+    out
+}
+
+/// An int8 quantized version of a [Dense] layer, for inference only.
+///
+/// Inputs are quantized using a scale calibrated from sample inputs, multiplied
+/// by the int8 weights with int32 accumulation, and then dequantized.
+#[derive(Clone, Debug)]
+pub struct QDense<E: Float, const I: usize, const O: usize> {
+    /// The weights from each input to each output, matching the memory layout of [Dense].
+    pub(crate) weights: Box<[[i8; O]; I]>,
+    /// The scale of the weights of each output. With [Granularity::PerTensor],
+    /// all scales are the same.
+    pub(crate) scales: [f32; O],
+    /// The scale of the inputs.
+    pub(crate) input_scale: f32,
+    pub(crate) dt: std::marker::PhantomData<E>,
+}
+
+impl<E: Float, const I: usize, const O: usize> Default for QDense<E, I, O> {
+    fn default() -> Self {
+        Self {
+            weights: crate::boxed_zeros(),
+            scales: [1.0; O],
+            input_scale: 1.0,
+            dt: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> QDense<E, I, O> {
+    /// Returns the int8 weights from each input to each output, and the scale of
+    /// the weights of each output.
+    pub fn weights(&self) -> (&[[i8; O]; I], &[f32; O]) {
+        (&*self.weights, &self.scales)
+    }
+
+    /// Returns the scale used to quantize inputs.
+    pub fn input_scale(&self) -> f32 {
+        self.input_scale
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> crate::Module<[E; I]> for QDense<E, I, O> {
+    type Output = [E; O];
+
+    fn forward(&self, x: &[E; I]) -> Result<Self::Output, crate::Error> {
+        let qx = quantize_input(x, self.input_scale);
+
+        let mut acc = [0i32; O];
+        for (x, w) in qx.iter().zip(self.weights.iter()) {
+            acc.iter_mut()
+                .zip(w.iter())
+                .for_each(|(a, w)| *a += *x as i32 * *w as i32);
+        }
+
+        let mut out = [E::default(); O];
+        for ((o, a), s) in out.iter_mut().zip(acc).zip(self.scales) {
+            *o = E::from_f32(a as f32 * s * self.input_scale).unwrap();
+        }
+        Ok(out)
+    }
+}
+
+impl<E: Float + MatMulImpl, const I: usize, const O: usize> Quantize<[E; I]> for Dense<E, I, O> {
+    type Quantized = QDense<E, I, O>;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        if calibration.is_empty() {
+            return Err(());
+        }
+        let mut q = QDense::<E, I, O> {
+            input_scale: scale_for(max_abs(calibration.iter().flatten())),
+            ..Default::default()
+        };
+
+        // Dense weights are laid out in memory as [[E; O]; I], despite their type.
+        let weights = self.weights.iter().flatten().collect::<Vec<_>>();
+        let tensor_scale = scale_for(max_abs(weights.iter().copied()));
+        for (o, s) in q.scales.iter_mut().enumerate() {
+            *s = match granularity {
+                Granularity::PerTensor => tensor_scale,
+                Granularity::PerChannel => {
+                    scale_for(max_abs(weights.iter().skip(o).step_by(O).copied()))
+                }
+            };
+        }
+        for (qw, w) in q.weights.iter_mut().zip(weights.chunks(O)) {
+            qw.iter_mut()
+                .zip(w.iter().zip(q.scales))
+                .for_each(|(q, (w, s))| *q = quantize(w.to_f32().unwrap(), s));
+        }
+        Ok(q)
+    }
+}
+
+/// An int8 quantized version of a [Conv1d] layer, for inference only.
+///
+/// As the convolution has a single filter, per-tensor and per-channel
+/// quantization are equivalent.
+#[derive(Clone, Debug)]
+pub struct QConv1d<E: Float, const I: usize, const O: usize> {
+    pub(crate) weights: Vec<i8>,
+    pub(crate) scale: f32,
+    pub(crate) input_scale: f32,
+    pub(crate) dt: std::marker::PhantomData<E>,
+}
+
+impl<E: Float, const I: usize, const O: usize> Default for QConv1d<E, I, O> {
+    fn default() -> Self {
+        Self {
+            weights: Vec::new(),
+            scale: 1.0,
+            input_scale: 1.0,
+            dt: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> QConv1d<E, I, O> {
+    /// Returns the int8 weights of the filter, and their scale.
+    pub fn weights(&self) -> (&[i8], f32) {
+        (&self.weights, self.scale)
+    }
+
+    /// Returns the scale used to quantize inputs.
+    pub fn input_scale(&self) -> f32 {
+        self.input_scale
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> crate::Module<[E; I]> for QConv1d<E, I, O> {
+    type Output = [E; O];
+
+    fn forward(&self, x: &[E; I]) -> Result<Self::Output, crate::Error> {
+        let qx = quantize_input(x, self.input_scale);
+
+        let mut out = [E::default(); O];
+        out.iter_mut().enumerate().for_each(|(i, o)| {
+            let acc: i32 = qx[i..]
+                .iter()
+                .zip(self.weights.iter())
+                .map(|(x, w)| *x as i32 * *w as i32)
+                .sum();
+            *o = E::from_f32(acc as f32 * self.scale * self.input_scale).unwrap();
+        });
+        Ok(out)
+    }
+}
+
+impl<
+        E: Float + MatMulImpl,
+        const I: usize,
+        const O: usize,
+        C: Conv1dKernel<E, Const<I>, Const<O>>,
+    > Quantize<[E; I]> for Conv1d<E, I, O, C>
+{
+    type Quantized = QConv1d<E, I, O>;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        _granularity: Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        if calibration.is_empty() {
+            return Err(());
+        }
+        let scale = scale_for(max_abs(self.weights.as_ref().iter()));
+        Ok(QConv1d {
+            weights: self
+                .weights
+                .as_ref()
+                .iter()
+                .map(|w| quantize(w.to_f32().unwrap(), scale))
+                .collect(),
+            scale,
+            input_scale: scale_for(max_abs(calibration.iter().flatten())),
+            dt: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> crate::Summarize<[E; I]> for QDense<E, I, O> {
+    fn describe(&self) -> crate::summary::LayerSummary {
+        let mut s = crate::summary::LayerSummary::leaf::<i8>("qdense", I, O, I * O).frozen();
+        s.param_bytes += std::mem::size_of::<f32>() * (O + 1);
+        s
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize> crate::Summarize<[E; I]> for QConv1d<E, I, O> {
+    fn describe(&self) -> crate::summary::LayerSummary {
+        let mut s =
+            crate::summary::LayerSummary::leaf::<i8>("qconv1d", I, O, self.weights.len()).frozen();
+        s.param_bytes += std::mem::size_of::<f32>() * 2;
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LoadableModule, Module};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_qdense() {
+        let mut dense = Dense::<f32, 2, 2>::default();
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![1.0, 0.01, -0.5, 0.04]);
+        dense.load("".into(), &store).unwrap();
+
+        let calibration = [[1.0f32, -2.0], [0.5, 0.5]];
+        let want = Module::forward(&dense, &[0.5, 1.5]).unwrap();
+        for granularity in [Granularity::PerTensor, Granularity::PerChannel] {
+            let q = dense.quantize(&calibration, granularity).unwrap();
+            assert_eq!(q.input_scale(), 2.0 / 127.0);
+            let got = q.forward(&[0.5, 1.5]).unwrap();
+            assert!((got[0] - want[0]).abs() < 0.02, "{:?}", granularity);
+        }
+
+        // The small weights of the second output only keep their precision per-channel.
+        let per_tensor = dense
+            .quantize(&calibration, Granularity::PerTensor)
+            .unwrap();
+        let per_channel = dense
+            .quantize(&calibration, Granularity::PerChannel)
+            .unwrap();
+        let column = |w: &[[i8; 2]; 2]| [w[0][1], w[1][1]];
+        assert_eq!(column(per_tensor.weights().0), [1, 5]);
+        assert_eq!(column(per_channel.weights().0), [32, 127]);
+        let got = per_channel.forward(&[0.5, 1.5]).unwrap();
+        assert!((got[1] - want[1]).abs() < 0.001);
+
+        assert!(dense.quantize(&[], Granularity::PerTensor).is_err());
+    }
+}
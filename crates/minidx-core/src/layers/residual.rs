@@ -60,6 +60,18 @@ impl<
         (out, mod_grads)
     }
 
+    fn backprop_inputs(
+        &self,
+        trace: &<M as crate::TracedModule<[E; I]>>::Trace,
+        grads_wrt_output: <M as crate::Module<[E; I]>>::Output,
+    ) -> [E; I] {
+        let mut out = self.module.backprop_inputs(trace, grads_wrt_output);
+        out.iter_mut()
+            .zip(grads_wrt_output)
+            .for_each(|(o, x)| *o += x);
+        out
+    }
+
     fn update(
         &mut self,
         applyer: &mut impl crate::optimizers::GradApplyer,
@@ -87,6 +99,10 @@ impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Loada
     ) -> Result<(), crate::LoadSaveError> {
         self.module.load(path + ".inner", dict)
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        self.module.trained_paths(path + ".inner", paths)
+    }
 }
 
 impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::ResetParams>
@@ -100,4 +116,53 @@ impl<E: Dtype, const I: usize, M: Default + crate::Module<[E; I]> + crate::Reset
         self.module.rand_params(rng, scale)?;
         Ok(())
     }
+
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), crate::Error> {
+        self.module.init_params_with(rng, init)
+    }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        self.module.preceding_init()
+    }
+
+    fn blocks_init(&self) -> bool {
+        self.module.blocks_init()
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I], Output = [E; I]> + crate::quantize::Quantize<[E; I]>,
+    > crate::quantize::Quantize<[E; I]> for Residual<E, I, M>
+where
+    M::Quantized: Default,
+{
+    type Quantized = Residual<E, I, M::Quantized>;
+
+    fn quantize(
+        &self,
+        calibration: &[[E; I]],
+        granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(Residual {
+            module: self.module.quantize(calibration, granularity)?,
+            dt: std::marker::PhantomData,
+        })
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + crate::Module<[E; I], Output = [E; I]> + crate::Summarize<[E; I]>,
+    > crate::Summarize<[E; I]> for Residual<E, I, M>
+{
+    fn describe(&self) -> crate::summary::LayerSummary {
+        crate::summary::LayerSummary::composite("residual", vec![self.module.describe()])
+    }
 }
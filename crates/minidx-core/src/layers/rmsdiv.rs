@@ -1,5 +1,5 @@
 use crate::matmul::MatMulImpl;
-use crate::{Float, Shape};
+use crate::{kernels, Float};
 
 /// A layer which divides each feature by the RMS of all features.
 #[derive(Clone, Debug, Default)]
@@ -8,54 +8,34 @@ pub struct RMSDiv<E: Float + MatMulImpl, const I: usize> {
 }
 
 impl<E: Float + MatMulImpl, const I: usize> RMSDiv<E, I> {
+    #[inline]
+    fn rms(input: &[E; I]) -> E {
+        (kernels::sum_sq(input) / E::from_usize(I).unwrap()).sqrt()
+    }
+
     #[inline]
     fn forward(&self, input: &[E; I]) -> [E; I] {
-        let i = E::from_usize(I).unwrap();
-        let rms: E = (input.iter().fold(E::default(), |acc, x| acc + (*x * *x)) / i).sqrt();
+        let rms = Self::rms(input);
 
-        let mut out: [E; I] = input.clone();
-        for o in out.iter_mut() {
-            *o /= rms;
-        }
+        let mut out: [E; I] = *input;
+        out.iter_mut().for_each(|o| *o /= rms);
         out
     }
 
+    /// Computes the gradients with respect to the input.
+    ///
+    /// The jacobian is `I/rms - x*x^T / (n * rms^3)`, so its product with the
+    /// output gradients can be computed without materializing it.
     #[inline]
-    fn jacobian(&self, input: &[E; I]) -> [[E; I]; I] {
+    fn backward(&self, input: &[E; I], output_gradients: &[E; I]) -> [E; I] {
         let n = E::from_usize(I).unwrap();
-        let rms: E = (input.iter().fold(E::default(), |acc, x| acc + (*x * *x))
-            / E::from_usize(I).unwrap())
-        .sqrt();
-
-        let mut jacobian: [[E; I]; I] = [[E::default(); I]; I];
-        for (i, iv) in input.iter().enumerate() {
-            for (j, jv) in input.iter().enumerate() {
-                jacobian[i][j] = if i == j {
-                    (n * (rms * rms) - (*iv * *iv)) / (n * rms * rms * rms)
-                } else {
-                    -*jv * *iv / (n * rms * rms * rms)
-                };
-            }
-        }
-        jacobian
-    }
+        let rms = Self::rms(input);
+        let c = kernels::dot(input, output_gradients) / (n * rms * rms * rms);
 
-    fn backward(&self, output_gradients: &[E; I], jacobian: &[[E; I]; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-
-        let (m, k) = (1, I);
-        let n = I;
-        E::matmul(
-            (m, k, n),
-            true,
-            output_gradients.as_ptr(),
-            Shape::strides(&(1, I)),
-            jacobian.as_ptr() as *const E,
-            Shape::strides(&(I, I)),
-            out.as_mut_ptr(),
-            Shape::strides(&(1, I)),
-        );
-
+        out.iter_mut()
+            .zip(input.iter().zip(output_gradients))
+            .for_each(|(o, (x, g))| *o = *g / rms - *x * c);
         out
     }
 }
@@ -74,10 +54,7 @@ impl<E: Float + MatMulImpl, const I: usize> crate::RevModule<[E; I]> for RMSDiv<
     type SelfGrads = ();
 
     fn reverse(&self, inputs: &[E; I], grads_wrt_output: &[E; I]) -> ([E; I], Self::SelfGrads) {
-        (
-            RMSDiv::backward(self, grads_wrt_output, &RMSDiv::jacobian(self, inputs)),
-            (),
-        )
+        (RMSDiv::backward(self, inputs, grads_wrt_output), ())
     }
 
     fn apply(
@@ -123,6 +100,20 @@ impl<E: Float + MatMulImpl, const I: usize> crate::LoadableModule for RMSDiv<E,
     ) -> Result<(), crate::LoadSaveError> {
         Ok(())
     }
+
+    fn trained_paths(&self, _path: String, _paths: &mut Vec<String>) {}
+}
+
+impl<E: Float + MatMulImpl, const I: usize> crate::quantize::Quantize<[E; I]> for RMSDiv<E, I> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
 }
 
 #[cfg(test)]
@@ -138,11 +129,8 @@ mod tests {
     #[test]
     fn test_backward() {
         let layer = RMSDiv::<f32, 2>::default();
-        assert_eq!(
-            layer.backward(&[1.0, 1.0], &layer.jacobian(&[2.0, 2.0])),
-            [0.0, 0.0],
-        );
-        let grads = layer.backward(&[1.0, 1.0], &layer.jacobian(&[4.0, 8.0]));
+        assert_eq!(layer.backward(&[2.0, 2.0], &[1.0, 1.0]), [0.0, 0.0],);
+        let grads = layer.backward(&[4.0, 8.0], &[1.0, 1.0]);
         assert!(grads[0] > 0.062, "[0]: {:?}", grads[0]);
         assert!(grads[0] < 0.0634, "[0]: {:?}", grads[0]);
         assert!(grads[1] > -0.0317, "[1]: {:?}", grads[1]);
@@ -140,4 +140,20 @@ impl<E: Dtype> crate::LoadableModule for ScalarScale<E> {
         self.scale = E::from_f64(params[0]).unwrap();
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
+}
+
+impl<E: Dtype, const I: usize> crate::quantize::Quantize<[E; I]> for ScalarScale<E> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
 }
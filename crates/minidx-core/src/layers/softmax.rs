@@ -1,4 +1,4 @@
-use crate::Float;
+use crate::{kernels, Float};
 
 /// A softmax activation layer with no trainable parameters.
 #[derive(Clone, Debug)]
@@ -11,22 +11,46 @@ impl Default for Softmax {
 }
 
 impl Softmax {
+    /// Computes the softmax of the inputs.
     #[inline]
-    fn forward<E: Float, const I: usize>(&self, input: &[E; I]) -> [E; I] {
+    pub(crate) fn forward_into<E: Float>(&self, input: &[E], out: &mut [E]) {
         let t = E::from_f32(self.0).unwrap();
-        let mut out: [E; I] = [E::default(); I];
-
-        let max_val = input.iter().fold(E::NEG_INFINITY, |l, r| E::max(l, *r));
+        let max_val = kernels::max(input);
 
         // Compute exponential of difference between x and max value.
         out.iter_mut().zip(input.iter()).for_each(|(o, &x)| {
-            *o = ((x - max_val) / t).exp();
+            *o = (x - max_val) / t;
         });
+        E::exp_slice(out);
 
         // Normalize
-        let sum_exp = out.iter().fold(E::default(), |a, x| a + *x);
+        let sum_exp = kernels::sum(out);
         out.iter_mut().for_each(|o| *o /= sum_exp);
+    }
+
+    /// Computes the gradients of the inputs, given the outputs of the softmax
+    /// and the gradients of the outputs.
+    #[inline]
+    pub(crate) fn backprop_into<E: Float>(
+        &self,
+        output: &[E],
+        grads_wrt_output: &[E],
+        out: &mut [E],
+    ) {
+        let t = E::from_f32(self.0).unwrap();
+
+        // The jacobian is diag(y) - y*y^T, so its product with the gradients
+        // is y * (g - y.g), which avoids materializing it.
+        let dot = kernels::dot(output, grads_wrt_output);
+        out.iter_mut()
+            .zip(output.iter().zip(grads_wrt_output))
+            .for_each(|(o, (y, g))| *o = *y * (*g - dot) / t);
+    }
 
+    #[inline]
+    fn forward<E: Float, const I: usize>(&self, input: &[E; I]) -> [E; I] {
+        let mut out: [E; I] = [E::default(); I];
+        self.forward_into(input, &mut out);
         out
     }
 
@@ -36,19 +60,9 @@ impl Softmax {
         input: &[E; I],
         grads_wrt_output: &[E; I],
     ) -> [E; I] {
-        let t = E::from_f32(self.0).unwrap();
         let output = self.forward(input);
-
         let mut out: [E; I] = [E::default(); I];
-        out.iter_mut().enumerate().for_each(|(i, o)| {
-            let mut sum = E::default();
-            for j in 0..I {
-                let kronecker = if i == j { E::ONE } else { E::default() };
-                sum += (kronecker - output[j]) * grads_wrt_output[j];
-            }
-            *o = output[i] * sum / t;
-        });
-
+        self.backprop_into(&output, grads_wrt_output, &mut out);
         out
     }
 }
@@ -95,6 +109,8 @@ impl crate::LoadableModule for Softmax {
     ) -> Result<(), crate::LoadSaveError> {
         Ok(())
     }
+
+    fn trained_paths(&self, _path: String, _paths: &mut Vec<String>) {}
 }
 
 impl crate::ResetParams for Softmax {
@@ -115,6 +131,18 @@ impl crate::VisualizableUnit for Softmax {
     }
 }
 
+impl<E: Float, const I: usize> crate::quantize::Quantize<[E; I]> for Softmax {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,6 +168,23 @@ mod tests {
     fn test_softmax_backprop() {
         let [lg, rg] = (Softmax::default()).backprop(&[0.01, 1.0f32], &[1.0, -1.0f32]);
         assert!(lg > rg);
-        // TODO: Needs moar checking
+
+        // Compare against the product with the full jacobian.
+        let softmax = Softmax(0.5);
+        let (x, g) = ([0.3, -1.0, 2.0f32], [0.5, 1.0, -2.0f32]);
+        let y = softmax.forward(&x);
+        let got = softmax.backprop(&x, &g);
+        for i in 0..3 {
+            let want: f32 = (0..3)
+                .map(|j| y[i] * ((i == j) as u8 as f32 - y[j]) * g[j] / 0.5)
+                .sum();
+            assert!(
+                (got[i] - want).abs() < 1.0e-6,
+                "{}: {} != {}",
+                i,
+                got[i],
+                want
+            );
+        }
     }
 }
@@ -1,6 +1,5 @@
-use super::sigmoid;
 use crate::gradients::{ClassActivation, ClassWrapper, Gradients};
-use crate::Float;
+use crate::{kernels, Float};
 
 /// The swish activation function with learnable beta.
 #[derive(Clone, Debug)]
@@ -17,36 +16,47 @@ impl<E: Float, const I: usize> Default for Swish<E, I> {
 }
 
 impl<E: Float, const I: usize> Swish<E, I> {
+    /// Returns `sigmoid(beta * x)` for each input.
     #[inline]
-    fn forward(&self, input: &[E; I]) -> [E; I] {
+    fn activations(&self, input: &[E; I]) -> [E; I] {
         let mut out: [E; I] = [E::default(); I];
-        for ((o, x), b) in out.iter_mut().zip(input.iter()).zip(self.beta.grad_iter()) {
-            *o = *x * sigmoid(*b * *x);
-        }
+        kernels::sigmoid(&mut out, input, Some(self.beta.raw_grads_ref()));
+        out
+    }
+
+    #[inline]
+    fn forward(&self, input: &[E; I]) -> [E; I] {
+        let mut out = self.activations(input);
+        kernels::mul_assign(&mut out, input);
         out
     }
 
     #[inline]
     fn gradients_wrt_input(&self, input: &[E; I]) -> [E; I] {
+        let act = self.activations(input);
         let mut out: [E; I] = [E::default(); I];
-        for ((o, x), b) in out.iter_mut().zip(input.iter()).zip(self.beta.grad_iter()) {
-            let act = sigmoid(*b * *x);
-            *o = act * (E::ONE + (*b * *x) * E::ONE.sub(act));
+        for (((o, x), b), act) in out
+            .iter_mut()
+            .zip(input.iter())
+            .zip(self.beta.grad_iter())
+            .zip(act.iter())
+        {
+            *o = *act * (E::ONE + (*b * *x) * E::ONE.sub(*act));
         }
         out
     }
 
     #[inline]
     fn gradients_wrt_beta(&self, input: &[E; I], output_gradients: &[E; I]) -> [E; I] {
+        let act = self.activations(input);
         let mut out: [E; I] = [E::default(); I];
-        for (((o, x), b), g) in out
+        for (((o, x), act), g) in out
             .iter_mut()
             .zip(input.iter())
-            .zip(self.beta.grad_iter())
+            .zip(act.iter())
             .zip(output_gradients.iter())
         {
-            let act = sigmoid(*b * *x);
-            *o = *g * (*x * *x) * act * E::ONE.sub(act);
+            *o = *g * (*x * *x) * *act * E::ONE.sub(*act);
         }
         out
     }
@@ -67,10 +77,7 @@ impl<E: Float, const I: usize> crate::RevModule<[E; I]> for Swish<E, I> {
 
     fn reverse(&self, inputs: &[E; I], grads_wrt_output: &[E; I]) -> ([E; I], Self::SelfGrads) {
         let mut output_grads = self.gradients_wrt_input(inputs);
-        output_grads
-            .iter_mut()
-            .zip(grads_wrt_output)
-            .for_each(|(ga, go)| *ga *= *go);
+        kernels::mul_assign(&mut output_grads, grads_wrt_output);
 
         (
             output_grads,
@@ -125,6 +132,10 @@ impl<E: Float, const I: usize> crate::LoadableModule for Swish<E, I> {
         }
         Ok(())
     }
+
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        paths.push(path);
+    }
 }
 
 impl<E: Float, const I: usize> crate::ResetParams for Swish<E, I> {
@@ -144,6 +155,10 @@ impl<E: Float, const I: usize> crate::ResetParams for Swish<E, I> {
         });
         Ok(())
     }
+
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        Some(crate::init::Init::KaimingNormal)
+    }
 }
 
 impl<E: Float, const I: usize> crate::VisualizableUnit for Swish<E, I> {
@@ -155,6 +170,18 @@ impl<E: Float, const I: usize> crate::VisualizableUnit for Swish<E, I> {
     }
 }
 
+impl<E: Float, const I: usize> crate::quantize::Quantize<[E; I]> for Swish<E, I> {
+    type Quantized = Self;
+
+    fn quantize(
+        &self,
+        _calibration: &[[E; I]],
+        _granularity: crate::quantize::Granularity,
+    ) -> Result<Self::Quantized, crate::Error> {
+        Ok(self.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,5 +1,6 @@
 //! The core types and logic implementing the `minidx` crate.
 mod dtypes;
+pub(crate) use dtypes::boxed_zeros;
 pub use dtypes::{Dtype, Float, Unit};
 pub mod shapes;
 pub use shapes::*;
@@ -17,10 +18,22 @@ pub use gradients::Gradients;
 mod modules;
 pub use modules::*;
 
+mod accumulate;
+pub mod averaging;
+#[cfg(feature = "serde")]
+pub mod dynamic;
+pub mod guard;
+pub mod init;
+pub mod kernels;
+pub use accumulate::GradAccumulator;
 pub mod layers;
 pub mod loss;
 pub mod optimizers;
+pub mod precision;
+pub mod quantize;
+pub mod summary;
 use optimizers::{GradAdjuster, GradApplyer};
+pub use summary::Summarize;
 
 pub type Error = ();
 
@@ -60,6 +73,9 @@ pub fn train_step<
 /// computing N input-output pairs.
 ///
 /// The average loss over all samples in the batch is returned.
+///
+/// Non-finite losses or gradients are not detected: to skip or recover from such steps,
+/// wrap the optimizer in a [guard::NonFiniteGuard].
 pub fn train_batch<
     Input,
     LV: Float,
@@ -78,24 +94,10 @@ where
     LV: std::ops::Mul<f32, Output = f32>,
     <Network as modules::BackpropModule<Input>>::SelfGrads: Gradients,
 {
-    let (mut grads, lv) = (0..batch_size).into_iter().fold(
-        (Network::SelfGrads::empty(), LV::default()),
-        |(mut accumulated_grads, mut accumulated_lv), _i| {
-            let (input, output) = source();
-
-            let (out, trace) = network.traced_forward(input).unwrap();
-            let (lv, loss_grads) = loss(&out, &output);
-
-            let (_, gradient_updates) = network.backprop(&trace, loss_grads);
-            accumulated_grads.add(gradient_updates);
-            accumulated_lv += lv;
-
-            (accumulated_grads, accumulated_lv)
-        },
-    );
+    let (mut grads, lv) = batch_grads::<Input, LV, Network, S>(network, loss, source, batch_size);
 
     grads.scale((batch_size as f32).recip());
-    let lv = lv.to_f32().unwrap() * (batch_size as f32).recip();
+    let lv = lv * (batch_size as f32).recip();
 
     let gradient_updates = ga.adjust(grads, lv);
     network.update(ga, gradient_updates).expect("update failed");
@@ -124,11 +126,65 @@ where
     LV: std::ops::Mul<f32, Output = f32>,
     (Input, Network::Output): Sized + Sync + Send,
     <Network as modules::BackpropModule<Input>>::SelfGrads: Gradients + Sync + Send,
+{
+    let (mut grads, lv) =
+        batch_grads_parallel::<Input, LV, Network, S>(network, loss, source, batch_size);
+
+    grads.scale((batch_size as f32).recip());
+    let lv = lv * (batch_size as f32).recip();
+
+    let gradient_updates = ga.adjust(grads, lv);
+    network.update(ga, gradient_updates).expect("update failed");
+    ga.advance_step();
+    lv
+}
+
+/// Computes the gradients of N input-output pairs, returning the sum of the gradients and losses.
+fn batch_grads<Input, LV: Float, Network: BackpropModule<Input>, S>(
+    network: &Network,
+    loss: impl Fn(&Network::Output, &Network::Output) -> (LV, Network::Output),
+    source: &mut S,
+    batch_size: usize,
+) -> (Network::SelfGrads, f32)
+where
+    S: FnMut() -> (Input, Network::Output),
+    <Network as modules::BackpropModule<Input>>::SelfGrads: Gradients,
+{
+    let (grads, lv) = (0..batch_size).fold(
+        (Network::SelfGrads::empty(), LV::default()),
+        |(mut accumulated_grads, mut accumulated_lv), _i| {
+            let (input, output) = source();
+
+            let (out, trace) = network.traced_forward(input).unwrap();
+            let (lv, loss_grads) = loss(&out, &output);
+
+            let (_, gradient_updates) = network.backprop(&trace, loss_grads);
+            accumulated_grads.add(gradient_updates);
+            accumulated_lv += lv;
+
+            (accumulated_grads, accumulated_lv)
+        },
+    );
+    (grads, lv.to_f32().unwrap())
+}
+
+/// Parallel version of [batch_grads].
+fn batch_grads_parallel<Input, LV: Float, Network, S>(
+    network: &Network,
+    loss: impl Fn(&Network::Output, &Network::Output) -> (LV, Network::Output) + Sync,
+    source: &mut S,
+    batch_size: usize,
+) -> (Network::SelfGrads, f32)
+where
+    Network: BackpropModule<Input> + Sized + Sync + Send,
+    S: FnMut() -> (Input, Network::Output),
+    (Input, Network::Output): Sized + Sync + Send,
+    <Network as modules::BackpropModule<Input>>::SelfGrads: Gradients + Sync + Send,
 {
     use rayon::prelude::*;
 
     let batch: Vec<_> = (0..batch_size).map(|_| source()).collect();
-    let (mut grads, lv) = batch
+    let (grads, lv) = batch
         .into_par_iter()
         .map(|sample| {
             let (input, output) = sample;
@@ -144,15 +200,8 @@ where
             l_lv += r_lv;
             (l_grads, l_lv)
         })
-        .unwrap();
-
-    grads.scale((batch_size as f32).recip());
-    let lv = lv.to_f32().unwrap() * (batch_size as f32).recip();
-
-    let gradient_updates = ga.adjust(grads, lv);
-    network.update(ga, gradient_updates).expect("update failed");
-    ga.advance_step();
-    lv
+        .unwrap_or((Network::SelfGrads::empty(), LV::default()));
+    (grads, lv.to_f32().unwrap())
 }
 
 /// Something which can have its parameters visualized.
@@ -611,4 +660,39 @@ mod tests {
         assert!((w[0] - 0.277).abs() < 0.1, "got {}, want 0.277", w[0]);
         assert!((w[1] - -1.8).abs() < 0.1, "got {}, want 1.8", w[1]);
     }
+
+    #[test]
+    fn test_large_network_default_stack() {
+        // ~2M parameters, trained on a thread with the default stack size.
+        std::thread::spawn(|| {
+            let mut network = (
+                layers::Dense::<f32, 1024, 2048>::default(),
+                layers::Bias1d::<f32, 2048>::default(),
+                layers::Activation::<f32>::Relu,
+                layers::Dense::<f32, 2048, 1>::default(),
+            );
+            let mut rng = SmallRng::seed_from_u64(1);
+            network.init_params(&mut rng).unwrap();
+
+            let input: [f32; 1024] = std::array::from_fn(|_| rng.random_range(-1.0..1.0));
+            let target = [1.0];
+            let before = network.forward(&input).unwrap().mse(&target);
+
+            let mut updater = network.new_momentum(TrainParams::with_lr(1.0e-4), 0.5);
+            for _i in 0..3 {
+                train_batch_parallel(
+                    &mut updater,
+                    &mut network,
+                    |got, want| (got.mse(want), got.mse_input_grads(want)),
+                    &mut || (input, target),
+                    4,
+                );
+            }
+
+            let after = network.forward(&input).unwrap().mse(&target);
+            assert!(after < before, "loss went from {} to {}", before, after);
+        })
+        .join()
+        .unwrap();
+    }
 }
@@ -1,4 +1,4 @@
-use crate::Float;
+use crate::{kernels, Float};
 
 /// Some output which can have its loss computed.
 pub trait DiffLoss: Clone {
@@ -15,84 +15,122 @@ pub trait DiffLoss: Clone {
     fn huber_input_grads(&self, beta: f32, truth: &Self) -> Self;
 }
 
+fn mse<E: Float>(got: &[E], truth: &[E]) -> E {
+    if got.is_empty() {
+        return E::default();
+    }
+
+    kernels::sum_sq_diff(got, truth) / E::from_usize(got.len()).unwrap()
+}
+
+fn mse_input_grads<E: Float>(got: &[E], truth: &[E], out: &mut [E]) {
+    let c = E::from_usize(got.len()).unwrap();
+
+    out.iter_mut()
+        .zip(got)
+        .zip(truth)
+        .for_each(|((out, test), truth)| *out = (E::ONE + E::ONE) * (*test - *truth) / c);
+}
+
+fn huber<E: Float>(got: &[E], beta: f32, truth: &[E]) -> E {
+    if got.is_empty() {
+        return E::default();
+    }
+    let half = E::from_f32(0.5).unwrap();
+    let beta = E::from_f32(beta).unwrap();
+
+    got.iter()
+        .zip(truth)
+        .fold(E::default(), |a, (test, truth)| {
+            let err = *test - *truth;
+            let err_abs = err.abs();
+
+            let huber_err = if err_abs < beta {
+                half * err * err
+            } else {
+                beta * (err_abs - half * beta * beta)
+            };
+
+            a + huber_err
+        })
+        / E::from_usize(got.len()).unwrap()
+}
+
+fn huber_input_grads<E: Float>(got: &[E], beta: f32, truth: &[E], out: &mut [E]) {
+    let beta = E::from_f32(beta).unwrap();
+    let c = E::from_usize(got.len()).unwrap();
+
+    out.iter_mut()
+        .zip(got)
+        .zip(truth)
+        .for_each(|((out, test), truth)| {
+            let err = *test - *truth;
+            let err_abs = err.abs();
+
+            *out = if err_abs < beta {
+                err / c
+            } else {
+                let signum = if err > E::default() { E::ONE } else { -E::ONE };
+
+                signum * beta / c
+            };
+        });
+}
+
 impl<E: Float, const I: usize> DiffLoss for [E; I] {
     type Output = E;
 
     /// Computes the Mean-squared error.
     fn mse(&self, truth: &Self) -> E {
-        if I == 0 {
-            return E::default();
-        }
-
-        self.iter()
-            .zip(truth)
-            .fold(E::default(), |a, (test, truth)| {
-                let err = *test - *truth;
-                a + (err * err)
-            })
-            / E::from_usize(I).unwrap()
+        mse(self, truth)
     }
 
     /// Computes the gradients of each input with regards to the [DiffLoss::mse].
     fn mse_input_grads(&self, truth: &Self) -> [E; I] {
-        let c = E::from_usize(I).unwrap();
         let mut out = [E::default(); I];
-
-        out.iter_mut()
-            .zip(self)
-            .zip(truth)
-            .for_each(|((out, test), truth)| *out = (E::ONE + E::ONE) * (*test - *truth) / c);
-
+        mse_input_grads(self, truth, &mut out);
         out
     }
 
     /// Computes the Huber error.
     fn huber(&self, beta: f32, truth: &Self) -> E {
-        if I == 0 {
-            return E::default();
-        }
-        let half = E::from_f32(0.5).unwrap();
-        let beta = E::from_f32(beta).unwrap();
-
-        self.iter()
-            .zip(truth)
-            .fold(E::default(), |a, (test, truth)| {
-                let err = *test - *truth;
-                let err_abs = err.abs();
-
-                let huber_err = if err_abs < beta {
-                    half * err * err
-                } else {
-                    beta * (err_abs - half * beta * beta)
-                };
-
-                a + huber_err
-            })
-            / E::from_usize(I).unwrap()
+        huber(self, beta, truth)
     }
 
     /// Computes the gradients of each input with regards to the [DiffLoss::huber].
     fn huber_input_grads(&self, beta: f32, truth: &Self) -> [E; I] {
-        let beta = E::from_f32(beta).unwrap();
-        let c = E::from_usize(I).unwrap();
         let mut out = [E::default(); I];
+        huber_input_grads(self, beta, truth, &mut out);
+        out
+    }
+}
 
-        out.iter_mut()
-            .zip(self)
-            .zip(truth)
-            .for_each(|((out, test), truth)| {
-                let err = *test - *truth;
-                let err_abs = err.abs();
+/// Outputs sized at runtime, such as those of a [DynNetwork](crate::dynamic::DynNetwork).
+/// Panics if the lengths of the output and truth differ.
+impl<E: Float> DiffLoss for Vec<E> {
+    type Output = E;
 
-                *out = if err_abs < beta {
-                    err / c
-                } else {
-                    let signum = if err > E::default() { E::ONE } else { -E::ONE };
+    fn mse(&self, truth: &Self) -> E {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        mse(self, truth)
+    }
+
+    fn mse_input_grads(&self, truth: &Self) -> Self {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        let mut out = vec![E::default(); self.len()];
+        mse_input_grads(self, truth, &mut out);
+        out
+    }
 
-                    signum * beta / c
-                };
-            });
+    fn huber(&self, beta: f32, truth: &Self) -> E {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        huber(self, beta, truth)
+    }
 
+    fn huber_input_grads(&self, beta: f32, truth: &Self) -> Self {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        let mut out = vec![E::default(); self.len()];
+        huber_input_grads(self, beta, truth, &mut out);
         out
     }
 }
@@ -108,43 +146,66 @@ pub trait LogitLoss: Clone {
     fn logit_bce_input_grads(&self, truth: &Self) -> Self;
 }
 
+fn logit_bce<E: Float>(got: &[E], truth: &[E]) -> E {
+    if got.is_empty() {
+        return E::default();
+    }
+
+    (got.iter()
+        .zip(truth)
+        .fold(E::default(), |a, (test, truth)| {
+            let y = *truth;
+            let y_hat = test.max(E::SMOL).min(E::ONE - E::SMOL); // Avoid 0
+
+            a + (y * y_hat.ln() + (E::ONE - y) * (E::ONE - y_hat).ln())
+        })
+        / E::from_usize(got.len()).unwrap())
+    .neg()
+    .max(E::SMOL)
+}
+
+fn logit_bce_input_grads<E: Float>(got: &[E], truth: &[E], out: &mut [E]) {
+    out.iter_mut()
+        .zip(got)
+        .zip(truth)
+        .for_each(|((out, test), truth)| {
+            let y = *truth;
+            let y_hat = *test;
+
+            *out = (y_hat - y) / (E::SMOL + y_hat * (E::ONE - y_hat));
+        });
+}
+
 impl<E: Float, const I: usize> LogitLoss for [E; I] {
     type Output = E;
     /// Computes the binary cross-entropy loss: assumes inputs are the outputs from
     /// sigmoid activation.
     fn logit_bce(&self, truth: &Self) -> E {
-        if I == 0 {
-            return E::default();
-        }
-
-        (self
-            .iter()
-            .zip(truth)
-            .fold(E::default(), |a, (test, truth)| {
-                let y = *truth;
-                let y_hat = test.max(E::SMOL).min(E::ONE - E::SMOL); // Avoid 0
-
-                a + (y * y_hat.ln() + (E::ONE - y) * (E::ONE - y_hat).ln())
-            })
-            / E::from_usize(I).unwrap())
-        .neg()
-        .max(E::SMOL)
+        logit_bce(self, truth)
     }
 
     /// Computes the gradients with respect to the inputs for [LogitLoss::logit_bce].
     fn logit_bce_input_grads(&self, truth: &Self) -> Self {
         let mut out = [E::default(); I];
+        logit_bce_input_grads(self, truth, &mut out);
+        out
+    }
+}
 
-        out.iter_mut()
-            .zip(self)
-            .zip(truth)
-            .for_each(|((out, test), truth)| {
-                let y = *truth;
-                let y_hat = *test;
+/// Outputs sized at runtime, such as those of a [DynNetwork](crate::dynamic::DynNetwork).
+/// Panics if the lengths of the output and truth differ.
+impl<E: Float> LogitLoss for Vec<E> {
+    type Output = E;
 
-                *out = (y_hat - y) / (E::SMOL + y_hat * (E::ONE - y_hat));
-            });
+    fn logit_bce(&self, truth: &Self) -> E {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        logit_bce(self, truth)
+    }
 
+    fn logit_bce_input_grads(&self, truth: &Self) -> Self {
+        assert_eq!(self.len(), truth.len(), "output and truth lengths differ");
+        let mut out = vec![E::default(); self.len()];
+        logit_bce_input_grads(self, truth, &mut out);
         out
     }
 }
@@ -210,7 +271,7 @@ mod tests {
     #[test]
     fn test_logit_bce() {
         assert_eq!([0.0f32; 0].logit_bce(&[]), 0.0f32);
-        assert_eq!([0.0f32; 0].logit_bce_input_grads(&[]), []);
+        assert_eq!([0.0f32; 0].logit_bce_input_grads(&[]), [0.0f32; 0]);
 
         // Small error for correct answer
         assert_eq!([1.0, 0.0].logit_bce(&[1.0, 0.0]), f32::SMOL);
@@ -254,4 +315,15 @@ mod tests {
         assert_eq!([3.0].huber_input_grads(1.0, &[3.5]), [-0.5]);
         assert_eq!([3.5].huber_input_grads(1.0, &[3.0]), [0.5]);
     }
+    #[test]
+    fn test_vec() {
+        assert_eq!(vec![2.0f32, -1.0].mse(&vec![5.0, 1.0]), 6.5);
+        assert_eq!(vec![2.0f32].huber_input_grads(1.0, &vec![5.0]), vec![-1.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "lengths differ")]
+    fn test_vec_length_mismatch() {
+        vec![2.0f32, -1.0].mse(&vec![5.0]);
+    }
 }
@@ -144,3 +144,67 @@ impl MatMulImpl for f64 {
         }
     }
 }
+
+/// Matrix multiplication for half-precision types, with products
+/// accumulated in f32 before rounding.
+#[cfg(feature = "half")]
+#[allow(clippy::too_many_arguments)]
+fn f32_accum_gemm<F: Dtype, M: Dim, K: Dim, N: Dim>(
+    (m, k, n): (M, K, N),
+    accum: bool,
+    ap: *const F,
+    a_strides: [usize; 2],
+    bp: *const F,
+    b_strides: [usize; 2],
+    cp: *mut F,
+    c_strides: [usize; 2],
+) {
+    for i_m in 0..m.size() {
+        for i_n in 0..n.size() {
+            unsafe {
+                let c = cp.add(c_strides[0] * i_m + c_strides[1] * i_n);
+                let mut sum = if accum { (*c).to_f32().unwrap() } else { 0.0 };
+                for i_k in 0..k.size() {
+                    let a = *ap.add(a_strides[0] * i_m + a_strides[1] * i_k);
+                    let b = *bp.add(b_strides[0] * i_k + b_strides[1] * i_n);
+                    sum += a.to_f32().unwrap() * b.to_f32().unwrap();
+                }
+                *c = F::from_f32(sum).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "half")]
+impl MatMulImpl for half::f16 {
+    #[inline]
+    fn matmul<M: Dim, K: Dim, N: Dim>(
+        dims: (M, K, N),
+        accum: bool,
+        ap: *const Self,
+        astr: [usize; 2],
+        bp: *const Self,
+        bstr: [usize; 2],
+        cp: *mut Self,
+        cstr: [usize; 2],
+    ) {
+        f32_accum_gemm(dims, accum, ap, astr, bp, bstr, cp, cstr);
+    }
+}
+
+#[cfg(feature = "half")]
+impl MatMulImpl for half::bf16 {
+    #[inline]
+    fn matmul<M: Dim, K: Dim, N: Dim>(
+        dims: (M, K, N),
+        accum: bool,
+        ap: *const Self,
+        astr: [usize; 2],
+        bp: *const Self,
+        bstr: [usize; 2],
+        cp: *mut Self,
+        cstr: [usize; 2],
+    ) {
+        f32_accum_gemm(dims, accum, ap, astr, bp, bstr, cp, cstr);
+    }
+}
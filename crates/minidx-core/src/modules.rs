@@ -33,6 +33,14 @@ pub trait RevModule<X>: Module<X> {
         grads_wrt_output: &<Self as Module<X>>::Output,
     ) -> (X, Self::SelfGrads);
 
+    /// Returns only the gradients with respect to the input.
+    ///
+    /// Layers should override this if the gradients of their own parameters
+    /// can be skipped.
+    fn reverse_inputs(&self, inputs: &X, grads_wrt_output: &<Self as Module<X>>::Output) -> X {
+        self.reverse(inputs, grads_wrt_output).0
+    }
+
     /// Applies a gradient update step: adding product of the provided gradients and
     /// the scalar to the parameters.
     fn apply(
@@ -92,6 +100,16 @@ pub trait BackpropModule<X>: TracedModule<X> {
         grads_wrt_output: <Self as Module<X>>::Output,
     ) -> (X, Self::SelfGrads);
 
+    /// Computes only the gradients with respect to the input, such as when the
+    /// parameters of this layer/module are not being trained.
+    fn backprop_inputs(
+        &self,
+        trace: &<Self as TracedModule<X>>::Trace,
+        grads_wrt_output: <Self as Module<X>>::Output,
+    ) -> X {
+        self.backprop(trace, grads_wrt_output).0
+    }
+
     /// Applies a gradient update step, given (Self::SelfGrads) and a [`GradApplyer`](GradApplyer).
     ///
     /// While `updates` describes the change in parameters, `applyer` is used to change the parameters
@@ -155,6 +173,14 @@ impl<Input, M: TracedModule<Input, Trace = Input> + RevModule<Input> + BaseModul
         M::reverse(self, trace, &grads_wrt_output)
     }
 
+    fn backprop_inputs(
+        &self,
+        trace: &<M as TracedModule<Input>>::Trace,
+        grads_wrt_output: <M as Module<Input>>::Output,
+    ) -> Input {
+        M::reverse_inputs(self, trace, &grads_wrt_output)
+    }
+
     fn update(
         &mut self,
         applyer: &mut impl GradApplyer,
@@ -177,6 +203,19 @@ pub trait LoadableModule {
     /// FIXME: We should be storing parameters as their base type, not f64.
     fn load(&mut self, path: String, dict: &HashMap<String, Vec<f64>>)
         -> Result<(), LoadSaveError>;
+
+    /// Appends the path of each parameter tensor updated during training, in the
+    /// order the tensors are passed to a [GradApplyer].
+    ///
+    /// By default, a module is assumed to update a single tensor saved under `path`,
+    /// if it saves one, which is found by saving the module. Layers should override
+    /// this to avoid the save, and modules composed of other modules must override it.
+    fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+        let mut dict = HashMap::new();
+        if self.save(path.clone(), &mut dict).is_ok() && dict.contains_key(&path) {
+            paths.push(path);
+        }
+    }
 }
 
 /// Marker trait for low-level layers which are composable modules.
@@ -196,6 +235,42 @@ pub trait ResetParams {
     /// Scale is typically `1.0`, but smaller values can help if you encounter stability issues
     /// early in training.
     fn rand_params<RNG: rand::Rng>(&mut self, rng: &mut RNG, scale: f32) -> Result<(), Error>;
+
+    /// Initializes the learnable parameters of some module, using a scheme
+    /// such as Kaiming/He or Xavier/Glorot initialization suited to the activation
+    /// following each layer. See [Init](crate::init::Init).
+    fn init_params<RNG: rand::Rng>(&mut self, rng: &mut RNG) -> Result<(), Error> {
+        self.init_params_with(rng, None)
+    }
+
+    /// Initializes the learnable parameters of some module using the given scheme,
+    /// or if none is given, the scheme which best suits the layer(s) which follow.
+    ///
+    /// Modules which have no notion of fan-in or fan-out fall back to
+    /// [ResetParams::rand_params] with a scale of `1.0`.
+    fn init_params_with<RNG: rand::Rng>(
+        &mut self,
+        rng: &mut RNG,
+        init: Option<crate::init::Init>,
+    ) -> Result<(), Error> {
+        let _ = init;
+        self.rand_params(rng, 1.0)
+    }
+
+    /// Returns the initialization scheme best suited to a layer which precedes this
+    /// one, if this module has a preference (such as an activation function).
+    fn preceding_init(&self) -> Option<crate::init::Init> {
+        None
+    }
+
+    /// Returns true if this module has weights initialized from their fan-in and
+    /// fan-out, such as a dense layer.
+    ///
+    /// The scheme preferred by a following layer is only passed to preceding layers
+    /// through modules which return false, such as biases.
+    fn blocks_init(&self) -> bool {
+        false
+    }
 }
 
 macro_rules! fwd_tuple_impls {
@@ -244,6 +319,42 @@ macro_rules! fwd_tuple_impls {
                 $(self.$idx.rand_params(rng, scale)?;)*
                 Ok(())
             }
+
+            fn init_params_with<RNG: rand::Rng>(
+                &mut self,
+                rng: &mut RNG,
+                init: Option<crate::init::Init>,
+            ) -> Result<(), Error> {
+                // Each layer uses the scheme preferred by the nearest following
+                // layer with a preference, up to the next layer which blocks it,
+                // or otherwise the given scheme.
+                let preferred = [self.0.preceding_init(), $(self.$idx.preceding_init(),)*];
+                let blocks = [self.0.blocks_init(), $(self.$idx.blocks_init(),)*];
+                let mut hints = preferred.map(|_| init);
+                for i in (0..hints.len() - 1).rev() {
+                    let passed = if blocks[i + 1] { init } else { hints[i + 1] };
+                    hints[i] = preferred[i + 1].or(passed);
+                }
+
+                self.0.init_params_with(rng, hints[0])?;
+                $(self.$idx.init_params_with(rng, hints[$idx])?;)*
+                Ok(())
+            }
+
+            fn preceding_init(&self) -> Option<crate::init::Init> {
+                let preferred = [self.0.preceding_init(), $(self.$idx.preceding_init(),)*];
+                let blocks = [self.0.blocks_init(), $(self.$idx.blocks_init(),)*];
+                for (p, b) in preferred.into_iter().zip(blocks) {
+                    if p.is_some() || b {
+                        return p;
+                    }
+                }
+                None
+            }
+
+            fn blocks_init(&self) -> bool {
+                self.0.blocks_init() $(|| self.$idx.blocks_init())*
+            }
         }
 
         impl<
@@ -262,6 +373,11 @@ macro_rules! fwd_tuple_impls {
                 $(self.$idx.load(format!("{}.{}", path, $idx), dict)?;)*
                 Ok(())
             }
+
+            fn trained_paths(&self, path: String, paths: &mut Vec<String>) {
+                self.0.trained_paths(path.clone() + ".0", paths);
+                $(self.$idx.trained_paths(format!("{}.{}", path, $idx), paths);)*
+            }
         }
     };
 }
@@ -295,6 +411,15 @@ macro_rules! backwd_tuple_impls {
                 (next_grads, ($($fwd_grads,)+))
             }
 
+            fn backprop_inputs(
+                &self,
+                trace: &<Self as TracedModule<Input>>::Trace,
+                next_grads: <Self as Module<Input>>::Output,
+            ) -> Input {
+                $(let next_grads = self.$rev_idx.backprop_inputs(&trace.$rev_idx, next_grads);)+
+                next_grads
+            }
+
             fn update(&mut self, applyer: &mut impl GradApplyer, updates: Self::SelfGrads) -> Result<(), Error> {
                 $(self.$idx.update(applyer, updates.$idx)?;)+
                 Ok(())
@@ -376,6 +501,23 @@ mod tests {
         assert_eq!(grad_wrt_input, [0.0, 0.0]);
     }
 
+    #[test]
+    fn test_backprop_inputs() {
+        let mut network = (
+            layers::Dense::<f32, 2, 3>::default(),
+            layers::GLU::<f32, 3, 3, layers::Activation<f32>>::default(),
+            layers::Residual::<f32, 3, layers::Dense<f32, 3, 3>>::default(),
+            layers::Frozen::<f32, 3, layers::Dense<f32, 3, 2>>::default(),
+        );
+        let mut rng = SmallRng::seed_from_u64(42);
+        network.rand_params(&mut rng, 1.0).unwrap();
+
+        let (_, trace) = network.traced_forward([1.0, 2.0]).unwrap();
+        let (grad_wrt_input, _) = network.backprop(&trace, [0.5, -1.0]);
+        assert_eq!(network.backprop_inputs(&trace, [0.5, -1.0]), grad_wrt_input);
+        assert_ne!(grad_wrt_input, [0.0, 0.0]);
+    }
+
     #[test]
     fn test_reset_params() {
         let mut network = (
@@ -394,6 +536,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_init_hints() {
+        use crate::init::Init;
+        let want = |init: Option<Init>| {
+            let mut dense = layers::Dense::<f32, 4, 4>::default();
+            dense
+                .init_params_with(&mut SmallRng::seed_from_u64(1), init)
+                .unwrap();
+            dense.weights
+        };
+
+        // The hint from the activation passes through the bias to the dense layer.
+        let mut network = (
+            layers::Dense::<f32, 4, 4>::default(),
+            layers::Bias1d::<f32, 4>::default(),
+            layers::Activation::<f32>::Relu,
+        );
+        network
+            .init_params(&mut SmallRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(network.0.weights, want(Some(Init::KaimingNormal)));
+
+        // But not through another dense layer.
+        let mut network = (
+            layers::Dense::<f32, 4, 4>::default(),
+            (
+                layers::Bias1d::<f32, 4>::default(),
+                layers::Dense::<f32, 4, 4>::default(),
+            ),
+            layers::Activation::<f32>::Relu,
+        );
+        network
+            .init_params(&mut SmallRng::seed_from_u64(1))
+            .unwrap();
+        assert_eq!(network.0.weights, want(None));
+    }
+
     #[test]
     fn test_dense_backprop() {
         let network = layers::Dense::<f32, 2, 3>::default();
@@ -401,10 +580,16 @@ mod tests {
         let (_, trace) = network.traced_forward([1.0, 2.0]).unwrap();
         let (grad_wrt_input, gradient_updates) = network.backprop(&trace, [0.0, 0.0, 0.0]);
         assert_eq!(grad_wrt_input, [0.0, 0.0]);
-        assert_eq!(gradient_updates, [[0.0, 0.0], [0.0, 0.0], [0.0, 0.0]]);
+        assert_eq!(
+            gradient_updates,
+            Box::new([[0.0, 0.0], [0.0, 0.0], [0.0, 0.0]])
+        );
 
         let (_, gradient_updates) = network.backprop(&trace, [1.0, 0.0, 0.0]);
-        assert_eq!(gradient_updates, [[1.0, 0.0], [0.0, 2.0], [0.0, 0.0]]); // TODO: Not sure if this is right?
+        assert_eq!(
+            gradient_updates,
+            Box::new([[1.0, 0.0], [0.0, 2.0], [0.0, 0.0]])
+        ); // TODO: Not sure if this is right?
     }
 
     #[test]
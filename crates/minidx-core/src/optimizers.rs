@@ -1,3 +1,4 @@
+use crate::gradients::GradClass;
 use crate::misc::Decay;
 use crate::{Dtype, Float, Gradients, Unit};
 use num_traits::FromPrimitive;
@@ -14,10 +15,22 @@ pub struct TrainInfo {
     pub l2_reg: f32,
     /// The maximum magnitude of any update to any parameter.
     pub grad_clip: Option<f32>,
+    /// The L2 norm of the gradients before norm clipping, if norm clipping is enabled.
+    #[serde(default)]
+    pub grad_norm: Option<f32>,
     /// The 0-indexed count of update steps.
     pub step: usize,
 }
 
+/// Describes how gradients should be clipped by their L2 norm.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum NormClip {
+    /// Scale all gradients so the L2 norm across the whole network is at most the given value.
+    Global(f32),
+    /// Scale the gradients of each parameter tensor so its L2 norm is at most the given value.
+    PerLayer(f32),
+}
+
 /// An object responsible for tweaking gradient updates, such as to
 /// add the effects of momentum, perform gradient clipping, etc.
 pub trait GradAdjuster<G: Gradients> {
@@ -33,9 +46,111 @@ pub trait GradApplyer {
         weights: &mut G,
     ) -> Result<(), crate::Error>;
 
+    /// Applies gradient updates like [GradApplyer::apply], with the given overrides
+    /// to the training parameters.
+    ///
+    /// By default, only [ParamOverride::lr_scale] is honored.
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        mut gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        if let Some(lr_scale) = overrides.lr_scale {
+            gradient_updates.scale(lr_scale);
+        }
+        self.apply(gradient_updates, weights)
+    }
+
     fn advance_step(&mut self);
 }
 
+/// Overrides to the training parameters, for a subset of the parameters of a network.
+///
+/// Overrides can be set per [GradClass] using [TrainParams::and_class_override], or
+/// per layer by wrapping the layer in a [LayerOverride](crate::layers::LayerOverride).
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ParamOverride {
+    /// Multiplies the learning rate.
+    pub lr_scale: Option<f32>,
+    /// Replaces the L1 regularization. Set to zero to disable L1 regularization.
+    pub l1_reg: Option<f32>,
+    /// Replaces the L2 regularization (weight decay). Set to zero to disable L2 regularization.
+    pub l2_reg: Option<f32>,
+}
+
+impl ParamOverride {
+    /// Sets the learning rate multiplier.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn with_lr_scale(mut self, lr_scale: f32) -> Self {
+        self.lr_scale = Some(lr_scale);
+        self
+    }
+
+    /// Sets the l1 regularization weight.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn with_l1(mut self, l1: f32) -> Self {
+        self.l1_reg = Some(l1);
+        self
+    }
+
+    /// Sets the l2 regularization weight.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn with_l2(mut self, l2: f32) -> Self {
+        self.l2_reg = Some(l2);
+        self
+    }
+
+    /// Combines two sets of overrides, where values in `inner` take precedence
+    /// and learning rate multipliers compound.
+    pub fn merged(&self, inner: &ParamOverride) -> ParamOverride {
+        ParamOverride {
+            lr_scale: match (self.lr_scale, inner.lr_scale) {
+                (Some(a), Some(b)) => Some(a * b),
+                (a, b) => a.or(b),
+            },
+            l1_reg: inner.l1_reg.or(self.l1_reg),
+            l2_reg: inner.l2_reg.or(self.l2_reg),
+        }
+    }
+}
+
+/// Applies updates through a [GradApplyer] with the given overrides, used by layers
+/// such as [LayerOverride](crate::layers::LayerOverride).
+pub(crate) struct OverrideApplyer<'a, GA: GradApplyer> {
+    pub(crate) inner: &'a mut GA,
+    pub(crate) overrides: &'a ParamOverride,
+}
+
+impl<GA: GradApplyer> GradApplyer for OverrideApplyer<'_, GA> {
+    fn apply<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+    ) -> Result<(), crate::Error> {
+        self.inner
+            .apply_with_override(gradient_updates, weights, self.overrides)
+    }
+
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        let overrides = self.overrides.merged(overrides);
+        self.inner
+            .apply_with_override(gradient_updates, weights, &overrides)
+    }
+
+    fn advance_step(&mut self) {
+        self.inner.advance_step();
+    }
+}
+
 /// Describes the basic set of parameters used in training. Implements
 /// optimizer traits, so it can be passed into training methods.
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -54,8 +169,16 @@ pub struct TrainParams {
 
     /// If set, the maximum magnitude of any update to any parameter.
     pub grad_clip: Option<f32>,
+    /// If set, how gradients should be clipped by their L2 norm.
+    #[serde(default)]
+    pub grad_norm_clip: Option<NormClip>,
+    /// Overrides to the training parameters for each class of parameter.
+    #[serde(default)]
+    pub class_overrides: Vec<(GradClass, ParamOverride)>,
 
     step: usize,
+    #[serde(skip)]
+    last_grad_norm: Option<f32>,
 }
 
 impl Default for TrainParams {
@@ -66,7 +189,10 @@ impl Default for TrainParams {
             l2_reg: None,
             soft_start_epochs: None,
             grad_clip: None,
+            grad_norm_clip: None,
+            class_overrides: Vec::new(),
             step: 0,
+            last_grad_norm: None,
         }
     }
 }
@@ -86,6 +212,7 @@ impl Into<TrainInfo> for &TrainParams {
                 .map(|d| d.at_timestep(self.step))
                 .unwrap_or(0.0),
             grad_clip: self.grad_clip,
+            grad_norm: self.last_grad_norm,
             step: self.step,
         }
     }
@@ -154,6 +281,36 @@ impl TrainParams {
         self
     }
 
+    /// Sets the maximum L2 norm of the gradients across the whole network. Gradients with
+    /// a larger norm are scaled down, preserving the direction of the update.
+    pub fn and_global_norm_clip(mut self, max_norm: f32) -> Self {
+        self.grad_norm_clip = Some(NormClip::Global(max_norm));
+        self
+    }
+
+    /// Sets the maximum L2 norm of the gradients of each parameter tensor, such as
+    /// the weights or bias of a layer.
+    pub fn and_per_layer_norm_clip(mut self, max_norm: f32) -> Self {
+        self.grad_norm_clip = Some(NormClip::PerLayer(max_norm));
+        self
+    }
+
+    /// Sets overrides to the training parameters for a class of parameter, such as
+    /// to disable regularization of connection weights, or to regularize biases.
+    ///
+    /// This method can be chained in a builder-pattern kind of way.
+    pub fn and_class_override(mut self, class: GradClass, overrides: ParamOverride) -> Self {
+        self.class_overrides.retain(|(c, _)| c != &class);
+        self.class_overrides.push((class, overrides));
+        self
+    }
+
+    /// Returns the L2 norm of the gradients of the most recent update before they were
+    /// clipped, if norm clipping is enabled.
+    pub fn last_grad_norm(&self) -> Option<f32> {
+        self.last_grad_norm
+    }
+
     pub fn current_lr(&self) -> f32 {
         let lr = self.lr.at_timestep(self.step);
         match self.soft_start_epochs {
@@ -168,6 +325,22 @@ impl TrainParams {
         }
     }
 
+    fn clip_norm<G: Gradients>(&mut self, grads: &mut G) {
+        self.last_grad_norm = match self.grad_norm_clip {
+            Some(NormClip::Global(max_norm)) => Some(grads.clip_norm(max_norm)),
+            Some(NormClip::PerLayer(max_norm)) => Some(grads.clip_norm_per_layer(max_norm)),
+            None => None,
+        };
+    }
+
+    fn scale_updates<G: Gradients>(&self, mut gradient_updates: G, loss: f32) -> G {
+        let l = G::Concrete::from_f32(-loss * self.current_lr()).unwrap();
+        gradient_updates
+            .grad_iter_mut()
+            .for_each(|g| *g = self.clip_grad(*g) * l);
+        gradient_updates
+    }
+
     fn clip_grad<G: Dtype>(&self, grad: G) -> G {
         if let Some(clip) = self.grad_clip {
             let clip = G::from_f32(clip).unwrap();
@@ -193,11 +366,8 @@ impl TrainParams {
 
 impl<G: Gradients> GradAdjuster<G> for TrainParams {
     fn adjust(&mut self, mut gradient_updates: G, loss: f32) -> G {
-        let l = G::Concrete::from_f32(-loss * self.current_lr()).unwrap();
-        gradient_updates
-            .grad_iter_mut()
-            .for_each(|g| *g = self.clip_grad(*g) * l);
-        gradient_updates
+        self.clip_norm(&mut gradient_updates);
+        self.scale_updates(gradient_updates, loss)
     }
 }
 
@@ -206,6 +376,15 @@ impl GradApplyer for TrainParams {
         &mut self,
         gradient_updates: G,
         weights: &mut G,
+    ) -> Result<(), crate::Error> {
+        self.apply_with_override(gradient_updates, weights, &ParamOverride::default())
+    }
+
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
     ) -> Result<(), crate::Error> {
         let l1 = self.l1_reg.as_ref().map(|d| d.at_timestep(self.step));
         let l2 = self.l2_reg.as_ref().map(|d| d.at_timestep(self.step));
@@ -214,7 +393,19 @@ impl GradApplyer for TrainParams {
             .grad_iter_mut_with_class()
             .zip(gradient_updates.into_grads())
             .for_each(|((w, c), u)| {
-                let reg_penalty = if c.should_regularize() {
+                let ov = match self.class_overrides.iter().find(|(class, _)| class == &c) {
+                    Some((_, class_ov)) => class_ov.merged(overrides),
+                    None => *overrides,
+                };
+                let regularize = c.should_regularize();
+                let l1 = ov.l1_reg.or(l1.filter(|_| regularize));
+                let l2 = ov.l2_reg.or(l2.filter(|_| regularize));
+                let u = match ov.lr_scale {
+                    Some(lr_scale) => u * G::Concrete::from_f32(lr_scale).unwrap(),
+                    None => u,
+                };
+
+                let reg_penalty = if l1.is_some() || l2.is_some() {
                     G::Concrete::from_f32(if let Some(l1) = l1 {
                         if *w > G::Concrete::default() {
                             l1
@@ -251,6 +442,7 @@ impl GradApplyer for TrainParams {
 /// Implements momentum computation in addition to the basics provided by [TrainParams].
 ///
 /// Implements optimizer traits, so it can be passed into training methods.
+#[derive(Clone, Debug)]
 pub struct Momentum<G: Gradients> {
     params: TrainParams,
     velocity: G,
@@ -295,6 +487,10 @@ impl<G: Gradients> Momentum<G> {
         })
         .unwrap();
 
+        if self.velocity.grad_iter().next().is_none() {
+            self.velocity = gradient_updates.zeros_like();
+        }
+
         // v = coeff * last_v + gradient_updates
         self.velocity
             .grad_iter_mut()
@@ -313,7 +509,8 @@ impl<G: Gradients> Momentum<G> {
 }
 
 impl<G: Gradients> GradAdjuster<G> for Momentum<G> {
-    fn adjust(&mut self, gradient_updates: G, loss: f32) -> G {
+    fn adjust(&mut self, mut gradient_updates: G, loss: f32) -> G {
+        self.params.clip_norm(&mut gradient_updates);
         self.update(gradient_updates, loss)
     }
 }
@@ -327,11 +524,22 @@ impl<G: Gradients> GradApplyer for Momentum<G> {
         self.params.apply(gradient_updates, weights)
     }
 
+    fn apply_with_override<G2: Gradients>(
+        &mut self,
+        gradient_updates: G2,
+        weights: &mut G2,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        self.params
+            .apply_with_override(gradient_updates, weights, overrides)
+    }
+
     fn advance_step(&mut self) {
         self.params.advance_step();
     }
 }
 
+#[derive(Clone, Debug)]
 enum RMSPropBase<G: Gradients> {
     NoMomentum(TrainParams),
     Momentum(Momentum<G>),
@@ -345,14 +553,22 @@ impl<G: Gradients> RMSPropBase<G> {
             RMSPropBase::Momentum(m) => m.train_params(),
         }
     }
+
+    fn train_params_mut(&mut self) -> &mut TrainParams {
+        match self {
+            RMSPropBase::NoMomentum(p) => p,
+            RMSPropBase::Momentum(m) => &mut m.params,
+        }
+    }
 }
 
 impl<G: Gradients> GradAdjuster<G> for RMSPropBase<G> {
+    /// Norm clipping is not performed, as [RMSProp] clips gradients before they are normalized.
     fn adjust(&mut self, gradient_updates: G, loss: f32) -> G {
         use RMSPropBase::*;
         match self {
-            NoMomentum(params) => params.adjust(gradient_updates, loss),
-            Momentum(m) => m.adjust(gradient_updates, loss),
+            NoMomentum(params) => params.scale_updates(gradient_updates, loss),
+            Momentum(m) => m.update(gradient_updates, loss),
         }
     }
 }
@@ -370,6 +586,19 @@ impl<G: Gradients> GradApplyer for RMSPropBase<G> {
         }
     }
 
+    fn apply_with_override<G2: Gradients>(
+        &mut self,
+        gradient_updates: G2,
+        weights: &mut G2,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        use RMSPropBase::*;
+        match self {
+            NoMomentum(params) => params.apply_with_override(gradient_updates, weights, overrides),
+            Momentum(m) => m.apply_with_override(gradient_updates, weights, overrides),
+        }
+    }
+
     fn advance_step(&mut self) {
         match self {
             RMSPropBase::NoMomentum(p) => p.advance_step(),
@@ -378,7 +607,32 @@ impl<G: Gradients> GradApplyer for RMSPropBase<G> {
     }
 }
 
+impl crate::summary::OptimizerState for TrainParams {
+    fn state_buffers(&self) -> usize {
+        0
+    }
+}
+
+impl<G: Gradients> crate::summary::OptimizerState for Momentum<G> {
+    fn state_buffers(&self) -> usize {
+        1
+    }
+}
+
+impl<G: Gradients> crate::summary::OptimizerState for RMSProp<G>
+where
+    G::Concrete: Float,
+{
+    fn state_buffers(&self) -> usize {
+        match self.base {
+            RMSPropBase::NoMomentum(_) => 1,
+            RMSPropBase::Momentum(_) => 2,
+        }
+    }
+}
+
 /// Implements rmsprop on top of basic training parameters or [Momentum].
+#[derive(Clone, Debug)]
 pub struct RMSProp<G: Gradients>
 where
     G::Concrete: Float,
@@ -430,6 +684,14 @@ where
     G::Concrete: Float,
 {
     fn adjust(&mut self, mut gradient_updates: G, loss: f32) -> G {
+        self.base
+            .train_params_mut()
+            .clip_norm(&mut gradient_updates);
+
+        if self.accumulator.grad_iter().next().is_none() {
+            self.accumulator = gradient_updates.zeros_like();
+        }
+
         let b = G::Concrete::from_f32(self.beta).unwrap();
         self.accumulator
             .grad_iter_mut()
@@ -460,7 +722,85 @@ where
         self.base.apply(gradient_updates, weights)
     }
 
+    fn apply_with_override<G2: Gradients>(
+        &mut self,
+        gradient_updates: G2,
+        weights: &mut G2,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        self.base
+            .apply_with_override(gradient_updates, weights, overrides)
+    }
+
     fn advance_step(&mut self) {
         self.base.advance_step();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_norm_clip() {
+        let mut params = TrainParams::with_lr(1.0).and_global_norm_clip(1.0);
+        let updates = params.adjust(([3.0f32], [4.0f32]), -1.0);
+        assert_eq!(params.last_grad_norm(), Some(5.0));
+        assert!((updates.0[0] - 0.6).abs() < 1e-6);
+        assert!((updates.1[0] - 0.8).abs() < 1e-6);
+        let info: TrainInfo = (&params).into();
+        assert_eq!(info.grad_norm, Some(5.0));
+
+        // Per-element clipping still applies after norm clipping.
+        let mut params = TrainParams::with_lr(1.0)
+            .and_per_layer_norm_clip(3.5)
+            .and_gradient_clip(3.0);
+        let updates = params.adjust(([3.0f32], [4.0f32]), -1.0);
+        assert_eq!(params.last_grad_norm(), Some(5.0));
+        assert_eq!(updates, ([3.0], [3.0]));
+
+        let mut m = Momentum::new(TrainParams::with_lr(1.0).and_global_norm_clip(1.0), 0.5);
+        let updates = m.adjust(([3.0f32], [4.0f32]), -1.0);
+        assert_eq!(m.train_params().last_grad_norm(), Some(5.0));
+        assert!((updates.0[0] - 0.6).abs() < 1e-6);
+
+        let mut r = RMSProp::new(TrainParams::with_lr(1.0).and_global_norm_clip(1.0), 0.9);
+        r.adjust(([3.0f32], [4.0f32]), -1.0);
+        assert_eq!(r.train_params().last_grad_norm(), Some(5.0));
+    }
+
+    #[test]
+    fn test_overrides() {
+        use crate::gradients::{ClassBias, ClassWrapper};
+        type G = ([[f32; 1]; 1], ClassWrapper<[f32; 1], ClassBias>);
+        let weights = || -> G { ([[1.0]], ClassWrapper::wrap([1.0])) };
+        let updates = || -> G { ([[0.5]], ClassWrapper::wrap([0.5])) };
+
+        // By default, only connective weights are regularized.
+        let mut params = TrainParams::with_lr(1.0).and_l2(0.25);
+        let mut w = weights();
+        params.apply(updates(), &mut w).unwrap();
+        assert_eq!((w.0[0][0], w.1.raw_grads()[0]), (1.0, 1.5));
+
+        // Disable weight decay on connective weights, and enable it for biases.
+        let mut params = TrainParams::with_lr(1.0)
+            .and_l2(0.25)
+            .and_class_override(GradClass::Connective, ParamOverride::default().with_l2(0.0))
+            .and_class_override(GradClass::Bias, ParamOverride::default().with_l2(0.5));
+        let mut w = weights();
+        params.apply(updates(), &mut w).unwrap();
+        assert_eq!((w.0[0][0], w.1.raw_grads()[0]), (1.5, 0.5));
+
+        // Per-layer overrides take precedence, and learning rate scales compound.
+        let mut params = TrainParams::with_lr(1.0).and_class_override(
+            GradClass::Bias,
+            ParamOverride::default().with_lr_scale(0.5).with_l2(0.5),
+        );
+        let mut w = weights();
+        let layer = ParamOverride::default().with_lr_scale(0.5).with_l2(0.0);
+        params
+            .apply_with_override(updates(), &mut w, &layer)
+            .unwrap();
+        assert_eq!((w.0[0][0], w.1.raw_grads()[0]), (1.25, 1.125));
+    }
+}
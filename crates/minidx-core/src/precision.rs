@@ -0,0 +1,318 @@
+//! Mixed-precision training, for networks with half-precision parameters.
+//!
+//! Parameters stored as [f16](half::f16) or [bf16](half::bf16) (with the `half`
+//! feature) lose small updates to rounding, which stalls training once the learning
+//! rate is small compared to the magnitude of the weights. Wrapping the optimizer in
+//! a [MixedPrecision] keeps an f32 master copy of all parameters: updates are applied
+//! to the master copy, and the parameters of the network are rounded from it.
+//!
+//! The wrapped optimizer adjusts the gradients as [F32Params], so that any state it
+//! keeps (such as momentum) is also in f32.
+//!
+//! ```
+//! # use minidx_core::{layers::Dense, train_batch};
+//! # use minidx_core::optimizers::{Momentum, TrainParams};
+//! # use minidx_core::precision::MixedPrecision;
+//! # use minidx_core::loss::DiffLoss;
+//! let mut network = Dense::<f32, 2, 1>::default();
+//! let updater = Momentum::new(TrainParams::with_lr(1.0e-3), 0.9);
+//! let mut updater = MixedPrecision::new(updater);
+//!
+//! train_batch(
+//!     &mut updater,
+//!     &mut network,
+//!     |got, want| (got.mse(want), got.mse_input_grads(want)),
+//!     &mut || ([1.0, 0.5], [0.5]),
+//!     4,
+//! );
+//! ```
+use crate::gradients::GradClass;
+use crate::optimizers::{GradAdjuster, GradApplyer, ParamOverride};
+use crate::Gradients;
+use num_traits::{FromPrimitive, ToPrimitive};
+
+/// Parameters or gradients converted to f32, along with their [GradClass].
+///
+/// The gradients of a whole network are flattened into one [F32Params], so
+/// [Gradients::clip_norm_per_layer] clips them as a single tensor.
+#[derive(Clone, Debug, Default)]
+pub struct F32Params {
+    values: Vec<f32>,
+    classes: Vec<GradClass>,
+}
+
+impl Gradients for F32Params {
+    type Concrete = f32;
+
+    fn grad_iter(&self) -> impl Iterator<Item = &Self::Concrete> {
+        self.values.iter()
+    }
+
+    fn grad_iter_mut(&mut self) -> impl Iterator<Item = &mut Self::Concrete> {
+        self.values.iter_mut()
+    }
+
+    fn grad_iter_mut_with_class(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut Self::Concrete, GradClass)> {
+        self.values.iter_mut().zip(self.classes.iter().cloned())
+    }
+
+    fn into_grads(self) -> impl Iterator<Item = Self::Concrete> {
+        self.values.into_iter()
+    }
+
+    fn empty() -> Self {
+        Self::default()
+    }
+
+    fn zeros_like(&self) -> Self {
+        Self {
+            values: vec![0.0; self.values.len()],
+            classes: self.classes.clone(),
+        }
+    }
+}
+
+/// Returns the values of the given parameter tensor in f32, along with their [GradClass].
+fn to_f32<G: Gradients>(g: &mut G) -> F32Params {
+    let (values, classes) = g
+        .grad_iter_mut_with_class()
+        .map(|(v, c)| (v.to_f32().unwrap(), c))
+        .unzip();
+    F32Params { values, classes }
+}
+
+/// Wraps an optimizer, applying updates to an f32 master copy of the parameters.
+///
+/// The master copy is created from the parameters of the network on the first step.
+/// If the parameters are changed outside of training (such as by loading a checkpoint),
+/// [MixedPrecision::reset] must be called so the master copy is re-created.
+#[derive(Clone, Debug)]
+pub struct MixedPrecision<GA> {
+    inner: GA,
+
+    // One entry per call to apply() in a step.
+    master: Vec<F32Params>,
+    tensor: usize,
+
+    // The updates from the last call to adjust(), consumed by apply() in order.
+    updates: F32Params,
+    offset: usize,
+}
+
+impl<GA> MixedPrecision<GA> {
+    /// Wraps the given optimizer.
+    pub fn new(inner: GA) -> Self {
+        Self {
+            inner,
+            master: Vec::new(),
+            tensor: 0,
+            updates: F32Params::default(),
+            offset: 0,
+        }
+    }
+
+    /// Returns the wrapped optimizer.
+    pub fn inner(&self) -> &GA {
+        &self.inner
+    }
+
+    /// Returns the wrapped optimizer, mutably.
+    pub fn inner_mut(&mut self) -> &mut GA {
+        &mut self.inner
+    }
+
+    /// Unwraps the optimizer, discarding the master copy of the parameters.
+    pub fn into_inner(self) -> GA {
+        self.inner
+    }
+
+    /// Discards the master copy of the parameters, which is re-created on the next step.
+    ///
+    /// This must be called after the parameters are changed outside of training.
+    pub fn reset(&mut self) {
+        self.master.clear();
+        self.tensor = 0;
+    }
+
+    /// Returns the master copy of the parameters, one vector per parameter tensor
+    /// in the order they are updated.
+    pub fn master(&self) -> impl Iterator<Item = &[f32]> {
+        self.master.iter().map(|m| m.values.as_slice())
+    }
+}
+
+impl<G: Gradients, GA: GradAdjuster<F32Params>> GradAdjuster<G> for MixedPrecision<GA> {
+    /// Adjusts the gradients in f32, keeping the result for the following calls to
+    /// [GradApplyer::apply] so that small updates are not rounded away.
+    fn adjust(&mut self, mut gradient_updates: G, loss: f32) -> G {
+        self.tensor = 0;
+        self.offset = 0;
+        self.updates = self.inner.adjust(to_f32(&mut gradient_updates), loss);
+        gradient_updates
+            .grad_iter_mut()
+            .zip(self.updates.values.iter())
+            .for_each(|(g, u)| *g = G::Concrete::from_f32(*u).unwrap());
+        gradient_updates
+    }
+}
+
+impl<GA: GradApplyer> GradApplyer for MixedPrecision<GA> {
+    fn apply<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+    ) -> Result<(), crate::Error> {
+        self.apply_with_override(gradient_updates, weights, &ParamOverride::default())
+    }
+
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        mut gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
+    ) -> Result<(), crate::Error> {
+        let idx = self.tensor;
+        self.tensor += 1;
+
+        // Use the f32 updates from adjust(), unless apply() was called without it.
+        let n = gradient_updates.grad_iter().count();
+        let range = self.offset..self.offset + n;
+        let updates = match self.updates.values.get(range.clone()) {
+            Some(values) => F32Params {
+                values: values.to_vec(),
+                classes: self.updates.classes[range].to_vec(),
+            },
+            None => to_f32(&mut gradient_updates),
+        };
+        self.offset += n;
+
+        if self.master.len() <= idx {
+            self.master.resize(idx + 1, F32Params::default());
+        }
+        let master = &mut self.master[idx];
+        if master.values.len() != n {
+            *master = to_f32(weights);
+        }
+
+        self.inner.apply_with_override(updates, master, overrides)?;
+
+        weights
+            .grad_iter_mut()
+            .zip(master.values.iter())
+            .for_each(|(w, m)| *w = G::Concrete::from_f32(*m).unwrap());
+        Ok(())
+    }
+
+    fn advance_step(&mut self) {
+        self.tensor = 0;
+        self.updates.values.clear();
+        self.updates.classes.clear();
+        self.offset = 0;
+        self.inner.advance_step();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizers::TrainParams;
+
+    #[test]
+    fn test_master_copy() {
+        let mut tp = MixedPrecision::new(TrainParams::with_lr(1.0));
+        let mut weights = [[1.0f32, 2.0]];
+
+        tp.apply([[0.5, -0.5]], &mut weights).unwrap();
+        tp.advance_step();
+        assert_eq!(weights, [[1.5, 1.5]]);
+        assert_eq!(tp.master().next(), Some(&[1.5, 1.5][..]));
+
+        // Changes made outside of training are picked up after a reset.
+        weights = [[3.0, 3.0]];
+        tp.reset();
+        tp.apply([[1.0, 0.0]], &mut weights).unwrap();
+        assert_eq!(weights, [[4.0, 3.0]]);
+    }
+
+    #[test]
+    fn test_class_overrides() {
+        use crate::gradients::GradClass;
+        let mut tp = MixedPrecision::new(TrainParams::with_lr(1.0).and_class_override(
+            GradClass::Connective,
+            ParamOverride::default().with_lr_scale(0.0),
+        ));
+        let mut weights = ([[1.0f32]], [1.0f32]);
+        tp.apply(([[1.0]], [1.0]), &mut weights).unwrap();
+        assert_eq!(weights, ([[1.0]], [2.0]));
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_bf16_small_updates() {
+        use crate::layers::Dense;
+        use crate::{BackpropModule, LoadableModule};
+        use half::bf16;
+        use std::collections::HashMap;
+
+        let load = || {
+            let mut network = Dense::<bf16, 1, 1>::default();
+            let mut store = HashMap::new();
+            store.insert("".to_string(), vec![1.0]);
+            network.load("".into(), &store).unwrap();
+            network
+        };
+        let weight = |network: &Dense<bf16, 1, 1>| network.weights[0][0].to_f32();
+
+        // bf16 has 8 bits of mantissa, so adding 1e-3 to 1.0 is lost to rounding.
+        let mut plain = load();
+        let mut mixed = load();
+        let mut tp = TrainParams::with_lr(1.0);
+        let mut mp = MixedPrecision::new(TrainParams::with_lr(1.0));
+        for _ in 0..100 {
+            let u = [[bf16::from_f32(1.0e-3)]];
+            plain.update(&mut tp, Box::new(u)).unwrap();
+            mixed.update(&mut mp, Box::new(u)).unwrap();
+            mp.advance_step();
+        }
+        assert_eq!(weight(&plain), 1.0);
+        assert!((weight(&mixed) - 1.1).abs() < 0.01, "{}", weight(&mixed));
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_bf16_momentum() {
+        use crate::layers::Dense;
+        use crate::optimizers::Momentum;
+        use crate::{BackpropModule, LoadableModule};
+        use half::bf16;
+        use std::collections::HashMap;
+
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![1.0]);
+        let mut reference = Dense::<f32, 1, 1>::default();
+        reference.load("".into(), &store).unwrap();
+        let mut mixed = Dense::<bf16, 1, 1>::default();
+        mixed.load("".into(), &store).unwrap();
+
+        // The momentum of the wrapped optimizer is kept in f32, so the master copy
+        // follows training in f32 exactly, given gradients exact in bf16.
+        let g = 0.5f32.powi(10);
+        let mut tp = Momentum::new(TrainParams::with_lr(1.0), 0.9);
+        let mut mp = MixedPrecision::new(Momentum::new(TrainParams::with_lr(1.0), 0.9));
+        for _ in 0..50 {
+            let u = tp.adjust(Box::new([[g]]), 1.0);
+            reference.update(&mut tp, u).unwrap();
+            tp.advance_step();
+
+            let u = mp.adjust(Box::new([[bf16::from_f32(g)]]), 1.0);
+            mixed.update(&mut mp, u).unwrap();
+            mp.advance_step();
+        }
+        let want = reference.weights[0][0];
+        assert!(want < 0.96, "{}", want);
+        assert_eq!(mp.master().next(), Some(&[want][..]));
+        assert_eq!(mixed.weights[0][0], bf16::from_f32(want));
+    }
+}
@@ -0,0 +1,265 @@
+//! Post-training int8 quantization of networks, for inference on small CPUs.
+//!
+//! A trained network is converted using [Quantize::quantize], given a set of sample
+//! inputs used to calibrate the range of the inputs to each layer. Dense and
+//! convolution layers are converted into int8 layers which accumulate in int32,
+//! such as [QDense](crate::layers::QDense). All other layers remain in floating point.
+//!
+//! ```
+//! # use minidx_core::layers::{Activation, Bias1d, Dense};
+//! # use minidx_core::quantize::{compare, Granularity, Quantize};
+//! # use minidx_core::{loss::DiffLoss, Module, ResetParams};
+//! # use rand::SeedableRng;
+//! let mut network = (
+//!     Dense::<f32, 2, 8>::default(),
+//!     Bias1d::<f32, 8>::default(),
+//!     Activation::<f32>::Relu,
+//!     Dense::<f32, 8, 2>::default(),
+//! );
+//! let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+//! network.init_params(&mut rng).unwrap();
+//!
+//! let calibration = vec![[1.0, 0.5], [-0.5, 1.0], [0.0, -1.0]];
+//! let quantized = network.quantize(&calibration, Granularity::PerChannel).unwrap();
+//!
+//! let samples = vec![([1.0, 0.0], [1.0, 0.0]), ([0.0, 1.0], [0.0, 1.0])];
+//! let report = compare(&network, &quantized, &samples, |got, want| got.mse(want)).unwrap();
+//! println!("{}", report);
+//! assert!(report.max_abs_diff < 0.1);
+//! ```
+use crate::{Error, Float, Module};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How weights share a quantization scale.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Granularity {
+    /// All weights of a layer share one scale.
+    PerTensor,
+    /// Each output of a layer has its own scale, which is more accurate
+    /// when the magnitude of weights differs between outputs.
+    #[default]
+    PerChannel,
+}
+
+/// Something which can be converted into a quantized version of itself for inference.
+pub trait Quantize<X>: Module<X> {
+    /// The quantized version of this module.
+    type Quantized: Module<X, Output = Self::Output>;
+
+    /// Quantizes the module, calibrating the range of inputs to each layer
+    /// from the given sample inputs.
+    ///
+    /// Weights and inputs are quantized symmetrically to `[-127, 127]`, using the maximum
+    /// magnitude observed. An error is returned if no calibration samples are given.
+    fn quantize(
+        &self,
+        calibration: &[X],
+        granularity: Granularity,
+    ) -> Result<Self::Quantized, Error>;
+}
+
+/// Returns the largest magnitude of the given values.
+pub(crate) fn max_abs<'a, E: Float + 'a>(values: impl Iterator<Item = &'a E>) -> f32 {
+    values.fold(0.0f32, |m, v| m.max(v.to_f32().unwrap().abs()))
+}
+
+/// Returns the scale which maps the given magnitude onto the int8 range.
+pub(crate) fn scale_for(max_abs: f32) -> f32 {
+    if max_abs > 0.0 && max_abs.is_finite() {
+        max_abs / 127.0
+    } else {
+        1.0
+    }
+}
+
+/// Quantizes a value given a scale, saturating at the limits of the int8 range.
+#[inline]
+pub(crate) fn quantize(v: f32, scale: f32) -> i8 {
+    (v / scale).round().clamp(-127.0, 127.0) as i8
+}
+
+/// Runs each of the inputs through the module.
+fn forward_all<X, M: Module<X>>(m: &M, inputs: &[X]) -> Result<Vec<M::Output>, Error> {
+    inputs.iter().map(|x| m.forward(x)).collect()
+}
+
+macro_rules! quantize_tuple_impls {
+    ([$($name:ident),+] [$($idx:tt),*], $last:ident, [$($rev_tail:ident),*], [$($q_name:ident),*]) => {
+        impl<
+            Input,
+            $last:
+            $(Quantize::<$rev_tail ::Output>, $rev_tail: )*
+            Quantize<Input>
+        > Quantize<Input> for ($($name,)+) {
+            type Quantized = ($($name::Quantized,)+);
+
+            /// Quantizes each module in the tuple, calibrating each using the
+            /// outputs of the (unquantized) modules before it.
+            fn quantize(&self, calibration: &[Input], granularity: Granularity) -> Result<Self::Quantized, Error> {
+                let q1 = self.0.quantize(calibration, granularity)?;
+                let x = forward_all(&self.0, calibration)?;
+                $(
+                    let $q_name = self.$idx.quantize(&x, granularity)?;
+                    let x = forward_all(&self.$idx, &x)?;
+                )*
+                let _ = x;
+                Ok((q1, $($q_name,)*))
+            }
+        }
+    };
+}
+
+quantize_tuple_impls!([M1][], M1, [], []);
+quantize_tuple_impls!([M1, M2][1], M2, [M1], [q2]);
+quantize_tuple_impls!([M1, M2, M3] [1, 2], M3, [M2, M1], [q2, q3]);
+quantize_tuple_impls!([M1, M2, M3, M4] [1, 2, 3], M4, [M3, M2, M1], [q2, q3, q4]);
+quantize_tuple_impls!([M1, M2, M3, M4, M5] [1, 2, 3, 4], M5, [M4, M3, M2, M1], [q2, q3, q4, q5]);
+quantize_tuple_impls!([M1, M2, M3, M4, M5, M6] [1, 2, 3, 4, 5], M6, [M5, M4, M3, M2, M1], [q2, q3, q4, q5, q6]);
+
+/// Describes the difference in accuracy between a floating-point model and its
+/// quantized version, computed by [compare].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QuantReport {
+    /// The number of samples evaluated.
+    pub samples: usize,
+    /// The average loss of the floating-point model.
+    pub float_loss: f32,
+    /// The average loss of the quantized model.
+    pub quant_loss: f32,
+    /// The fraction of samples where the largest output of the floating-point
+    /// model matches the largest wanted output.
+    pub float_accuracy: f32,
+    /// The fraction of samples where the largest output of the quantized
+    /// model matches the largest wanted output.
+    pub quant_accuracy: f32,
+    /// The fraction of samples where both models have the same largest output.
+    pub agreement: f32,
+    /// The largest difference between the outputs of the two models.
+    pub max_abs_diff: f32,
+    /// The mean difference between the outputs of the two models.
+    pub mean_abs_diff: f32,
+}
+
+impl QuantReport {
+    /// Returns the drop in accuracy caused by quantization.
+    pub fn accuracy_drop(&self) -> f32 {
+        self.float_accuracy - self.quant_accuracy
+    }
+}
+
+impl std::fmt::Display for QuantReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} samples: loss {:.5} -> {:.5}, accuracy {:.2}% -> {:.2}% ({:+.2}%), agreement {:.2}%, output diff max {:.5} mean {:.5}",
+            self.samples,
+            self.float_loss,
+            self.quant_loss,
+            self.float_accuracy * 100.0,
+            self.quant_accuracy * 100.0,
+            -self.accuracy_drop() * 100.0,
+            self.agreement * 100.0,
+            self.max_abs_diff,
+            self.mean_abs_diff,
+        )
+    }
+}
+
+fn argmax<E: Float>(v: &[E]) -> usize {
+    v.iter()
+        .enumerate()
+        .fold((0, None), |(bi, bv), (i, x)| match bv {
+            Some(b) if *x <= b => (bi, bv),
+            _ => (i, Some(*x)),
+        })
+        .0
+}
+
+/// Compares the outputs of a floating-point model and its quantized version on the
+/// given samples of inputs and wanted outputs.
+pub fn compare<X, E: Float, const O: usize>(
+    float: &impl Module<X, Output = [E; O]>,
+    quantized: &impl Module<X, Output = [E; O]>,
+    samples: &[(X, [E; O])],
+    loss: impl Fn(&[E; O], &[E; O]) -> E,
+) -> Result<QuantReport, Error> {
+    let mut r = QuantReport {
+        samples: samples.len(),
+        ..Default::default()
+    };
+    if samples.is_empty() {
+        return Ok(r);
+    }
+
+    let (mut float_correct, mut quant_correct, mut agree) = (0, 0, 0);
+    let mut sum_diff = 0.0;
+    for (x, want) in samples {
+        let (f, q) = (float.forward(x)?, quantized.forward(x)?);
+        r.float_loss += loss(&f, want).to_f32().unwrap();
+        r.quant_loss += loss(&q, want).to_f32().unwrap();
+
+        let (fa, qa, wa) = (argmax(&f), argmax(&q), argmax(want));
+        float_correct += (fa == wa) as usize;
+        quant_correct += (qa == wa) as usize;
+        agree += (fa == qa) as usize;
+
+        for (f, q) in f.iter().zip(q.iter()) {
+            let d = (*f - *q).to_f32().unwrap().abs();
+            r.max_abs_diff = r.max_abs_diff.max(d);
+            sum_diff += d as f64;
+        }
+    }
+
+    let n = samples.len() as f32;
+    r.float_loss /= n;
+    r.quant_loss /= n;
+    r.float_accuracy = float_correct as f32 / n;
+    r.quant_accuracy = quant_correct as f32 / n;
+    r.agreement = agree as f32 / n;
+    r.mean_abs_diff = (sum_diff / (samples.len() * O.max(1)) as f64) as f32;
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::{Activation, Bias1d, Dense, Softmax};
+    use crate::ResetParams;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_quantize_network() {
+        let mut network = (
+            (Dense::<f32, 4, 16>::default(), Bias1d::<f32, 16>::default()),
+            Activation::Relu,
+            (Dense::<f32, 16, 3>::default(), Bias1d::<f32, 3>::default()),
+            Softmax::default(),
+        );
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(3);
+        network.init_params(&mut rng).unwrap();
+
+        let mut input = || -> [f32; 4] { std::array::from_fn(|_| rng.random_range(-1.0..1.0)) };
+        let calibration: Vec<_> = (0..64).map(|_| input()).collect();
+        let samples: Vec<_> = (0..64)
+            .map(|_| {
+                let x = input();
+                (x, network.forward(&x).unwrap())
+            })
+            .collect();
+
+        for granularity in [Granularity::PerTensor, Granularity::PerChannel] {
+            let quantized = network.quantize(&calibration, granularity).unwrap();
+            let r = compare(&network, &quantized, &samples, |got, want| {
+                got.iter().zip(want).map(|(g, w)| (g - w).powi(2)).sum()
+            })
+            .unwrap();
+            assert_eq!(r.samples, 64);
+            assert_eq!(r.float_accuracy, 1.0);
+            assert!(r.max_abs_diff < 0.05, "{}", r);
+            assert!(r.agreement > 0.9, "{}", r);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Text summaries of the structure of a network.
+use crate::{BaseModule, Gradients, Module, RevModule, VisualizableUnit};
+use std::fmt;
+
+/// Something with a fixed number of elements, such as the input or output of a layer.
+pub trait Elements {
+    const LEN: usize;
+}
+
+impl<T, const N: usize> Elements for [T; N] {
+    const LEN: usize = N;
+}
+
+/// Describes a layer, or a composition of layers, within a network.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayerSummary {
+    /// The kind of layer, such as `"dense"`, or `"sequential"` for a tuple of layers.
+    pub kind: String,
+    /// The number of inputs to the layer.
+    pub input: usize,
+    /// The number of outputs of the layer.
+    pub output: usize,
+    /// The number of parameters, including any which are not trained.
+    pub params: usize,
+    /// The number of parameters updated during training.
+    pub trainable: usize,
+    /// The size of the parameters in memory, in bytes.
+    pub param_bytes: usize,
+    /// The size of the gradients of the trainable parameters, in bytes.
+    pub grad_bytes: usize,
+    /// The layers composing this one, in the order they are evaluated.
+    pub children: Vec<LayerSummary>,
+}
+
+impl LayerSummary {
+    /// Describes a layer with the given number of parameters, all of which are trained.
+    pub fn leaf<E>(kind: &str, input: usize, output: usize, params: usize) -> Self {
+        let bytes = params * std::mem::size_of::<E>();
+        Self {
+            kind: kind.to_string(),
+            input,
+            output,
+            params,
+            trainable: params,
+            param_bytes: bytes,
+            grad_bytes: bytes,
+            children: vec![],
+        }
+    }
+
+    /// Describes a layer composed of the given layers, totalling their parameters.
+    pub fn composite(kind: &str, children: Vec<LayerSummary>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            input: children.first().map(|c| c.input).unwrap_or_default(),
+            output: children.last().map(|c| c.output).unwrap_or_default(),
+            params: children.iter().map(|c| c.params).sum(),
+            trainable: children.iter().map(|c| c.trainable).sum(),
+            param_bytes: children.iter().map(|c| c.param_bytes).sum(),
+            grad_bytes: children.iter().map(|c| c.grad_bytes).sum(),
+            children,
+        }
+    }
+
+    /// Marks this layer and the layers composing it as not being trained.
+    pub fn frozen(mut self) -> Self {
+        self.trainable = 0;
+        self.grad_bytes = 0;
+        self.children = self.children.into_iter().map(|c| c.frozen()).collect();
+        self
+    }
+
+    fn write_rows(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let name = format!("{:indent$}{}", "", self.kind, indent = 2 * depth);
+        writeln!(
+            f,
+            "{:<32} {:>8} {:>8} {:>12} {:>12} {:>10} {:>10}",
+            name,
+            format!("[{}]", self.input),
+            format!("[{}]", self.output),
+            self.params,
+            self.trainable,
+            bytes(self.param_bytes),
+            bytes(self.grad_bytes),
+        )?;
+        for c in self.children.iter() {
+            c.write_rows(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a number of bytes using binary units.
+fn bytes(n: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut v = n as f64;
+    let mut unit = 0;
+    while v >= 1024.0 && unit < UNITS.len() - 1 {
+        v /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", n)
+    } else {
+        format!("{:.1} {}", v, UNITS[unit])
+    }
+}
+
+/// An optimizer which keeps state for each trainable parameter between steps.
+pub trait OptimizerState {
+    /// The number of values kept for each trainable parameter, the same size as its gradient.
+    fn state_buffers(&self) -> usize;
+}
+
+/// A summary of the layers of a network, with their shapes, parameter counts
+/// and memory footprint. Displays as a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Summary {
+    /// Describes the network as a whole.
+    pub network: LayerSummary,
+    /// The number of values kept by the optimizer for each trainable parameter.
+    pub state_buffers: usize,
+}
+
+impl Summary {
+    /// Sets the optimizer used to train the network, which determines the size of
+    /// the optimizer state. Otherwise, one value per trainable parameter is assumed,
+    /// as kept by [Momentum](crate::optimizers::Momentum) or
+    /// [RMSProp](crate::optimizers::RMSProp) without momentum.
+    pub fn with_optimizer(mut self, optimizer: &impl OptimizerState) -> Self {
+        self.state_buffers = optimizer.state_buffers();
+        self
+    }
+
+    /// Returns the size of the optimizer state in bytes.
+    pub fn optimizer_bytes(&self) -> usize {
+        self.network.grad_bytes * self.state_buffers
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<32} {:>8} {:>8} {:>12} {:>12} {:>10} {:>10}",
+            "layer", "input", "output", "params", "trainable", "weights", "grads"
+        )?;
+        writeln!(f, "{}", "-".repeat(99))?;
+        // The top-level tuple is implied by the table, so only its layers are listed.
+        if self.network.children.is_empty() || self.network.kind != "sequential" {
+            self.network.write_rows(f, 0)?;
+        } else {
+            for c in self.network.children.iter() {
+                c.write_rows(f, 0)?;
+            }
+        }
+        writeln!(f, "{}", "-".repeat(99))?;
+        writeln!(
+            f,
+            "total params: {} ({} trainable)",
+            self.network.params, self.network.trainable
+        )?;
+        writeln!(
+            f,
+            "memory: {} weights, {} gradients, {} optimizer state",
+            bytes(self.network.param_bytes),
+            bytes(self.network.grad_bytes),
+            bytes(self.optimizer_bytes()),
+        )
+    }
+}
+
+/// A module which can describe its structure, given its input.
+pub trait Summarize<X>: Module<X> {
+    /// Describes the module, and any layers composing it.
+    fn describe(&self) -> LayerSummary;
+
+    /// Returns a summary of the module which displays as a table.
+    fn summary(&self) -> Summary {
+        Summary {
+            network: self.describe(),
+            state_buffers: 1,
+        }
+    }
+}
+
+impl<X: Elements, M: BaseModule + RevModule<X> + VisualizableUnit> Summarize<X> for M
+where
+    M::Output: Elements,
+{
+    fn describe(&self) -> LayerSummary {
+        let params = M::SelfGrads::empty().grad_iter().count();
+        LayerSummary::leaf::<<M::SelfGrads as Gradients>::Concrete>(
+            M::KIND,
+            X::LEN,
+            M::Output::LEN,
+            params,
+        )
+    }
+}
+
+macro_rules! summarize_tuple_impls {
+    ([$($name:ident),+] [$($idx:tt),*], $last:ident, [$($rev_tail:ident),*]) => {
+        impl<
+            Input,
+            $last:
+            $(Summarize::<$rev_tail ::Output>, $rev_tail: )*
+            Summarize<Input>
+        > Summarize<Input> for ($($name,)+) {
+            fn describe(&self) -> LayerSummary {
+                LayerSummary::composite(
+                    "sequential",
+                    vec![self.0.describe(), $(self.$idx.describe(),)*],
+                )
+            }
+        }
+    };
+}
+
+summarize_tuple_impls!([M1][], M1, []);
+summarize_tuple_impls!([M1, M2][1], M2, [M1]);
+summarize_tuple_impls!([M1, M2, M3] [1, 2], M3, [M2, M1]);
+summarize_tuple_impls!([M1, M2, M3, M4] [1, 2, 3], M4, [M3, M2, M1]);
+summarize_tuple_impls!([M1, M2, M3, M4, M5] [1, 2, 3, 4], M5, [M4, M3, M2, M1]);
+summarize_tuple_impls!([M1, M2, M3, M4, M5, M6] [1, 2, 3, 4, 5], M6, [M5, M4, M3, M2, M1]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::{Activation, Bias1d, Dense, Frozen, Residual, Softmax};
+    use crate::optimizers::TrainParams;
+    use crate::BackpropModule;
+
+    #[test]
+    fn test_summary() {
+        let network = (
+            (Dense::<f32, 2, 3>::default(), Bias1d::<f32, 3>::default()),
+            Activation::Relu,
+            Residual::<f32, 3, Dense<f32, 3, 3>>::default(),
+            Frozen::<f32, 3, Dense<f32, 3, 4>>::default(),
+            Softmax::default(),
+        );
+        let s = network.summary();
+        assert_eq!(s.network.input, 2);
+        assert_eq!(s.network.output, 4);
+        assert_eq!(s.network.params, 6 + 3 + 9 + 12);
+        assert_eq!(s.network.trainable, 6 + 3 + 9);
+        assert_eq!(s.network.param_bytes, 4 * 30);
+        assert_eq!(s.network.grad_bytes, 4 * 18);
+
+        let layers = &s.network.children;
+        assert_eq!(layers[0].kind, "sequential");
+        assert_eq!(layers[0].children[1].kind, "bias1d");
+        assert_eq!(layers[2].kind, "residual");
+        assert_eq!(layers[2].children[0].kind, "dense");
+        assert_eq!(layers[3].kind, "dense (frozen)");
+        assert_eq!((layers[3].input, layers[3].output), (3, 4));
+        assert_eq!(layers[4].output, 4);
+
+        let table = s.to_string();
+        assert!(table.contains("  bias1d"), "{}", table);
+        assert!(
+            table.contains("total params: 30 (18 trainable)"),
+            "{}",
+            table
+        );
+        assert!(
+            table.contains("memory: 120 B weights, 72 B gradients, 72 B optimizer state"),
+            "{}",
+            table
+        );
+
+        let updater = network.new_rmsprop_with_momentum(TrainParams::with_lr(1.0e-3), 0.8, 0.9);
+        assert_eq!(s.with_optimizer(&updater).optimizer_bytes(), 2 * 72);
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes(12), "12 B");
+        assert_eq!(bytes(1536), "1.5 KiB");
+        assert_eq!(bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -1,8 +1,10 @@
-use crate::{LineChart, ParamVisOpts, VisualizableNetwork};
+use crate::{ChartLayout, MultiChart, ParamVisOpts, SeriesOpts, VisualizableNetwork};
 use raqote::{DrawTarget, SolidSource};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, RecvError, Sender};
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
 
 use fontdue::layout::LayoutSettings;
 
@@ -13,41 +15,106 @@ pub enum RecorderErr {
     Run(std::io::Error),
     Write(std::io::Error),
     Flush(std::io::Error),
+    /// Encoding a GIF frame failed.
+    Gif(gif::EncodingError),
+    /// The `ffmpeg` binary could not be started, so frames were written as a
+    /// GIF to the `fallback` path instead.
+    FfmpegUnavailable {
+        err: std::io::Error,
+        fallback: PathBuf,
+    },
+    /// `ffmpeg` exited unsuccessfully, for instance because it lacks libx264.
+    FfmpegFailed(std::process::ExitStatus),
 }
 
+impl std::fmt::Display for RecorderErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecorderErr::Recv(e) => write!(f, "recording ended: {}", e),
+            RecorderErr::Pipe(e) => write!(f, "creating pipe to ffmpeg: {}", e),
+            RecorderErr::Run(e) => write!(f, "starting ffmpeg: {}", e),
+            RecorderErr::Write(e) => write!(f, "writing frame: {}", e),
+            RecorderErr::Flush(e) => write!(f, "flushing frames: {}", e),
+            RecorderErr::Gif(e) => write!(f, "encoding gif: {}", e),
+            RecorderErr::FfmpegUnavailable { err, fallback } => write!(
+                f,
+                "could not start ffmpeg ({}), wrote a gif to {} instead",
+                err,
+                fallback.display()
+            ),
+            RecorderErr::FfmpegFailed(status) => write!(f, "ffmpeg failed: {}", status),
+        }
+    }
+}
+
+impl std::error::Error for RecorderErr {}
+
+/// Where a [Recorder] writes the frames it renders.
+#[derive(Debug, Clone)]
+pub enum Sink {
+    /// An H.264 MP4 video, encoded by an external `ffmpeg` binary.
+    ///
+    /// If `ffmpeg` cannot be started, frames are written as a GIF next to the
+    /// given path and [RecorderErr::FfmpegUnavailable] is returned from [Recorder::wait].
+    Mp4(PathBuf),
+    /// An animated GIF, encoded without any external tools.
+    Gif(PathBuf),
+    /// A directory of numbered PNG files, one per frame.
+    Frames(PathBuf),
+}
+
+/// A frame sent to the render thread: the epoch, values of named series and the network.
+type Checkpoint<N> = (f32, Vec<(String, f32)>, N);
+
 /// Records training progress to a video.
 pub struct Recorder<N: VisualizableNetwork<DrawTarget> + std::marker::Send> {
     marker: std::marker::PhantomData<N>,
-    sender: Option<Sender<(f32, f32, N)>>,
-    final_error: Arc<Mutex<Option<RecorderErr>>>,
+    sender: Option<Sender<Checkpoint<N>>>,
+    handle: Option<JoinHandle<Option<RecorderErr>>>,
 }
 
 impl<N: VisualizableNetwork<DrawTarget> + std::marker::Send + 'static> Recorder<N> {
-    pub fn mp4(path: &str, size: (usize, usize), mut opts: ParamVisOpts) -> Self {
-        let (sender, receiver) = channel();
-        let err = Arc::<Mutex<Option<RecorderErr>>>::default();
+    /// Records to an MP4 at 30 fps, see [Sink::Mp4].
+    pub fn mp4(path: &str, size: (usize, usize), opts: ParamVisOpts) -> Self {
+        Self::new(Sink::Mp4(path.into()), size, 30, opts)
+    }
 
-        let s = Self {
-            sender: Some(sender),
-            marker: Default::default(),
-            final_error: err.clone(),
-        };
+    /// Records to an animated GIF at 30 fps.
+    pub fn gif(path: &str, size: (usize, usize), opts: ParamVisOpts) -> Self {
+        Self::new(Sink::Gif(path.into()), size, 30, opts)
+    }
+
+    /// Records frames to the given sink, played back at `fps` frames a second.
+    ///
+    /// The loss is charted as a single smoothed series.
+    pub fn new(sink: Sink, size: (usize, usize), fps: u32, opts: ParamVisOpts) -> Self {
+        let chart = MultiChart::new(ChartLayout::Overlay)
+            .caption("Loss")
+            .series("loss", SeriesOpts::default().smoothing(0.92));
+        Self::with_chart(sink, size, fps, opts, chart)
+    }
 
-        let path = path.to_string();
-        thread::spawn(move || {
-            let mut err = err.lock().unwrap();
-            let mut loss_chart = LineChart::new(false, Some(0.92));
+    /// Records frames to the given sink, drawing the series pushed with
+    /// [Recorder::push_series] onto the given chart.
+    pub fn with_chart(
+        sink: Sink,
+        size: (usize, usize),
+        fps: u32,
+        mut opts: ParamVisOpts,
+        mut chart: MultiChart,
+    ) -> Self {
+        let (sender, receiver) = channel::<Checkpoint<N>>();
 
+        let handle = thread::spawn(move || {
             // Offset parameters render to the right of the screen
             opts.offset.0 += (size.0 / 2) as f32;
 
-            let res = make_video(size, path.as_str(), |dt, _n| match receiver.recv() {
-                Err(e) => {
-                    *err = Some(RecorderErr::Recv(e));
-                    return false;
-                }
-                Ok((epoch, loss, network)) => {
-                    loss_chart.push(epoch, loss);
+            let res = make_video(size, &sink, fps, |dt, _n| match receiver.recv() {
+                Err(e) => Err(RecorderErr::Recv(e)),
+                Ok((epoch, values, network)) => {
+                    for (name, v) in values {
+                        chart.push(&name, epoch, v);
+                    }
 
                     // Render the parameter visualization, right
                     dt.clear(SolidSource::from_unpremultiplied_argb(
@@ -55,8 +122,14 @@ impl<N: VisualizableNetwork<DrawTarget> + std::marker::Send + 'static> Recorder<
                     ));
                     network.visualize(dt, &mut opts.clone());
 
+                    let latest = chart
+                        .latest()
+                        .map(|(name, v)| format!("{}: {:07.5}", name, v))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
                     // Render the plot, left
-                    loss_chart.draw(dt, 5, size.0 as u32 / 2, 50, 50);
+                    chart.draw(dt, 5, size.0 as u32 / 2, 50, 50);
 
                     opts.font.raster(
                         &LayoutSettings {
@@ -68,112 +141,367 @@ impl<N: VisualizableNetwork<DrawTarget> + std::marker::Send + 'static> Recorder<
                             vertical_align: fontdue::layout::VerticalAlign::Bottom,
                             ..LayoutSettings::default()
                         },
-                        format!("L: {:07.5} - N: {:05}", loss, epoch).as_str(),
+                        format!("{} - N: {:05}", latest, epoch).as_str(),
                         30.0,
                         (10, 10, 10),
                         dt,
                     );
 
-                    return true;
+                    Ok(())
                 }
             });
 
-            if let Err(e) = res {
-                *err = Some(e);
-            }
+            res.err()
         });
 
-        s
+        Self {
+            sender: Some(sender),
+            marker: Default::default(),
+            handle: Some(handle),
+        }
     }
 
     /// Push sends a checkpoint and its corresponding loss value to the recorder.
     ///
     /// Each call to push corresponds with a single frame.
     pub fn push(&mut self, epoch: f32, loss: f32, network: N) {
+        self.push_series(epoch, &[("loss", loss)], network);
+    }
+
+    /// Sends a checkpoint along with values of named series, such as the training
+    /// and validation loss, to the recorder.
+    ///
+    /// Each call corresponds with a single frame. Series may be left out of frames,
+    /// for instance when the validation loss is only computed every few epochs.
+    pub fn push_series(&mut self, epoch: f32, values: &[(&str, f32)], network: N) {
         if let Some(sender) = &self.sender {
-            sender.send((epoch, loss, network)).unwrap();
+            let values = values.iter().map(|(n, v)| (n.to_string(), *v)).collect();
+            sender.send((epoch, values, network)).unwrap();
         }
     }
 
     /// Blocks till the render is complete and returns the error. Must only be called once.
+    ///
+    /// A completed recording reports [RecorderErr::Recv], once all frames are written.
     pub fn wait(&mut self) -> Option<RecorderErr> {
         self.sender = None;
-        self.final_error.lock().unwrap().take()
+        self.handle
+            .take()
+            .and_then(|h| h.join().expect("render thread panicked"))
     }
 }
 
-/// Renders a video file by calling the given function to generate every frame.
-///
-/// The callback function should write the frame in bgra (4x8 bits a pixel) format,
-/// and return true if the callback should be called again to generate the next
-/// frame.
+/// Encodes rendered frames to a [Sink].
+enum Encoder {
+    #[cfg(not(target_os = "windows"))]
+    Ffmpeg {
+        child: std::process::Child,
+        writer: os_pipe::PipeWriter,
+        n: usize,
+    },
+    Gif {
+        enc: gif::Encoder<BufWriter<File>>,
+        delay: u16,
+        rgba: Vec<u8>,
+    },
+    Frames {
+        dir: PathBuf,
+        n: usize,
+    },
+}
+
+impl Encoder {
+    /// Opens the sink, returning the encoder along with the error which caused
+    /// it to fall back to a different sink, if any.
+    fn open(
+        sink: &Sink,
+        size: (usize, usize),
+        fps: u32,
+    ) -> Result<(Self, Option<RecorderErr>), RecorderErr> {
+        match sink {
+            Sink::Mp4(path) => match Self::ffmpeg(path, size, fps) {
+                Ok(enc) => Ok((enc, None)),
+                Err(RecorderErr::Run(err)) => {
+                    let fallback = path.with_extension("gif");
+                    let (enc, _) = Self::open(&Sink::Gif(fallback.clone()), size, fps)?;
+                    Ok((enc, Some(RecorderErr::FfmpegUnavailable { err, fallback })))
+                }
+                Err(e) => Err(e),
+            },
+            Sink::Gif(path) => {
+                let f = File::create(path).map_err(RecorderErr::Write)?;
+                let mut enc =
+                    gif::Encoder::new(BufWriter::new(f), size.0 as u16, size.1 as u16, &[])
+                        .map_err(RecorderErr::Gif)?;
+                enc.set_repeat(gif::Repeat::Infinite)
+                    .map_err(RecorderErr::Gif)?;
+                Ok((
+                    Encoder::Gif {
+                        enc,
+                        // GIF frame delays are in hundredths of a second.
+                        delay: (100.0 / fps.max(1) as f32).round().max(1.0) as u16,
+                        rgba: Vec::with_capacity(size.0 * size.1 * 4),
+                    },
+                    None,
+                ))
+            }
+            Sink::Frames(dir) => {
+                std::fs::create_dir_all(dir).map_err(RecorderErr::Write)?;
+                Ok((
+                    Encoder::Frames {
+                        dir: dir.clone(),
+                        n: 0,
+                    },
+                    None,
+                ))
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn ffmpeg(_path: &Path, _size: (usize, usize), _fps: u32) -> Result<Self, RecorderErr> {
+        Err(RecorderErr::Run(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "piping frames to ffmpeg is not supported on windows",
+        )))
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    fn ffmpeg(path: &Path, size: (usize, usize), fps: u32) -> Result<Self, RecorderErr> {
+        let (w, h) = size;
+        let (reader, writer) = os_pipe::pipe().map_err(RecorderErr::Pipe)?;
+
+        use command_fds::CommandFdExt;
+        let mut command = std::process::Command::new("ffmpeg");
+        command
+            .args([
+                "-f",
+                "rawvideo",
+                "-video_size",
+                &format!("{}x{}", w, h),
+                "-pixel_format",
+                "bgra",
+                "-framerate",
+                &format!("{}/1", fps),
+            ])
+            .arg("-i")
+            .arg("pipe:3")
+            .args([
+                "-c:v",
+                "libx264",
+                "-crf",
+                "21",
+                "-profile:v",
+                "baseline",
+                "-level",
+                "3.0",
+                "-pix_fmt",
+                "yuv420p",
+                "-movflags",
+                "faststart",
+            ])
+            .arg("-y")
+            .arg(path);
+
+        command
+            .fd_mappings(vec![command_fds::FdMapping {
+                parent_fd: reader.into(),
+                child_fd: 3,
+            }])
+            .unwrap();
+
+        let child = command.spawn().map_err(RecorderErr::Run)?;
+        Ok(Encoder::Ffmpeg {
+            child,
+            writer,
+            n: 0,
+        })
+    }
+
+    fn write(&mut self, dt: &DrawTarget) -> Result<(), RecorderErr> {
+        match self {
+            #[cfg(not(target_os = "windows"))]
+            Encoder::Ffmpeg { writer, n, .. } => {
+                use std::io::Write;
+                writer
+                    .write_all(dt.get_data_u8())
+                    .map_err(RecorderErr::Write)?;
+                *n += 1;
+                if *n == 4 {
+                    writer.flush().map_err(RecorderErr::Flush)?; // get ffmpeg encoding early on
+                }
+            }
+            Encoder::Gif { enc, delay, rgba } => {
+                rgba.clear();
+                rgba.extend(dt.get_data().iter().flat_map(|p| {
+                    let [b, g, r, _] = p.to_le_bytes();
+                    [r, g, b, 0xff]
+                }));
+                let mut frame =
+                    gif::Frame::from_rgba_speed(dt.width() as u16, dt.height() as u16, rgba, 10);
+                frame.delay = *delay;
+                enc.write_frame(&frame).map_err(RecorderErr::Gif)?;
+            }
+            Encoder::Frames { dir, n } => {
+                dt.write_png(dir.join(format!("frame_{:05}.png", n)))
+                    .map_err(|e| RecorderErr::Write(std::io::Error::other(e)))?;
+                *n += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), RecorderErr> {
+        match self {
+            #[cfg(not(target_os = "windows"))]
+            Encoder::Ffmpeg {
+                mut child, writer, ..
+            } => {
+                drop(writer);
+                let status = child.wait().map_err(RecorderErr::Run)?;
+                if !status.success() {
+                    return Err(RecorderErr::FfmpegFailed(status));
+                }
+            }
+            Encoder::Gif { enc, .. } => {
+                use std::io::Write;
+                enc.into_inner()
+                    .and_then(|mut w| w.flush())
+                    .map_err(RecorderErr::Flush)?;
+            }
+            Encoder::Frames { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Renders a video by calling the given function to generate every frame.
 ///
-/// The video is encoded as an MP4 at 30 fps.
-#[cfg(not(target_os = "windows"))]
-fn make_video<F: FnMut(&mut DrawTarget, usize) -> bool>(
+/// The callback function should draw the frame, and return an error once there are
+/// no more frames to draw. Frames drawn till then are written to the sink, and the
+/// error is returned, unless the sink fell back or failed to finish.
+fn make_video<F: FnMut(&mut DrawTarget, usize) -> Result<(), RecorderErr>>(
     size: (usize, usize),
-    out_path: &str,
+    sink: &Sink,
+    fps: u32,
     mut frame_cb: F,
 ) -> Result<(), RecorderErr> {
     let (w, h) = size;
     let mut dt = DrawTarget::new(w as i32, h as i32);
-
-    let (reader, mut writer) = os_pipe::pipe().map_err(RecorderErr::Pipe)?;
-
-    use command_fds::CommandFdExt;
-    let mut command = std::process::Command::new("ffmpeg");
-    command
-        .args([
-            "-f",
-            "rawvideo",
-            "-video_size",
-            &format!("{}x{}", w, h),
-            "-pixel_format",
-            "bgra",
-            "-framerate",
-            "30/1",
-        ])
-        .arg("-i")
-        .arg("pipe:3")
-        .args([
-            "-c:v",
-            "libx264",
-            "-crf",
-            "21",
-            "-profile:v",
-            "baseline",
-            "-level",
-            "3.0",
-            "-pix_fmt",
-            "yuv420p",
-            "-movflags",
-            "faststart",
-        ])
-        .arg("-y")
-        .arg(out_path);
-
-    command
-        .fd_mappings(vec![command_fds::FdMapping {
-            parent_fd: reader.into(),
-            child_fd: 3,
-        }])
-        .unwrap();
-
-    let mut child = command.spawn().map_err(RecorderErr::Run)?;
+    let (mut enc, fallback) = Encoder::open(sink, size, fps)?;
 
     let mut n = 0;
-    while frame_cb(&mut dt, n) {
-        use std::io::Write;
-        writer
-            .write_all(dt.get_data_u8())
-            .map_err(RecorderErr::Write)?;
+    let end = loop {
+        if let Err(e) = frame_cb(&mut dt, n) {
+            break e;
+        }
+        if let Err(e) = enc.write(&dt) {
+            let _ = enc.finish();
+            return Err(e);
+        }
         n += 1;
-        if n == 4 {
-            writer.flush().map_err(RecorderErr::Flush)?; // get ffmpeg encoding early on
+    };
+
+    enc.finish()?;
+    Err(fallback.unwrap_or(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidx_core::layers as l;
+
+    fn record(sink: Sink) -> Option<RecorderErr> {
+        let network = (
+            l::Dense::<f32, 2, 3>::default(),
+            l::Activation::<f32>::default(),
+        );
+        let mut rec = Recorder::new(sink, (160, 120), 10, ParamVisOpts::small());
+        for i in 0..3 {
+            rec.push(i as f32, 1.0 / (i + 1) as f32, network.clone());
+        }
+        rec.wait()
+    }
+
+    #[test]
+    fn test_gif_sink() {
+        let path = std::env::temp_dir().join("minidx_vis_test_gif_sink.gif");
+        let res = record(Sink::Gif(path.clone()));
+        assert!(matches!(res, Some(RecorderErr::Recv(_))), "got {:?}", res);
+
+        let mut dec = gif::DecodeOptions::new()
+            .read_info(File::open(&path).unwrap())
+            .unwrap();
+        assert_eq!((dec.width(), dec.height()), (160, 120));
+        let mut delays = vec![];
+        while let Some(frame) = dec.read_next_frame().unwrap() {
+            delays.push(frame.delay);
         }
+        assert_eq!(delays, vec![10, 10, 10]);
     }
 
-    drop(writer);
-    child.wait().unwrap();
-    Ok(())
+    #[test]
+    fn test_frames_sink() {
+        let dir = std::env::temp_dir().join("minidx_vis_test_frames_sink");
+        let _ = std::fs::remove_dir_all(&dir);
+        let res = record(Sink::Frames(dir.clone()));
+        assert!(matches!(res, Some(RecorderErr::Recv(_))), "got {:?}", res);
+
+        let mut frames = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        frames.sort();
+        assert_eq!(
+            frames,
+            vec!["frame_00000.png", "frame_00001.png", "frame_00002.png"]
+        );
+    }
+
+    #[test]
+    fn test_mp4_fallback() {
+        let path = std::env::temp_dir().join("minidx_vis_test_mp4_fallback.mp4");
+        match record(Sink::Mp4(path.clone())) {
+            // ffmpeg is installed, or it failed for another reason.
+            Some(RecorderErr::Recv(_)) | Some(RecorderErr::FfmpegFailed(_)) => {}
+            Some(RecorderErr::FfmpegUnavailable { fallback, .. }) => {
+                assert_eq!(fallback, path.with_extension("gif"));
+                assert!(std::fs::metadata(fallback).unwrap().len() > 0);
+            }
+            res => panic!("got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_push_series() {
+        let dir = std::env::temp_dir().join("minidx_vis_test_push_series");
+        let _ = std::fs::remove_dir_all(&dir);
+        let chart = MultiChart::new(ChartLayout::SmallMultiples)
+            .series("train", SeriesOpts::default().log(true).smoothing(0.9))
+            .series("val", SeriesOpts::default().log(true));
+        let mut rec = Recorder::with_chart(
+            Sink::Frames(dir.clone()),
+            (320, 240),
+            10,
+            ParamVisOpts::small(),
+            chart,
+        );
+
+        let network = l::Dense::<f32, 2, 3>::default();
+        for i in 0..4 {
+            let train = ("train", 1.0 / (i + 1) as f32);
+            if i % 2 == 0 {
+                rec.push_series(
+                    i as f32,
+                    &[train, ("val", 2.0 / (i + 1) as f32)],
+                    network.clone(),
+                );
+            } else {
+                rec.push_series(i as f32, &[train], network.clone());
+            }
+        }
+        let res = rec.wait();
+        assert!(matches!(res, Some(RecorderErr::Recv(_))), "got {:?}", res);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+    }
 }
@@ -0,0 +1,176 @@
+use crate::VisFont;
+use fontdue::layout::{HorizontalAlign, LayoutSettings, VerticalAlign};
+use raqote::{DrawOptions, DrawTarget, PathBuilder, SolidSource, Source, StrokeStyle};
+
+/// A surface which visualizations can be painted onto.
+pub trait Canvas {
+    /// Fills a box of the given color, optionally with a black border.
+    fn paint_box(&mut self, tl: (f32, f32), size: (f32, f32), rgb: (u8, u8, u8), border: bool);
+
+    /// Draws text positioned and aligned within the given layout.
+    fn paint_text(
+        &mut self,
+        font: &mut VisFont,
+        layout: &LayoutSettings,
+        text: &str,
+        font_size: f32,
+        rgb: (u8, u8, u8),
+    );
+}
+
+impl Canvas for DrawTarget {
+    fn paint_box(&mut self, tl: (f32, f32), size: (f32, f32), rgb: (u8, u8, u8), border: bool) {
+        let mut pb = PathBuilder::new();
+        pb.move_to(tl.0, tl.1);
+        pb.line_to(tl.0 + size.0, tl.1);
+        pb.line_to(tl.0 + size.0, tl.1 + size.1);
+        pb.line_to(tl.0, tl.1 + size.1);
+        pb.line_to(tl.0, tl.1);
+        let p = pb.finish();
+
+        self.fill(
+            &p,
+            &Source::Solid(SolidSource::from_unpremultiplied_argb(
+                0xFF, rgb.0, rgb.1, rgb.2,
+            )),
+            &DrawOptions::new(),
+        );
+        if border {
+            self.stroke(
+                &p,
+                &Source::Solid(SolidSource::from_unpremultiplied_argb(0xFF, 0, 0, 0)),
+                &StrokeStyle {
+                    width: 1.0,
+                    ..StrokeStyle::default()
+                },
+                &DrawOptions::new(),
+            );
+        }
+    }
+
+    fn paint_text(
+        &mut self,
+        font: &mut VisFont,
+        layout: &LayoutSettings,
+        text: &str,
+        font_size: f32,
+        rgb: (u8, u8, u8),
+    ) {
+        font.raster(layout, text, font_size, rgb, self);
+    }
+}
+
+/// An SVG document, which visualizations can be painted onto as vector graphics.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    size: (f32, f32),
+    elements: String,
+}
+
+impl Svg {
+    /// Creates an empty document of the given size, filled with the background color.
+    pub fn new(size: (f32, f32), background: (u8, u8, u8)) -> Self {
+        let mut s = Self {
+            size,
+            elements: String::new(),
+        };
+        s.paint_box((0.0, 0.0), size, background, false);
+        s
+    }
+}
+
+impl std::fmt::Display for Svg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+            self.size.0, self.size.1, self.size.0, self.size.1
+        )?;
+        f.write_str(&self.elements)?;
+        writeln!(f, "</svg>")
+    }
+}
+
+impl Canvas for Svg {
+    fn paint_box(&mut self, tl: (f32, f32), size: (f32, f32), rgb: (u8, u8, u8), border: bool) {
+        self.elements.push_str(&format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="rgb({},{},{})"{}/>"#,
+            tl.0,
+            tl.1,
+            size.0,
+            size.1,
+            rgb.0,
+            rgb.1,
+            rgb.2,
+            if border { r#" stroke="black""# } else { "" },
+        ));
+        self.elements.push('\n');
+    }
+
+    fn paint_text(
+        &mut self,
+        _font: &mut VisFont,
+        layout: &LayoutSettings,
+        text: &str,
+        font_size: f32,
+        rgb: (u8, u8, u8),
+    ) {
+        let (x, anchor) = match (layout.horizontal_align, layout.max_width) {
+            (HorizontalAlign::Center, Some(w)) => (layout.x + w / 2.0, "middle"),
+            (HorizontalAlign::Right, Some(w)) => (layout.x + w, "end"),
+            _ => (layout.x, "start"),
+        };
+        let (y, baseline) = match (layout.vertical_align, layout.max_height) {
+            (VerticalAlign::Middle, Some(h)) => (layout.y + h / 2.0, "central"),
+            (VerticalAlign::Bottom, Some(h)) => (layout.y + h, "text-after-edge"),
+            _ => (layout.y, "text-before-edge"),
+        };
+
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '&' => escaped.push_str("&amp;"),
+                c => escaped.push(c),
+            }
+        }
+        self.elements.push_str(&format!(
+            r#"<text x="{}" y="{}" font-family="monospace" font-size="{}" text-anchor="{}" dominant-baseline="{}" fill="rgb({},{},{})">{}</text>"#,
+            x, y, font_size, anchor, baseline, rgb.0, rgb.1, rgb.2, escaped,
+        ));
+        self.elements.push('\n');
+    }
+}
+
+/// Records the extent of everything painted, to size a canvas before painting it.
+#[derive(Debug, Clone, Default)]
+pub struct Extent(pub (f32, f32));
+
+impl Extent {
+    fn include(&mut self, br: (f32, f32)) {
+        self.0 = (self.0 .0.max(br.0), self.0 .1.max(br.1));
+    }
+}
+
+impl Canvas for Extent {
+    fn paint_box(&mut self, tl: (f32, f32), size: (f32, f32), _rgb: (u8, u8, u8), _b: bool) {
+        self.include((tl.0 + size.0, tl.1 + size.1));
+    }
+
+    fn paint_text(
+        &mut self,
+        _font: &mut VisFont,
+        layout: &LayoutSettings,
+        text: &str,
+        font_size: f32,
+        _rgb: (u8, u8, u8),
+    ) {
+        // Glyphs of monospace fonts are about 0.6 of the font size wide.
+        let w = layout
+            .max_width
+            .unwrap_or(0.6 * font_size * text.chars().count() as f32);
+        let h = layout.max_height.unwrap_or(font_size);
+        self.include((layout.x + w, layout.y + h));
+    }
+}
@@ -1,10 +1,13 @@
 use minidx_core::misc::ExpAvg;
-use raqote::DrawTarget;
+use raqote::{DrawTarget, SolidSource};
 
 use plotters::backend::{BGRXPixel, BitMapBackend};
+use plotters::coord::Shift;
 use plotters::prelude::*;
+use std::ops::Range;
 
-pub(crate) struct LineChart {
+/// A line chart of a single series, such as the loss over training.
+pub struct LineChart {
     min: (f32, f32),
     max: (f32, f32),
     data: Vec<(f32, f32)>,
@@ -14,6 +17,8 @@ pub(crate) struct LineChart {
 }
 
 impl LineChart {
+    /// Creates an empty chart, optionally with a logarithmic y-axis and
+    /// exponential smoothing of the plotted values.
     pub fn new(log: bool, smoothing_alpha: Option<f32>) -> Self {
         Self {
             min: (f32::INFINITY, f32::INFINITY),
@@ -24,12 +29,14 @@ impl LineChart {
         }
     }
 
+    /// Adds a point to the series.
     pub fn push(&mut self, x: f32, y: f32) {
         self.min = (self.min.0.min(x), self.min.1.min(y));
         self.max = (self.max.0.max(x), self.max.1.max(y));
         self.data.push((x, y));
     }
 
+    /// Draws the chart onto the draw target, inset by the given margins.
     pub fn draw(
         &self,
         dt: &mut DrawTarget,
@@ -95,6 +102,19 @@ impl LineChart {
                 .draw_series(LineSeries::new(self.series(), &RED))
                 .unwrap();
         };
+        drop(chart_area);
+        make_opaque(dt);
+    }
+
+    /// Renders the chart onto a white canvas of the given size, and writes it to a PNG file.
+    pub fn save_png(&self, path: &str, size: (usize, usize)) -> std::io::Result<()> {
+        let mut dt = DrawTarget::new(size.0 as i32, size.1 as i32);
+        dt.clear(SolidSource::from_unpremultiplied_argb(
+            0xff, 0xff, 0xff, 0xff,
+        ));
+        self.draw(&mut dt, 5, 20, 10, 10);
+        dt.write_png(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
     }
 
     fn series(&self) -> impl Iterator<Item = (f32, f32)> + use<'_> {
@@ -104,4 +124,348 @@ impl LineChart {
             (*x, ea.get().unwrap())
         })
     }
+
+    /// Returns the smoothed series, with values mapped to their base-10 logarithm
+    /// if the chart is logarithmic.
+    fn plotted(&self) -> impl Iterator<Item = (f32, f32)> + use<'_> {
+        let log = self.log;
+        self.series().map(move |(x, y)| {
+            if log {
+                (x, y.max(f32::MIN_POSITIVE).log10())
+            } else {
+                (x, y)
+            }
+        })
+    }
+}
+
+/// Options for plotting a series in a [MultiChart].
+#[derive(Debug, Clone, Default)]
+pub struct SeriesOpts {
+    log: bool,
+    smoothing_alpha: Option<f32>,
+    secondary: bool,
+    color: Option<(u8, u8, u8)>,
+}
+
+impl SeriesOpts {
+    /// Plots the series on a logarithmic y-axis.
+    ///
+    /// Series sharing an axis should agree on this, as the axis labels follow
+    /// the first series plotted on it.
+    pub fn log(mut self, log: bool) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Smooths the plotted values with an exponential average of the given factor.
+    pub fn smoothing(mut self, alpha: f32) -> Self {
+        self.smoothing_alpha = Some(alpha);
+        self
+    }
+
+    /// Plots the series against a second y-axis on the right of the chart,
+    /// for series with a different range such as the accuracy or learning rate.
+    pub fn secondary_axis(mut self, secondary: bool) -> Self {
+        self.secondary = secondary;
+        self
+    }
+
+    /// Sets the color of the series, rather than picking one from a palette.
+    pub fn color(mut self, rgb: (u8, u8, u8)) -> Self {
+        self.color = Some(rgb);
+        self
+    }
+}
+
+/// How the series of a [MultiChart] are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartLayout {
+    /// All series are drawn on one chart, with a legend.
+    #[default]
+    Overlay,
+    /// Each series is drawn on its own chart, stacked vertically.
+    SmallMultiples,
+}
+
+const PALETTE: [(u8, u8, u8); 6] = [
+    (255, 0, 0),
+    (31, 119, 180),
+    (44, 160, 44),
+    (148, 103, 189),
+    (255, 127, 14),
+    (23, 190, 207),
+];
+
+struct Series {
+    name: String,
+    color: RGBColor,
+    secondary: bool,
+    chart: LineChart,
+}
+
+/// A line chart of several named series, such as the training and validation loss.
+pub struct MultiChart {
+    series: Vec<Series>,
+    layout: ChartLayout,
+    caption: Option<String>,
+}
+
+impl MultiChart {
+    /// Creates a chart without any series.
+    pub fn new(layout: ChartLayout) -> Self {
+        Self {
+            series: Vec::with_capacity(4),
+            layout,
+            caption: None,
+        }
+    }
+
+    /// Sets the caption drawn above the chart.
+    pub fn caption(mut self, caption: &str) -> Self {
+        self.caption = Some(caption.to_string());
+        self
+    }
+
+    /// Adds a series with the given options.
+    ///
+    /// Series are otherwise added with default options when first pushed to.
+    pub fn series(mut self, name: &str, opts: SeriesOpts) -> Self {
+        self.add_series(name, opts);
+        self
+    }
+
+    fn add_series(&mut self, name: &str, opts: SeriesOpts) -> &mut Series {
+        let (r, g, b) = opts
+            .color
+            .unwrap_or(PALETTE[self.series.len() % PALETTE.len()]);
+        self.series.push(Series {
+            name: name.to_string(),
+            color: RGBColor(r, g, b),
+            secondary: opts.secondary,
+            chart: LineChart::new(opts.log, opts.smoothing_alpha),
+        });
+        self.series.last_mut().unwrap()
+    }
+
+    /// Adds a point to the named series.
+    ///
+    /// Series need not have a point at every x, so a validation loss computed
+    /// every few epochs can be plotted alongside the training loss.
+    pub fn push(&mut self, name: &str, x: f32, y: f32) {
+        match self.series.iter_mut().position(|s| s.name == name) {
+            Some(i) => self.series[i].chart.push(x, y),
+            None => self
+                .add_series(name, SeriesOpts::default())
+                .chart
+                .push(x, y),
+        }
+    }
+
+    /// Returns the most recent value of each series which has any.
+    pub fn latest(&self) -> impl Iterator<Item = (&str, f32)> + use<'_> {
+        self.series
+            .iter()
+            .filter_map(|s| s.chart.data.last().map(|(_, y)| (s.name.as_str(), *y)))
+    }
+
+    /// Draws the chart onto the draw target, inset by the given margins.
+    pub fn draw(
+        &self,
+        dt: &mut DrawTarget,
+        margin_left: u32,
+        margin_right: u32,
+        margin_top: u32,
+        margin_bottom: u32,
+    ) {
+        crate::font::ensure_plotters_font_registered();
+
+        let size = (dt.width() as u32, dt.height() as u32);
+        let area = BitMapBackend::<BGRXPixel>::with_buffer_and_format(dt.get_data_u8_mut(), size)
+            .unwrap()
+            .into_drawing_area()
+            .margin(margin_top, margin_bottom, margin_left, margin_right);
+
+        match self.layout {
+            ChartLayout::Overlay => {
+                let series = self.series.iter().collect::<Vec<_>>();
+                let caption = self.caption.as_deref().map(|c| (c, 40));
+                draw_series(&area, &series, caption);
+            }
+            ChartLayout::SmallMultiples => {
+                let areas = area.split_evenly((self.series.len().max(1), 1));
+                for (s, area) in self.series.iter().zip(areas.iter()) {
+                    draw_series(area, &[s], Some((&s.name, 20)));
+                }
+            }
+        }
+        drop(area);
+        make_opaque(dt);
+    }
+
+    /// Renders the chart onto a white canvas of the given size, and writes it to a PNG file.
+    pub fn save_png(&self, path: &str, size: (usize, usize)) -> std::io::Result<()> {
+        let mut dt = DrawTarget::new(size.0 as i32, size.1 as i32);
+        dt.clear(SolidSource::from_unpremultiplied_argb(
+            0xff, 0xff, 0xff, 0xff,
+        ));
+        self.draw(&mut dt, 5, 20, 10, 10);
+        dt.write_png(path).map_err(std::io::Error::other)
+    }
+}
+
+/// Sets the alpha of every pixel, which plotters clears when filling
+/// lines and rectangles with solid colors.
+fn make_opaque(dt: &mut DrawTarget) {
+    dt.get_data_mut().iter_mut().for_each(|p| *p |= 0xff00_0000);
+}
+
+/// Formats an axis label, switching to scientific notation for small values.
+fn axis_label(v: f32) -> String {
+    if v != 0.0 && v.abs() < 0.01 {
+        format!("{:.1e}", v)
+    } else {
+        format!("{:.3}", v)
+    }
+}
+
+/// Returns the range spanned by values, padded if they are all the same.
+fn span(values: impl Iterator<Item = f32>) -> Range<f32> {
+    let (min, max) = values
+        .filter(|v| v.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if min > max {
+        0.0..1.0
+    } else if min == max {
+        let pad = if min == 0.0 { 0.5 } else { min.abs() / 2.0 };
+        min - pad..max + pad
+    } else {
+        min..max
+    }
+}
+
+/// Draws series onto a single chart, with series on the secondary axis (if any)
+/// scaled against a y-axis on the right.
+fn draw_series(
+    area: &DrawingArea<BitMapBackend<'_, BGRXPixel>, Shift>,
+    series: &[&Series],
+    caption: Option<(&str, u32)>,
+) {
+    let (mut primary, mut secondary): (Vec<&Series>, Vec<&Series>) =
+        series.iter().partition(|s| !s.secondary);
+    if primary.is_empty() {
+        std::mem::swap(&mut primary, &mut secondary);
+    }
+
+    let x_range = span(
+        series
+            .iter()
+            .flat_map(|s| s.chart.data.iter().map(|(x, _)| *x)),
+    );
+    let y_range = |series: &[&Series]| {
+        span(
+            series
+                .iter()
+                .flat_map(|s| s.chart.plotted().map(|(_, y)| y)),
+        )
+    };
+    let is_log = |series: &[&Series]| series.first().map(|s| s.chart.log).unwrap_or(false);
+    let (log, secondary_log) = (is_log(&primary), is_log(&secondary));
+    let format = |log: bool, v: f32| axis_label(if log { 10f32.powf(v) } else { v });
+
+    let mut builder = ChartBuilder::on(area);
+    builder.x_label_area_size(35).y_label_area_size(45);
+    if !secondary.is_empty() {
+        builder.right_y_label_area_size(45);
+    }
+    if let Some((caption, size)) = caption {
+        builder.caption(caption, ("sans-serif", size));
+    }
+    let mut chart = builder
+        .build_cartesian_2d(x_range.clone(), y_range(&primary))
+        .unwrap()
+        .set_secondary_coord(x_range, y_range(&secondary));
+
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .y_labels(5)
+        .x_label_formatter(&|v| format!("{}", v))
+        .y_label_formatter(&|v| format(log, *v))
+        .draw()
+        .unwrap();
+    if !secondary.is_empty() {
+        chart
+            .configure_secondary_axes()
+            .y_labels(5)
+            .y_label_formatter(&|v| format(secondary_log, *v))
+            .draw()
+            .unwrap();
+    }
+
+    for s in primary.iter() {
+        let color = s.color;
+        chart
+            .draw_series(LineSeries::new(s.chart.plotted(), &color))
+            .unwrap()
+            .label(s.name.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    for s in secondary.iter() {
+        let color = s.color;
+        chart
+            .draw_secondary_series(LineSeries::new(s.chart.plotted(), &color))
+            .unwrap()
+            .label(s.name.as_str())
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    if series.len() > 1 {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_chart() {
+        for layout in [ChartLayout::Overlay, ChartLayout::SmallMultiples] {
+            let mut chart = MultiChart::new(layout)
+                .series("train", SeriesOpts::default().log(true).smoothing(0.9))
+                .series("lr", SeriesOpts::default().secondary_axis(true))
+                .series("val", SeriesOpts::default().log(true));
+            for i in 0..20 {
+                chart.push("train", i as f32, 1.0 / (i + 1) as f32);
+                chart.push("lr", i as f32, 1.0e-3);
+                if i % 5 == 0 {
+                    chart.push("val", i as f32, 1.5 / (i + 1) as f32);
+                }
+            }
+            assert_eq!(
+                chart.latest().collect::<Vec<_>>(),
+                vec![("train", 0.05), ("lr", 1.0e-3), ("val", 0.09375)]
+            );
+
+            let mut dt = DrawTarget::new(400, 300);
+            chart.draw(&mut dt, 5, 5, 5, 5);
+            // chart.save_png("/tmp/ye.png", (400, 300)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_span() {
+        assert_eq!(span([1.0, -2.0, 3.0].into_iter()), -2.0..3.0);
+        assert_eq!(span([2.0, 2.0].into_iter()), 1.0..3.0);
+        assert_eq!(span([0.0].into_iter()), -0.5..0.5);
+        assert_eq!(span([f32::NAN].into_iter()), 0.0..1.0);
+    }
 }
@@ -0,0 +1,225 @@
+/// How to scale the representation of parameters relative to each other.
+///
+/// Values are scaled per layer to the range `-1..=1`, which spans the [ColorMap].
+#[derive(Debug, Clone, Default)]
+pub enum ParamScale {
+    /// Values are used as they are.
+    #[default]
+    None,
+    /// Values are divided by a multiple of the standard deviation of the layer.
+    StdDev { mul: f32 },
+    /// The smallest and largest values of the layer span the full range.
+    MinMax,
+    /// Values are scaled logarithmically beyond `linthresh`, and linearly within it,
+    /// so both small and large magnitudes can be told apart.
+    SymLog { linthresh: f32 },
+}
+
+impl ParamScale {
+    /// Computes the scaling for the given values of a layer.
+    pub fn fit(&self, values: &[f32]) -> FittedScale {
+        let max_abs = values.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        match self {
+            ParamScale::None => FittedScale::Linear {
+                offset: 0.0,
+                div: 1.0,
+            },
+            ParamScale::StdDev { mul } => {
+                let n = values.len().max(1) as f32;
+                let mean = values.iter().sum::<f32>() / n;
+                let var = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / n;
+                FittedScale::Linear {
+                    offset: 0.0,
+                    div: non_zero(mul * var.sqrt()),
+                }
+            }
+            ParamScale::MinMax if values.is_empty() => ParamScale::None.fit(values),
+            ParamScale::MinMax => {
+                let min = values.iter().fold(f32::INFINITY, |m, v| m.min(*v));
+                let max = values.iter().fold(f32::NEG_INFINITY, |m, v| m.max(*v));
+                FittedScale::Linear {
+                    offset: (min + max) / 2.0,
+                    div: non_zero((max - min) / 2.0),
+                }
+            }
+            ParamScale::SymLog { linthresh } => FittedScale::SymLog {
+                linthresh: *linthresh,
+                div: non_zero((1.0 + max_abs / linthresh).ln()),
+            },
+        }
+    }
+}
+
+fn non_zero(div: f32) -> f32 {
+    if div > 0.0 && div.is_finite() {
+        div
+    } else {
+        1.0
+    }
+}
+
+/// A [ParamScale] fitted to the values of a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FittedScale {
+    Linear { offset: f32, div: f32 },
+    SymLog { linthresh: f32, div: f32 },
+}
+
+impl FittedScale {
+    /// Scales a value, such that values of the layer fall in `-1..=1`.
+    pub fn apply(&self, v: f32) -> f32 {
+        match self {
+            FittedScale::Linear { offset, div } => (v - offset) / div,
+            FittedScale::SymLog { linthresh, div } => {
+                v.signum() * (1.0 + v.abs() / linthresh).ln() / div
+            }
+        }
+    }
+
+    /// Returns the values which are scaled to `-1` and `1`.
+    pub fn bounds(&self) -> (f32, f32) {
+        match self {
+            FittedScale::Linear { offset, div } => (offset - div, offset + div),
+            FittedScale::SymLog { linthresh, div } => {
+                let v = (div.exp() - 1.0) * linthresh;
+                (-v, v)
+            }
+        }
+    }
+}
+
+/// How scaled values are mapped to colors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMap {
+    /// Negative values are red and positive values green, saturating smoothly.
+    #[default]
+    RedGreen,
+    /// The perceptually uniform, sequential viridis colormap.
+    Viridis,
+    /// The perceptually uniform, sequential inferno colormap.
+    Inferno,
+    /// A perceptually uniform, diverging blue-grey-red colormap.
+    CoolWarm,
+}
+
+const VIRIDIS: [(u8, u8, u8); 9] = [
+    (68, 1, 84),
+    (71, 44, 122),
+    (59, 81, 139),
+    (44, 113, 142),
+    (33, 144, 141),
+    (39, 173, 129),
+    (92, 200, 99),
+    (170, 220, 50),
+    (253, 231, 37),
+];
+
+const INFERNO: [(u8, u8, u8); 9] = [
+    (0, 0, 4),
+    (31, 12, 72),
+    (85, 15, 109),
+    (136, 34, 106),
+    (186, 54, 85),
+    (227, 89, 51),
+    (249, 140, 10),
+    (249, 201, 50),
+    (252, 255, 164),
+];
+
+const COOL_WARM: [(u8, u8, u8); 9] = [
+    (59, 76, 192),
+    (98, 130, 234),
+    (141, 176, 254),
+    (184, 208, 249),
+    (221, 221, 221),
+    (245, 196, 173),
+    (244, 154, 123),
+    (222, 96, 77),
+    (180, 4, 38),
+];
+
+/// Linearly interpolates between evenly-spaced colors, at `t` in `0..=1`.
+fn interpolate(stops: &[(u8, u8, u8)], t: f32) -> (u8, u8, u8) {
+    let x = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (x.floor() as usize).min(stops.len() - 2);
+    let f = x - i as f32;
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+    let (a, b) = (stops[i], stops[i + 1]);
+    (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+}
+
+impl ColorMap {
+    /// Returns the color of a scaled value, where `-1..=1` spans the colormap.
+    pub fn color(&self, v: f32) -> (u8, u8, u8) {
+        let v = if v.is_nan() { 0.0 } else { v };
+        let t = (v.clamp(-1.0, 1.0) + 1.0) / 2.0;
+        match self {
+            ColorMap::RedGreen => (
+                ((-v).tanh().max(0.0) * 130.0) as u8 + 48,
+                (v.tanh().max(0.0) * 120.0) as u8 + 48,
+                48,
+            ),
+            ColorMap::Viridis => interpolate(&VIRIDIS, t),
+            ColorMap::Inferno => interpolate(&INFERNO, t),
+            ColorMap::CoolWarm => interpolate(&COOL_WARM, t),
+        }
+    }
+
+    /// Returns a color for text which is legible over the color of the scaled value.
+    pub fn text_color(&self, v: f32) -> (u8, u8, u8) {
+        let (r, g, b) = self.color(v);
+        let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        if luma > 140.0 {
+            (30, 30, 30)
+        } else {
+            (201, 201, 201)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scales() {
+        let values = [-2.0, 0.0, 1.0, 5.0];
+
+        let s = ParamScale::None.fit(&values);
+        assert_eq!(s.apply(0.5), 0.5);
+        assert_eq!(s.bounds(), (-1.0, 1.0));
+
+        let s = ParamScale::MinMax.fit(&values);
+        assert_eq!(s.apply(-2.0), -1.0);
+        assert_eq!(s.apply(5.0), 1.0);
+        assert_eq!(s.bounds(), (-2.0, 5.0));
+
+        let s = ParamScale::StdDev { mul: 2.0 }.fit(&[-1.0, 1.0]);
+        assert_eq!(s.apply(1.0), 0.5);
+
+        let s = ParamScale::SymLog { linthresh: 0.1 }.fit(&values);
+        assert!((s.apply(5.0) - 1.0).abs() < 1e-6);
+        assert!((s.apply(-5.0) + 1.0).abs() < 1e-6);
+        assert!(s.apply(0.1) > 0.1);
+        let (lo, hi) = s.bounds();
+        assert!((lo + 5.0).abs() < 1e-4 && (hi - 5.0).abs() < 1e-4);
+
+        // Constant layers don't divide by zero.
+        let s = ParamScale::MinMax.fit(&[3.0, 3.0]);
+        assert_eq!(s.apply(3.0), 0.0);
+        let s = ParamScale::StdDev { mul: 1.0 }.fit(&[]);
+        assert_eq!(s.apply(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_colormaps() {
+        assert_eq!(ColorMap::RedGreen.color(0.0), (48, 48, 48));
+        assert_eq!(ColorMap::Viridis.color(-1.0), VIRIDIS[0]);
+        assert_eq!(ColorMap::Viridis.color(1.0), VIRIDIS[8]);
+        assert_eq!(ColorMap::Viridis.color(50.0), VIRIDIS[8]);
+        assert_eq!(ColorMap::CoolWarm.color(0.0), COOL_WARM[4]);
+        assert_eq!(ColorMap::Inferno.color(f32::NAN), INFERNO[4]);
+        assert_eq!(ColorMap::Inferno.text_color(-1.0), (201, 201, 201));
+        assert_eq!(ColorMap::Inferno.text_color(1.0), (30, 30, 30));
+    }
+}
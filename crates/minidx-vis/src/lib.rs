@@ -1,22 +1,34 @@
 use fontdue::layout::LayoutSettings;
 use minidx_core::Dtype;
-use raqote::*;
+
+mod canvas;
+pub use canvas::{Canvas, Extent, Svg};
 
 mod chart;
-use chart::LineChart;
+pub use chart::{ChartLayout, LineChart, MultiChart, SeriesOpts};
+
+mod color;
+pub use color::{ColorMap, FittedScale, ParamScale};
 
 mod network_traits;
-pub use network_traits::VisualizableNetwork;
+pub use network_traits::{VisualizableNetwork, VisualizableTrace};
 
 mod font;
 pub use font::VisFont;
 
 pub mod anim;
 
+mod snapshot;
+pub use snapshot::{render_png, save_png, save_svg};
+
+pub use raqote::DrawTarget;
+
 pub mod prelude {
     pub use crate::anim;
-    pub use crate::ParamVisOpts;
     pub use crate::VisualizableNetwork;
+    pub use crate::VisualizableTrace;
+    pub use crate::{render_png, save_png, save_svg};
+    pub use crate::{ColorMap, ParamScale, ParamVisOpts};
 }
 
 /// Describes the sizing of the cell for a single parameter.
@@ -27,6 +39,14 @@ pub struct ParamBox {
     font_size: f32,
 }
 
+impl ParamBox {
+    /// Whether values can be printed in each cell, or the cells are too small
+    /// for the font and are drawn as pixels of color.
+    fn shows_text(&self) -> bool {
+        self.h >= self.font_size && self.w >= 1.8 * self.font_size
+    }
+}
+
 impl Default for ParamBox {
     fn default() -> Self {
         Self {
@@ -37,22 +57,6 @@ impl Default for ParamBox {
     }
 }
 
-/// How to scale the representation of parameters relative to each other.
-#[derive(Debug, Clone, Default)]
-pub enum ParamScale {
-    #[default]
-    None,
-    StdDev {
-        mul: f32,
-    },
-}
-
-// impl Default for ParamScale {
-//     fn default() -> Self {
-//         Self::StdDev { mul: 1.2 }
-//     }
-// }
-
 /// Options for rendering a set of parameters.
 #[derive(Debug, Clone)]
 pub struct ParamVisOpts {
@@ -60,6 +64,10 @@ pub struct ParamVisOpts {
     module_padding: (f32, f32),
     cell: ParamBox,
     font: VisFont,
+    scale: ParamScale,
+    color_map: ColorMap,
+    legend: bool,
+    labels: bool,
 }
 
 impl Default for ParamVisOpts {
@@ -71,6 +79,10 @@ impl Default for ParamVisOpts {
             module_padding: (2.0, 6.0),
             cell: Default::default(),
             font,
+            scale: Default::default(),
+            color_map: Default::default(),
+            legend: false,
+            labels: false,
         }
     }
 }
@@ -96,6 +108,49 @@ impl ParamVisOpts {
             ..Default::default()
         }
     }
+
+    /// Sets the size of the cell for each parameter. Values are only printed in cells
+    /// which are large enough for the font.
+    pub fn cell_size(mut self, w: f32, h: f32) -> Self {
+        self.cell.w = w;
+        self.cell.h = h;
+        self
+    }
+
+    /// Sets how parameters are scaled before being mapped to colors.
+    pub fn scale(mut self, scale: ParamScale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the colormap used to paint parameters.
+    pub fn color_map(mut self, color_map: ColorMap) -> Self {
+        self.color_map = color_map;
+        self
+    }
+
+    /// Sets whether a color bar is drawn to the right of each layer, labelled
+    /// with the values at either end of the scale.
+    pub fn legend(mut self, legend: bool) -> Self {
+        self.legend = legend;
+        self
+    }
+
+    /// Sets whether the kind of each layer is labelled, in a gutter to the left
+    /// of its parameters.
+    pub fn labels(mut self, labels: bool) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    /// Returns the width of the gutter for layer labels.
+    fn label_gutter(&self) -> f32 {
+        if self.labels {
+            7.5 * self.cell.font_size
+        } else {
+            0.0
+        }
+    }
 }
 
 /// Implements visual rendering of a set of parameters.
@@ -106,7 +161,7 @@ trait PaintParams<P> {
     fn layout_bounds(&self, opts: &ParamVisOpts) -> (f32, f32);
 }
 
-impl PaintParams<()> for DrawTarget {
+impl<C: Canvas> PaintParams<()> for C {
     type Concrete = ();
     fn layout_bounds(&self, opts: &ParamVisOpts) -> (f32, f32) {
         (opts.module_padding.0, opts.module_padding.1)
@@ -115,86 +170,151 @@ impl PaintParams<()> for DrawTarget {
     fn paint_params(&mut self, _params: &(), _opts: &mut ParamVisOpts) {}
 }
 
-impl<E: Dtype, const I: usize, const O: usize> PaintParams<[[E; I]; O]> for DrawTarget {
+impl<C: Canvas, E: Dtype, const I: usize, const O: usize> PaintParams<[[E; I]; O]> for C {
     type Concrete = [[E; I]; O];
     fn layout_bounds(&self, opts: &ParamVisOpts) -> (f32, f32) {
         (opts.cell.w * I as f32, opts.cell.h * O as f32)
     }
 
     fn paint_params(&mut self, params: &[[E; I]; O], opts: &mut ParamVisOpts) {
-        let scale = 1.0;
-
-        for (j, params) in params.iter().enumerate() {
-            for (i, v) in params.iter().enumerate() {
-                let tl = (
-                    opts.offset.0 + opts.cell.w * i as f32,
-                    opts.offset.1 + opts.cell.h * j as f32,
-                );
-
-                // Make box
-                let mut pb = PathBuilder::new();
-                pb.move_to(tl.0, tl.1);
-                pb.line_to(tl.0 + opts.cell.w, tl.1);
-                pb.line_to(tl.0 + opts.cell.w, tl.1 + opts.cell.h);
-                pb.line_to(tl.0, tl.1 + opts.cell.h);
-                pb.line_to(tl.0, tl.1);
-                let p = pb.finish();
-
-                // Paint red => grey => green background based on parameter
-                let v = scale * v.to_f32().unwrap();
-                self.fill(
-                    &p,
-                    &Source::Solid(SolidSource::from_unpremultiplied_argb(
-                        0xFF,
-                        ((-v).tanh().max(0.0) * 130.0) as u8 + 48,
-                        (v.tanh().max(0.0) * 120.0) as u8 + 48,
-                        48,
-                    )),
-                    &DrawOptions::new(),
-                );
-                // Paint grid cell boundary
-                self.stroke(
-                    &p,
-                    &Source::Solid(SolidSource::from_unpremultiplied_argb(0xFF, 0, 0, 0)),
-                    &StrokeStyle {
-                        width: 1.0,
-                        ..StrokeStyle::default()
-                    },
-                    &DrawOptions::new(),
-                );
-
-                // Generate the text
-                let v_abs = v.abs();
-                let mut s = if v_abs >= 10.0 {
-                    format!("{:.0}", v_abs)
-                } else {
-                    format!("{:.1}", v_abs)
-                };
-                s.truncate(3);
-
-                opts.font.raster(
-                    &LayoutSettings {
-                        x: tl.0,
-                        y: tl.1 + 1.0,
-                        max_width: Some(opts.cell.w),
-                        max_height: Some(opts.cell.h - 2.0),
-                        horizontal_align: fontdue::layout::HorizontalAlign::Center,
-                        vertical_align: fontdue::layout::VerticalAlign::Middle,
-                        ..LayoutSettings::default()
-                    },
-                    s.as_str(),
-                    opts.cell.font_size,
-                    (201, 201, 201),
-                    self,
-                );
-            }
+        let values: Vec<f32> = params
+            .iter()
+            .flatten()
+            .map(|v| v.to_f32().unwrap())
+            .collect();
+        paint_cells(self, &values, I, opts);
+    }
+}
+
+/// Paints values as a grid of cells with the given number of columns, starting at
+/// the current offset, followed by a legend if enabled.
+pub(crate) fn paint_cells<C: Canvas>(
+    dt: &mut C,
+    values: &[f32],
+    cols: usize,
+    opts: &mut ParamVisOpts,
+) {
+    let scale = opts.scale.fit(values);
+    let shows_text = opts.cell.shows_text();
+
+    for (n, v) in values.iter().enumerate() {
+        let (i, j) = (n % cols, n / cols);
+        let tl = (
+            opts.offset.0 + opts.cell.w * i as f32,
+            opts.offset.1 + opts.cell.h * j as f32,
+        );
+
+        // Paint the background based on the scaled parameter
+        let scaled = scale.apply(*v);
+        dt.paint_box(
+            tl,
+            (opts.cell.w, opts.cell.h),
+            opts.color_map.color(scaled),
+            shows_text,
+        );
+        if !shows_text {
+            continue;
         }
+
+        // Generate the text
+        let v_abs = v.abs();
+        let mut s = if v_abs >= 10.0 {
+            format!("{:.0}", v_abs)
+        } else {
+            format!("{:.1}", v_abs)
+        };
+        s.truncate(3);
+
+        dt.paint_text(
+            &mut opts.font,
+            &LayoutSettings {
+                x: tl.0,
+                y: tl.1 + 1.0,
+                max_width: Some(opts.cell.w),
+                max_height: Some(opts.cell.h - 2.0),
+                horizontal_align: fontdue::layout::HorizontalAlign::Center,
+                vertical_align: fontdue::layout::VerticalAlign::Middle,
+                ..LayoutSettings::default()
+            },
+            s.as_str(),
+            opts.cell.font_size,
+            opts.color_map.text_color(scaled),
+        );
+    }
+
+    if opts.legend && !values.is_empty() {
+        let rows = values.len().div_ceil(cols);
+        let (w, h) = (opts.cell.w * cols as f32, opts.cell.h * rows as f32);
+        paint_legend(dt, (opts.offset.0 + w, opts.offset.1), h, &scale, opts);
+    }
+}
+
+/// Formats a value at the end of a color bar.
+fn legend_label(v: f32) -> String {
+    if v == 0.0 || (0.01..1000.0).contains(&v.abs()) {
+        format!("{:.2}", v)
+    } else {
+        format!("{:.1e}", v)
+    }
+}
+
+/// Paints a color bar of the given height with its top-left corner at `tl`, going from
+/// the largest value of the scale at the top to the smallest at the bottom.
+fn paint_legend<C: Canvas>(
+    dt: &mut C,
+    tl: (f32, f32),
+    height: f32,
+    scale: &FittedScale,
+    opts: &mut ParamVisOpts,
+) {
+    let x = tl.0 + (opts.cell.w / 4.0).max(2.0);
+    let w = (opts.cell.w / 2.0).max(4.0);
+    let steps = height.ceil().max(1.0) as usize;
+    for s in 0..steps {
+        let v = 1.0 - 2.0 * (s as f32 + 0.5) / steps as f32;
+        let step_h = height / steps as f32;
+        dt.paint_box(
+            (x, tl.1 + s as f32 * step_h),
+            (w, step_h),
+            opts.color_map.color(v),
+            false,
+        );
+    }
+
+    // Label the ends of the bar, if there is space for both.
+    if !opts.cell.shows_text() || height < 2.0 * opts.cell.font_size {
+        return;
+    }
+    let (lo, hi) = scale.bounds();
+    for (label, y, align) in [
+        (hi, tl.1, fontdue::layout::VerticalAlign::Top),
+        (
+            lo,
+            tl.1 + height - opts.cell.font_size,
+            fontdue::layout::VerticalAlign::Bottom,
+        ),
+    ] {
+        dt.paint_text(
+            &mut opts.font,
+            &LayoutSettings {
+                x: x + w + 2.0,
+                y,
+                max_height: Some(opts.cell.font_size),
+                horizontal_align: fontdue::layout::HorizontalAlign::Left,
+                vertical_align: align,
+                ..LayoutSettings::default()
+            },
+            legend_label(label).as_str(),
+            opts.cell.font_size,
+            (10, 10, 10),
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use raqote::*;
 
     #[test]
     fn test_paint_params() {
@@ -214,6 +334,37 @@ mod tests {
         // dt.write_png("/tmp/ye.png").expect("write failed");
     }
 
+    #[test]
+    fn test_paint_params_pixels() {
+        assert!(ParamVisOpts::small().cell.shows_text());
+        let mut opts = ParamVisOpts::small()
+            .cell_size(4.0, 4.0)
+            .scale(ParamScale::MinMax)
+            .color_map(ColorMap::Viridis)
+            .legend(true);
+        assert!(!opts.cell.shows_text());
+
+        let mut params = [[0.0f32; 32]; 32];
+        params
+            .iter_mut()
+            .flatten()
+            .enumerate()
+            .for_each(|(i, p)| *p = i as f32 / 100.0);
+        let mut dt = DrawTarget::new(160, 140);
+        dt.paint_params(&params, &mut opts);
+
+        let pixel = |x: i32, y: i32| {
+            let p = dt.get_data()[(y * dt.width() + x) as usize];
+            ((p >> 16) as u8, (p >> 8) as u8, p as u8)
+        };
+        // The smallest parameter is the bottom of the colormap, and the top of
+        // the legend is the top of the colormap.
+        assert_eq!(pixel(3, 3), ColorMap::Viridis.color(-1.0));
+        let (r, g, _) = pixel(2 + 32 * 4 + 3, 2);
+        assert!(r > 240 && g > 220, "got {:?}", (r, g));
+        // dt.write_png("/tmp/ye.png").expect("write failed");
+    }
+
     #[test]
     fn test_visualize() {
         use minidx_core::layers as l;
@@ -236,4 +387,39 @@ mod tests {
         network.visualize(&mut dt, &mut params.clone());
         // dt.write_png("/tmp/ye.png").expect("write failed");
     }
+
+    #[test]
+    fn test_visualize_trace() {
+        use minidx_core::layers as l;
+        use minidx_core::{BackpropModule, TracedModule};
+        let network = (
+            (
+                l::Dense::<f32, 2, 3>::default(),
+                l::Bias1d::<f32, 3>::default(),
+            ),
+            l::Activation::<f32>::default(),
+            l::GLU::<f32, 3, 2>::default(),
+            l::Dense::<f32, 2, 1>::default(),
+        );
+        let mut dt = DrawTarget::new(460, 800);
+        let opts = ParamVisOpts::default();
+        let (out, trace) = network.traced_forward([1.0, -1.0]).unwrap();
+
+        // Values are drawn aligned with the layout of the parameters.
+        let bounds = network.visualize(&mut dt, &mut opts.clone());
+        assert_eq!(
+            network.visualize_trace(&trace, &mut dt, &mut opts.clone()),
+            bounds
+        );
+
+        let (grads, layer_grads) =
+            VisualizableTrace::<_, DrawTarget>::input_grads(&network, &trace, out);
+        assert_eq!(grads, network.backprop(&trace, out).0);
+        assert_eq!(grads, layer_grads.0 .0);
+        assert_eq!(
+            network.visualize_grads(&layer_grads, &mut dt, &mut opts.clone()),
+            bounds
+        );
+        // dt.write_png("/tmp/ye.png").expect("write failed");
+    }
 }
@@ -1,6 +1,7 @@
-use crate::{PaintParams, ParamVisOpts};
-use minidx_core::{Dtype, Float};
-use raqote::DrawTarget;
+use crate::{paint_cells, Canvas, PaintParams, ParamVisOpts};
+use fontdue::layout::{HorizontalAlign, LayoutSettings, VerticalAlign};
+use minidx_core::layers::Conv1dKernel;
+use minidx_core::{BackpropModule, Const, Dtype, Float, Module, VisualizableUnit};
 
 /// Identifies layer types which are native to minidx: needed to get around
 /// trait conflicts between impls on M and (M,)
@@ -28,6 +29,27 @@ impl<
     > LayerMarker for minidx_core::layers::LR<E, I, M>
 {
 }
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + minidx_core::Module<[E; I]> + minidx_core::VisualizableUnit,
+    > LayerMarker for minidx_core::layers::Frozen<E, I, M>
+{
+}
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + minidx_core::Module<[E; I]> + minidx_core::VisualizableUnit,
+    > LayerMarker for minidx_core::layers::LayerOverride<E, I, M>
+{
+}
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + minidx_core::Module<[E; I]> + minidx_core::VisualizableUnit,
+    > LayerMarker for minidx_core::layers::Initialized<E, I, M>
+{
+}
 
 /// Some composition of parameters which can be visualized.
 pub trait VisualizableNetwork<DT> {
@@ -36,15 +58,44 @@ pub trait VisualizableNetwork<DT> {
     fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32);
 }
 
-impl<M: minidx_core::VisualizableUnit + LayerMarker> VisualizableNetwork<DrawTarget> for M
+/// Draws the kind of a layer in the gutter to its left, if layer labels are enabled.
+///
+/// Returns the width of the gutter, which the layer should be offset by.
+fn paint_label<DT: Canvas>(dt: &mut DT, kind: &str, height: f32, opts: &mut ParamVisOpts) -> f32 {
+    let gutter = opts.label_gutter();
+    if gutter > 0.0 {
+        // Layers without parameters are short, so shrink the font to fit.
+        let font_size = opts.cell.font_size.min(height + opts.module_padding.1);
+        dt.paint_text(
+            &mut opts.font,
+            &LayoutSettings {
+                x: opts.offset.0,
+                y: opts.offset.1,
+                max_width: Some(gutter),
+                horizontal_align: HorizontalAlign::Left,
+                vertical_align: VerticalAlign::Top,
+                ..LayoutSettings::default()
+            },
+            kind,
+            font_size,
+            (10, 10, 10),
+        );
+    }
+    gutter
+}
+
+impl<DT: Canvas, M: minidx_core::VisualizableUnit + LayerMarker> VisualizableNetwork<DT> for M
 where
-    DrawTarget: PaintParams<M::Params>,
+    DT: PaintParams<M::Params>,
 {
     type Params = M::Params;
 
-    fn visualize(&self, dt: &mut DrawTarget, opts: &mut ParamVisOpts) -> (f32, f32) {
-        let bounds = dt.layout_bounds(&opts);
-        dt.paint_params(self.params(), opts);
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let bounds = <DT as PaintParams<M::Params>>::layout_bounds(dt, opts);
+        let gutter = paint_label(dt, M::KIND, bounds.1, opts);
+        opts.offset.0 += gutter;
+        <DT as PaintParams<M::Params>>::paint_params(dt, self.params(), opts);
+        opts.offset.0 -= gutter;
         (0.0, bounds.1)
     }
 }
@@ -54,62 +105,175 @@ impl<
         const I: usize,
         const O: usize,
         A: minidx_core::Module<[E; O], Output = [E; O]> + Default,
-    > VisualizableNetwork<DrawTarget> for minidx_core::layers::GLU<E, I, O, A>
+        DT: Canvas,
+    > VisualizableNetwork<DT> for minidx_core::layers::GLU<E, I, O, A>
 where
-    DrawTarget: PaintParams<[[E; O]; 1]> + PaintParams<[[E; I]; O]>,
+    DT: PaintParams<[[E; O]; 1]> + PaintParams<[[E; I]; O]>,
 {
     type Params = (); // Not technically correct but it'll do
 
-    fn visualize(&self, dt: &mut DrawTarget, opts: &mut ParamVisOpts) -> (f32, f32) {
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
         let (gc, gb, sc, sb) = self.connection_params();
 
-        let gc_box = <raqote::DrawTarget as PaintParams<[[E; I]; O]>>::layout_bounds(dt, &opts);
+        let gc_box = <DT as PaintParams<[[E; I]; O]>>::layout_bounds(dt, opts);
+        let gutter = paint_label(dt, "glu", gc_box.1, opts);
+        opts.offset.0 += gutter;
         dt.paint_params(gc, opts);
-        let gb_box = <raqote::DrawTarget as PaintParams<[[E; O]; 1]>>::layout_bounds(
+        let gb_box = <DT as PaintParams<[[E; O]; 1]>>::layout_bounds(
             dt,
             opts.update_cursor((0.0, gc_box.1)),
         );
-        dt.paint_params(
-            unsafe {
-                // SAFETY: An array of N is exactly the same as a unary array of the array of N
-                std::mem::transmute::<&[E; O], &[[E; O]; 1]>(gb)
-            },
-            opts,
-        );
+        dt.paint_params(std::array::from_ref(gb), opts);
 
-        let sc_box = <raqote::DrawTarget as PaintParams<[[E; I]; O]>>::layout_bounds(
+        let sc_box = <DT as PaintParams<[[E; I]; O]>>::layout_bounds(
             dt,
             opts.update_cursor((0.0, gb_box.1)),
         );
         dt.paint_params(sc, opts);
-        let sb_box = <raqote::DrawTarget as PaintParams<[[E; O]; 1]>>::layout_bounds(
+        let sb_box = <DT as PaintParams<[[E; O]; 1]>>::layout_bounds(
             dt,
             opts.update_cursor((0.0, sc_box.1)),
         );
-        dt.paint_params(
-            unsafe {
-                // SAFETY: An array of N is exactly the same as a unary array of the array of N
-                std::mem::transmute::<&[E; O], &[[E; O]; 1]>(sb)
-            },
-            opts,
-        );
+        dt.paint_params(std::array::from_ref(sb), opts);
+        opts.offset.0 -= gutter;
 
         (0.0, sb_box.1)
     }
 }
 
+/// Draws the filter of a 1d convolution as a row of cells, above a diagram of
+/// the window of inputs (columns) each output (rows) is computed from.
+fn paint_kernel<DT: Canvas>(
+    dt: &mut DT,
+    kind: &str,
+    kernel: &[f32],
+    (inputs, outputs): (usize, usize),
+    opts: &mut ParamVisOpts,
+) -> (f32, f32) {
+    let tile = (opts.cell.w / 4.0).max(2.0);
+    let height = opts.cell.h + opts.module_padding.1 + tile * outputs as f32;
+    let gutter = paint_label(dt, kind, height, opts);
+    opts.offset.0 += gutter;
+    paint_cells(dt, kernel, kernel.len().max(1), opts);
+
+    let scale = opts.scale.fit(kernel);
+    let top = opts.offset.1 + opts.cell.h + opts.module_padding.1;
+    for o in 0..outputs {
+        for i in 0..inputs {
+            let rgb = match i.checked_sub(o).and_then(|k| kernel.get(k)) {
+                Some(w) => opts.color_map.color(scale.apply(*w)),
+                None => (0xb8, 0xb8, 0xb8),
+            };
+            dt.paint_box(
+                (opts.offset.0 + tile * i as f32, top + tile * o as f32),
+                (tile, tile),
+                rgb,
+                false,
+            );
+        }
+    }
+    opts.offset.0 -= gutter;
+    (0.0, height)
+}
+
+impl<
+        E: Dtype + minidx_core::matmul::MatMulImpl,
+        const I: usize,
+        const O: usize,
+        C: Conv1dKernel<E, Const<I>, Const<O>>,
+        DT: Canvas,
+    > VisualizableNetwork<DT> for minidx_core::layers::Conv1d<E, I, O, C>
+where
+    C::Weights: std::fmt::Debug,
+{
+    type Params = C::Weights;
+
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let kernel: Vec<f32> = self
+            .params()
+            .as_ref()
+            .iter()
+            .map(|w| w.to_f32().unwrap())
+            .collect();
+        paint_kernel(dt, Self::KIND, &kernel, (I, O), opts)
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize, DT: Canvas> VisualizableNetwork<DT>
+    for minidx_core::layers::QConv1d<E, I, O>
+{
+    type Params = Vec<i8>;
+
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let (weights, scale) = self.weights();
+        let kernel: Vec<f32> = weights.iter().map(|q| *q as f32 * scale).collect();
+        paint_kernel(dt, "qconv1d", &kernel, (I, O), opts)
+    }
+}
+
+impl<E: Float, const I: usize, const O: usize, DT: Canvas> VisualizableNetwork<DT>
+    for minidx_core::layers::QDense<E, I, O>
+{
+    type Params = [[i8; O]; I];
+
+    /// Draws the dequantized weights, laid out the same as a [Dense](minidx_core::layers::Dense) layer.
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let (weights, scales) = self.weights();
+        let values: Vec<f32> = weights
+            .iter()
+            .flat_map(|w| w.iter().zip(scales).map(|(q, s)| *q as f32 * s))
+            .collect();
+
+        let height = opts.cell.h * O as f32;
+        let gutter = paint_label(dt, "qdense", height, opts);
+        opts.offset.0 += gutter;
+        paint_cells(dt, &values, I, opts);
+        opts.offset.0 -= gutter;
+        (0.0, height)
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        M: Default + minidx_core::Module<[E; I]> + VisualizableNetwork<DT>,
+        DT: Canvas,
+    > VisualizableNetwork<DT> for minidx_core::layers::Residual<E, I, M>
+{
+    type Params = M::Params;
+
+    /// Draws the wrapped module indented, with a bracket to its left showing
+    /// the skip path around it.
+    fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let (w, thickness) = ((opts.cell.w / 4.0).max(3.0), 2.0);
+        let start = opts.offset;
+
+        opts.offset.0 += 2.0 * w;
+        let bounds = self.module.visualize(dt, opts);
+        opts.offset.0 -= 2.0 * w;
+
+        let bottom = opts.offset.1 + bounds.1.max(thickness);
+        let rgb = (60, 60, 60);
+        dt.paint_box(start, (w, thickness), rgb, false);
+        dt.paint_box(start, (thickness, bottom - start.1), rgb, false);
+        dt.paint_box((start.0, bottom - thickness), (w, thickness), rgb, false);
+        bounds
+    }
+}
+
 macro_rules! tuple_impls {
     ([$($name:ident),+] [$($idx:tt),*], $last:ident, [$($rev_tail:ident),*]) => {
         impl<
+            DT,
             $last:
-            $(VisualizableNetwork<DrawTarget>, $rev_tail: )*
-            VisualizableNetwork<DrawTarget>
-        > VisualizableNetwork<DrawTarget> for ($($name,)+) {
+            $(VisualizableNetwork<DT>, $rev_tail: )*
+            VisualizableNetwork<DT>
+        > VisualizableNetwork<DT> for ($($name,)+) {
             type Params = (
                 $($name::Params,)+
             );
 
-            fn visualize(&self, dt: &mut DrawTarget, opts: &mut ParamVisOpts) -> (f32, f32) {
+            fn visualize(&self, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
                 let bounds = self.0.visualize(dt, opts);
                 $(let bounds = self.$idx.visualize(dt, opts.update_cursor(bounds));)*
                 bounds
@@ -125,3 +289,310 @@ tuple_impls!([M1, M2, M3, M4] [1, 2, 3], M4, [M3, M2, M1]);
 tuple_impls!([M1, M2, M3, M4, M5] [1, 2, 3, 4], M5, [M4, M3, M2, M1]);
 tuple_impls!([M1, M2, M3, M4, M5, M6] [1, 2, 3, 4, 5], M6, [M5, M4, M3, M2, M1]);
 tuple_impls!([M1, M2, M3, M4, M5, M6, M7] [1, 2, 3, 4, 5, 6], M7, [M6, M5, M4, M3, M2, M1]);
+
+/// A network which can visualize the values flowing through it, such as the
+/// activations of each layer, using the same layout as [VisualizableNetwork::visualize].
+///
+/// Values are drawn as a heat-map row at the top of the space taken by each layer,
+/// so the input to a dense layer lines up with the columns of its weights. Layers
+/// without parameters (such as activation functions) take no space in the layout,
+/// so their values are not drawn: their output is drawn as the input to the next layer.
+pub trait VisualizableTrace<X, DT>: VisualizableNetwork<DT> + BackpropModule<X> {
+    /// The gradients with respect to the input of each layer.
+    type InputGrads;
+
+    /// Draws the input to each layer, as captured in the trace from
+    /// [traced_forward](minidx_core::TracedModule::traced_forward).
+    fn visualize_trace(
+        &self,
+        trace: &Self::Trace,
+        dt: &mut DT,
+        opts: &mut ParamVisOpts,
+    ) -> (f32, f32);
+
+    /// Backpropagates the gradients of the output like [BackpropModule::backprop],
+    /// but returns the gradients with respect to the input of each layer, rather than
+    /// with respect to the parameters.
+    fn input_grads(
+        &self,
+        trace: &Self::Trace,
+        grads_wrt_output: <Self as Module<X>>::Output,
+    ) -> (X, Self::InputGrads);
+
+    /// Draws the gradients with respect to the input of each layer, as returned
+    /// by [VisualizableTrace::input_grads].
+    fn visualize_grads(
+        &self,
+        grads: &Self::InputGrads,
+        dt: &mut DT,
+        opts: &mut ParamVisOpts,
+    ) -> (f32, f32);
+}
+
+/// Draws values as a row of cells at the current offset, if the space taken by
+/// the layer is tall enough to fit the row.
+fn paint_row<DT: Canvas, E: Dtype, const N: usize>(
+    dt: &mut DT,
+    values: &[E; N],
+    bounds: (f32, f32),
+    opts: &mut ParamVisOpts,
+) {
+    if bounds.1 >= opts.cell.h {
+        let gutter = opts.label_gutter();
+        opts.offset.0 += gutter;
+        dt.paint_params(std::array::from_ref(values), opts);
+        opts.offset.0 -= gutter;
+    }
+}
+
+impl<DT: Canvas, E: Dtype, const N: usize, M> VisualizableTrace<[E; N], DT> for M
+where
+    M: minidx_core::VisualizableUnit + LayerMarker + BackpropModule<[E; N], Trace = [E; N]>,
+    DT: PaintParams<M::Params>,
+{
+    type InputGrads = [E; N];
+
+    fn visualize_trace(&self, trace: &[E; N], dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        let bounds = <DT as PaintParams<M::Params>>::layout_bounds(dt, opts);
+        paint_row(dt, trace, bounds, opts);
+        (0.0, bounds.1)
+    }
+
+    fn input_grads(
+        &self,
+        trace: &[E; N],
+        grads_wrt_output: <Self as Module<[E; N]>>::Output,
+    ) -> ([E; N], [E; N]) {
+        let (grads, _) = self.backprop(trace, grads_wrt_output);
+        (grads, grads)
+    }
+
+    fn visualize_grads(&self, grads: &[E; N], dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        self.visualize_trace(grads, dt, opts)
+    }
+}
+
+/// Draws rows of values aligned with the connections and biases of the gate
+/// and signal paths of a [GLU](minidx_core::layers::GLU).
+fn paint_glu_rows<DT: Canvas, E: Dtype, const I: usize, const O: usize>(
+    dt: &mut DT,
+    opts: &mut ParamVisOpts,
+    gc: Option<&[E; I]>,
+    gb: Option<&[E; O]>,
+    sc: Option<&[E; I]>,
+    sb: Option<&[E; O]>,
+) -> (f32, f32) {
+    let gc_box = <DT as PaintParams<[[E; I]; O]>>::layout_bounds(dt, opts);
+    if let Some(r) = gc {
+        paint_row(dt, r, gc_box, opts);
+    }
+    let gb_box =
+        <DT as PaintParams<[[E; O]; 1]>>::layout_bounds(dt, opts.update_cursor((0.0, gc_box.1)));
+    if let Some(r) = gb {
+        paint_row(dt, r, gb_box, opts);
+    }
+    let sc_box =
+        <DT as PaintParams<[[E; I]; O]>>::layout_bounds(dt, opts.update_cursor((0.0, gb_box.1)));
+    if let Some(r) = sc {
+        paint_row(dt, r, sc_box, opts);
+    }
+    let sb_box =
+        <DT as PaintParams<[[E; O]; 1]>>::layout_bounds(dt, opts.update_cursor((0.0, sc_box.1)));
+    if let Some(r) = sb {
+        paint_row(dt, r, sb_box, opts);
+    }
+
+    (0.0, sb_box.1)
+}
+
+impl<
+        E: Dtype + Float + minidx_core::matmul::MatMulImpl,
+        const I: usize,
+        const O: usize,
+        A: minidx_core::Module<[E; O], Output = [E; O]>
+            + minidx_core::TracedModule<[E; O]>
+            + BackpropModule<[E; O]>
+            + Default,
+        DT: Canvas,
+    > VisualizableTrace<[E; I], DT> for minidx_core::layers::GLU<E, I, O, A>
+where
+    DT: PaintParams<[[E; O]; 1]> + PaintParams<[[E; I]; O]>,
+{
+    type InputGrads = [E; I];
+
+    /// Draws the input to the connections of each path, and the outputs of those
+    /// connections as the input to the biases.
+    fn visualize_trace(
+        &self,
+        trace: &Self::Trace,
+        dt: &mut DT,
+        opts: &mut ParamVisOpts,
+    ) -> (f32, f32) {
+        let ((gc, gb, _), (sc, sb), _) = trace;
+        paint_glu_rows(dt, opts, Some(gc), Some(gb), Some(sc), Some(sb))
+    }
+
+    fn input_grads(&self, trace: &Self::Trace, grads_wrt_output: [E; O]) -> ([E; I], [E; I]) {
+        let (grads, _) = self.backprop(trace, grads_wrt_output);
+        (grads, grads)
+    }
+
+    /// Draws the gradients with respect to the input of the layer, aligned with
+    /// the connections of the gate.
+    fn visualize_grads(&self, grads: &[E; I], dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+        paint_glu_rows::<DT, E, I, O>(dt, opts, Some(grads), None, None, None)
+    }
+}
+
+macro_rules! trace_tuple_impls {
+    ([$first:ident $(, $name:ident)*] [$($idx:tt),*] [$($rev_idx:tt),*], [$($rev_grads:ident),*], [$($fwd_grads:ident),*], [$(($mod_for:ident, $mod_from:ident)),*]) => {
+        impl<
+            Input,
+            DT,
+            $first: VisualizableTrace<Input, DT>,
+            $($mod_for: VisualizableTrace<$mod_from::Output, DT>,)*
+        > VisualizableTrace<Input, DT> for ($first, $($name,)*) {
+            type InputGrads = ($first::InputGrads, $($name::InputGrads,)*);
+
+            fn visualize_trace(&self, trace: &Self::Trace, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+                let bounds = self.0.visualize_trace(&trace.0, dt, opts);
+                $(let bounds = self.$idx.visualize_trace(&trace.$idx, dt, opts.update_cursor(bounds));)*
+                bounds
+            }
+
+            fn input_grads(
+                &self,
+                trace: &Self::Trace,
+                grads_wrt_output: <Self as Module<Input>>::Output,
+            ) -> (Input, Self::InputGrads) {
+                let grads = grads_wrt_output;
+                $(let (grads, $rev_grads) = self.$rev_idx.input_grads(&trace.$rev_idx, grads);)*
+                let (grads, m1g) = self.0.input_grads(&trace.0, grads);
+                (grads, (m1g, $($fwd_grads,)*))
+            }
+
+            fn visualize_grads(&self, grads: &Self::InputGrads, dt: &mut DT, opts: &mut ParamVisOpts) -> (f32, f32) {
+                let bounds = self.0.visualize_grads(&grads.0, dt, opts);
+                $(let bounds = self.$idx.visualize_grads(&grads.$idx, dt, opts.update_cursor(bounds));)*
+                bounds
+            }
+        }
+    }
+}
+
+trace_tuple_impls!([M1][][], [], [], []);
+trace_tuple_impls!([M1, M2][1][1], [m2g], [m2g], [(M2, M1)]);
+trace_tuple_impls!([M1, M2, M3] [1, 2] [2, 1], [m3g, m2g], [m2g, m3g], [(M2, M1), (M3, M2)]);
+trace_tuple_impls!([M1, M2, M3, M4] [1, 2, 3] [3, 2, 1], [m4g, m3g, m2g], [m2g, m3g, m4g], [(M2, M1), (M3, M2), (M4, M3)]);
+trace_tuple_impls!([M1, M2, M3, M4, M5] [1, 2, 3, 4] [4, 3, 2, 1], [m5g, m4g, m3g, m2g], [m2g, m3g, m4g, m5g], [(M2, M1), (M3, M2), (M4, M3), (M5, M4)]);
+trace_tuple_impls!([M1, M2, M3, M4, M5, M6] [1, 2, 3, 4, 5] [5, 4, 3, 2, 1], [m6g, m5g, m4g, m3g, m2g], [m2g, m3g, m4g, m5g, m6g], [(M2, M1), (M3, M2), (M4, M3), (M5, M4), (M6, M5)]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Extent, Svg};
+    use minidx_core::layers as l;
+    use raqote::DrawTarget;
+
+    /// Fails to compile unless the network can be drawn onto every kind of canvas.
+    fn covered<
+        N: VisualizableNetwork<DrawTarget> + VisualizableNetwork<Svg> + VisualizableNetwork<Extent>,
+    >(
+        _network: &N,
+    ) {
+    }
+
+    type Dense = l::Dense<f32, 3, 3>;
+
+    /// An instance of each layer exported by minidx_core. A layer missing here
+    /// fails to compile.
+    macro_rules! example {
+        (Activation) => {
+            l::Activation::<f32>
+        };
+        (Bias1d) => {
+            l::Bias1d::<f32, 3>
+        };
+        (Dense) => {
+            Dense
+        };
+        (Softmax) => {
+            l::Softmax
+        };
+        (Swish) => {
+            l::Swish::<f32, 3>
+        };
+        (Residual) => {
+            l::Residual::<f32, 3, Dense>
+        };
+        (GLU) => {
+            l::GLU::<f32, 3, 3>
+        };
+        (Conv1d) => {
+            l::Conv1d::<f32, 4, 2, Const<3>>
+        };
+        (QConv1d) => {
+            l::QConv1d::<f32, 4, 2>
+        };
+        (QDense) => {
+            l::QDense::<f32, 3, 3>
+        };
+        (LR) => {
+            l::LR::<f32, 3, Dense>
+        };
+        (Frozen) => {
+            l::Frozen::<f32, 3, Dense>
+        };
+        (LayerOverride) => {
+            l::LayerOverride::<f32, 3, Dense>
+        };
+        (Initialized) => {
+            l::Initialized::<f32, 3, Dense>
+        };
+        (Diag) => {
+            l::Diag::<f32, 3>
+        };
+        (ScalarScale) => {
+            l::ScalarScale::<f32>
+        };
+        (RMSDiv) => {
+            l::RMSDiv::<f32, 3>
+        };
+    }
+
+    macro_rules! check_coverage {
+        ($($layer:ident),+) => {
+            $(
+                let layer: example!($layer) = Default::default();
+                covered(&layer);
+            )+
+        };
+    }
+
+    #[test]
+    fn test_layer_coverage() {
+        minidx_core::for_each_layer!(check_coverage);
+    }
+
+    #[test]
+    fn test_visualize_conv_residual() {
+        let network = (
+            l::Residual::<f32, 4, (l::Dense<f32, 4, 4>, l::Activation<f32>)>::default(),
+            l::Conv1d::<f32, 4, 2, Const<3>>::default(),
+        );
+        let opts = ParamVisOpts::small().labels(true);
+
+        let mut extent = Extent::default();
+        let bounds = network.visualize(&mut extent, &mut opts.clone());
+        let mut dt = DrawTarget::new(extent.0 .0 as i32 + 2, extent.0 .1 as i32 + 2);
+        assert_eq!(network.visualize(&mut dt, &mut opts.clone()), bounds);
+
+        // The kernel is drawn above a diagram of the 2 outputs over the 4 inputs.
+        let tile = (opts.cell.w / 4.0).max(2.0);
+        assert_eq!(
+            bounds,
+            (0.0, opts.cell.h + opts.module_padding.1 + 2.0 * tile)
+        );
+        // dt.write_png("/tmp/ye.png").expect("write failed");
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Extent, ParamVisOpts, Svg, VisualizableNetwork};
+use raqote::{DrawTarget, SolidSource};
+use std::path::Path;
+
+const BACKGROUND: (u8, u8, u8) = (0xcf, 0xcf, 0xcf);
+
+/// Computes the size of a canvas needed to fit the visualization of a network,
+/// with a margin matching the offset at which the visualization starts.
+fn canvas_size<N: VisualizableNetwork<Extent>>(network: &N, opts: &ParamVisOpts) -> (f32, f32) {
+    let mut extent = Extent::default();
+    network.visualize(&mut extent, &mut opts.clone());
+    let (w, h) = extent.0;
+    (w + opts.offset.0, h + opts.offset.1)
+}
+
+/// Renders the parameters of a network to an encoded PNG image, sized to fit the visualization.
+pub fn render_png<N>(network: &N, opts: &ParamVisOpts) -> std::io::Result<Vec<u8>>
+where
+    N: VisualizableNetwork<DrawTarget> + VisualizableNetwork<Extent>,
+{
+    let (w, h) = canvas_size(network, opts);
+    let mut dt = DrawTarget::new(w.ceil() as i32, h.ceil() as i32);
+    dt.clear(SolidSource::from_unpremultiplied_argb(
+        0xff,
+        BACKGROUND.0,
+        BACKGROUND.1,
+        BACKGROUND.2,
+    ));
+    <N as VisualizableNetwork<DrawTarget>>::visualize(network, &mut dt, &mut opts.clone());
+
+    // Pixels are premultiplied ARGB, where PNG wants straight RGBA.
+    let rgba: Vec<u8> = dt
+        .get_data()
+        .iter()
+        .flat_map(|p| {
+            let [b, g, r, a] = p.to_le_bytes();
+            let unmul = |c: u8| (c as u32 * 255).checked_div(a as u32).unwrap_or(0) as u8;
+            [unmul(r), unmul(g), unmul(b), a]
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    let mut enc = png::Encoder::new(&mut out, dt.width() as u32, dt.height() as u32);
+    enc.set_color(png::ColorType::Rgba);
+    enc.set_depth(png::BitDepth::Eight);
+    enc.write_header()
+        .and_then(|mut w| w.write_image_data(&rgba))
+        .map_err(std::io::Error::other)?;
+    Ok(out)
+}
+
+/// Renders the parameters of a network to a PNG file, sized to fit the visualization.
+pub fn save_png<N>(network: &N, path: impl AsRef<Path>, opts: &ParamVisOpts) -> std::io::Result<()>
+where
+    N: VisualizableNetwork<DrawTarget> + VisualizableNetwork<Extent>,
+{
+    std::fs::write(path, render_png(network, opts)?)
+}
+
+/// Renders the parameters of a network to an SVG file, sized to fit the visualization.
+pub fn save_svg<N>(network: &N, path: impl AsRef<Path>, opts: &ParamVisOpts) -> std::io::Result<()>
+where
+    N: VisualizableNetwork<Svg> + VisualizableNetwork<Extent>,
+{
+    let (w, h) = canvas_size(network, opts);
+    let mut svg = Svg::new((w.ceil(), h.ceil()), BACKGROUND);
+    <N as VisualizableNetwork<Svg>>::visualize(network, &mut svg, &mut opts.clone());
+    std::fs::write(path, svg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minidx_core::layers as l;
+
+    #[test]
+    fn test_save() {
+        let network = (
+            (
+                l::Dense::<f32, 2, 3>::default(),
+                l::Bias1d::<f32, 3>::default(),
+            ),
+            l::Activation::<f32>::default(),
+            l::GLU::<f32, 3, 2>::default(),
+            l::Dense::<f32, 2, 1>::default(),
+        );
+        let opts = ParamVisOpts::default().labels(true).legend(true);
+
+        // The canvas covers every layer, including the gutter for labels.
+        let (w, h) = canvas_size(&network, &opts);
+        let (_, layout_h) = <_ as VisualizableNetwork<Extent>>::visualize(
+            &network,
+            &mut Extent::default(),
+            &mut opts.clone(),
+        );
+        assert!(h >= layout_h);
+        assert!(w >= opts.label_gutter() + 3.0 * opts.cell.w);
+
+        let dir = std::env::temp_dir();
+        let png = dir.join("minidx_vis_test_save.png");
+        save_png(&network, &png, &opts).unwrap();
+        let data = std::fs::read(&png).unwrap();
+        assert!(data.starts_with(b"\x89PNG"));
+        let dec = png::Decoder::new(data.as_slice()).read_info().unwrap();
+        assert_eq!(
+            (dec.info().width, dec.info().height),
+            (w.ceil() as u32, h.ceil() as u32)
+        );
+
+        let svg = dir.join("minidx_vis_test_save.svg");
+        save_svg(&network, &svg, &opts).unwrap();
+        let doc = std::fs::read_to_string(&svg).unwrap();
+        assert!(doc.starts_with("<svg"));
+        assert!(doc.contains("<rect"));
+        assert!(doc.contains(">dense</text>"));
+        assert!(doc.contains(">glu</text>"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Summarizes a recording made by a [minidx::recorder::Recorder], optionally
+//! rendering its loss curve to a PNG.
+//!
+//! Usage: `minidx-replay <recording.json> [--png <out.png>] [--log]`
+
+use minidx::recorder::Recording;
+use minidx_vis::LineChart;
+
+fn usage() -> ! {
+    eprintln!("usage: minidx-replay <recording.json> [--png <out.png>] [--log]");
+    std::process::exit(2);
+}
+
+fn summarize(label: &str, curve: &[(usize, f64)]) {
+    if curve.is_empty() {
+        return;
+    }
+    let (first, last) = (curve[0], curve[curve.len() - 1]);
+    let best = curve
+        .iter()
+        .cloned()
+        .fold(first, |b, p| if p.1 < b.1 { p } else { b });
+    println!(
+        "{:<12} {} points, steps {}..={}: first {:.5}, last {:.5}, best {:.5} @ {}",
+        label,
+        curve.len(),
+        first.0,
+        last.0,
+        first.1,
+        last.1,
+        best.1,
+        best.0
+    );
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (mut path, mut png, mut log) = (None, None, false);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--png" => png = Some(args.next().unwrap_or_else(|| usage())),
+            "--log" => log = true,
+            _ if path.is_none() => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let Some(path) = path else { usage() };
+
+    let rec = match Recording::open(&path) {
+        Ok(rec) => rec,
+        Err(e) => {
+            eprintln!("failed to read {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("{}: {} records", path, rec.records.len());
+    if let Some(params) = rec.params() {
+        println!("{:<12} {:?}", "params", params);
+    }
+    if let Some(info) = rec.last_train_info() {
+        println!(
+            "{:<12} step {}, lr {}, l1 {}, l2 {}",
+            "optimizer", info.step, info.lr, info.l1_reg, info.l2_reg
+        );
+        if let Some(norm) = info.grad_norm {
+            println!("{:<12} {} (before clipping)", "grad norm", norm);
+        }
+    }
+
+    let loss = rec.loss_curve();
+    summarize("loss", &loss);
+    if let Some(total_us) = rec
+        .batches()
+        .map(|b| b.time_us)
+        .reduce(|a, b| a.saturating_add(b))
+    {
+        println!(
+            "{:<12} {:.1}ms per recorded batch",
+            "timing",
+            total_us as f64 / 1000.0 / loss.len() as f64
+        );
+    }
+    summarize("validation", &rec.validation_curve());
+    for name in rec.scalar_names() {
+        summarize(name, &rec.scalar_curve(name));
+    }
+
+    if let Some((step, layers)) = rec.layer_stats().last() {
+        println!("layer stats @ step {}:", step);
+        println!(
+            "  {:<24} {:>8} {:>12} {:>12} {:>12} {:>9} {:>8}",
+            "path", "count", "param norm", "grad norm", "update ratio", "zero grad", "non-fin"
+        );
+        for l in layers {
+            println!(
+                "  {:<24} {:>8} {:>12.5} {:>12.5} {:>12.3e} {:>9.3} {:>8}",
+                l.path,
+                l.count,
+                l.param_norm,
+                l.grad_norm,
+                l.update_ratio,
+                l.zero_grad_frac,
+                l.non_finite_params + l.non_finite_grads
+            );
+        }
+    }
+
+    let non_finite: Vec<_> = rec.non_finite().collect();
+    if let Some(last) = non_finite.last() {
+        println!(
+            "{:<12} {} bad steps, last: {}",
+            "non-finite",
+            non_finite.len(),
+            last
+        );
+    }
+
+    let snapshots = rec.snapshot_steps();
+    if let Some(last) = snapshots.last() {
+        println!(
+            "{:<12} {} snapshots, latest at step {}",
+            "snapshots",
+            snapshots.len(),
+            last
+        );
+    }
+
+    if let Some(png) = png {
+        if loss.len() < 2 {
+            eprintln!("not enough batches recorded to chart the loss");
+            std::process::exit(1);
+        }
+        let mut chart = LineChart::new(log, Some(0.92));
+        for (step, loss) in loss {
+            chart.push(step as f32, loss as f32);
+        }
+        if let Err(e) = chart.save_png(&png, (1024, 600)) {
+            eprintln!("failed to write {}: {}", png, e);
+            std::process::exit(1);
+        }
+        println!("wrote loss curve to {}", png);
+    }
+}
@@ -0,0 +1,335 @@
+//! A live view of training, served over HTTP to a browser.
+//!
+//! The dashboard charts the loss, optimizer state and metrics as they are recorded,
+//! alongside the latest visualization of the network's parameters. The page and
+//! its charts are served by the dashboard itself, so no internet access is needed.
+//!
+//! ```no_run
+//! use minidx::dashboard::Dashboard;
+//! use minidx::recorder::Recorder;
+//!
+//! let dashboard = Dashboard::serve("127.0.0.1:8080").unwrap();
+//! println!("watch training at {}", dashboard.url());
+//!
+//! let mut recorder = Recorder::new().dashboard(&dashboard).build().unwrap();
+//! ```
+use crate::recorder::Record;
+use minidx_vis::{DrawTarget, Extent, ParamVisOpts, VisualizableNetwork};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const INDEX: &str = include_str!("dashboard/index.html");
+
+/// How long an event stream can be idle before a keep-alive is sent, which
+/// also notices browsers which have gone away.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// The number of records kept for browsers which connect later. Older records
+/// are dropped, so a long training run doesn't grow without bound.
+const HISTORY: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct Feed {
+    /// The most recent records serialized as JSON, in the order they were published.
+    records: VecDeque<String>,
+    /// The number of records ever published, including those since dropped.
+    published: usize,
+    /// The latest visualization of the network as a PNG.
+    network: Option<Vec<u8>>,
+    /// The number of visualizations published.
+    network_version: usize,
+}
+
+impl Feed {
+    fn push(&mut self, json: String, history: usize) {
+        if self.records.len() >= history {
+            self.records.pop_front();
+        }
+        self.records.push_back(json);
+        self.published += 1;
+    }
+
+    /// Returns the records published after the first `sent`, skipping any which
+    /// have been dropped.
+    fn since(&self, sent: usize) -> Vec<String> {
+        let dropped = self.published - self.records.len();
+        let skip = sent.saturating_sub(dropped);
+        self.records.iter().skip(skip).cloned().collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    feed: Mutex<Feed>,
+    updated: Condvar,
+}
+
+/// A handle to a running dashboard server, which publishes training progress to
+/// any browsers watching it.
+///
+/// Handles are cheap to clone. The server runs until the process exits.
+#[derive(Clone, Debug)]
+pub struct Dashboard {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl Dashboard {
+    /// Starts serving the dashboard on the given address, such as `"127.0.0.1:8080"`.
+    ///
+    /// Binding port 0 picks any free port, which can be found with [Dashboard::addr].
+    pub fn serve<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared::default());
+
+        let s = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let s = s.clone();
+                thread::spawn(move || {
+                    // Errors just mean the browser went away.
+                    let _ = handle(stream, &s);
+                });
+            }
+        });
+
+        Ok(Self { addr, shared })
+    }
+
+    /// Returns the address the dashboard is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the URL to open in a browser to watch the dashboard.
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// Publishes a record to the dashboard.
+    ///
+    /// Snapshots are skipped, as they hold every parameter of the network:
+    /// use [Dashboard::publish_network] to show the network instead.
+    pub fn publish(&self, record: &Record) {
+        if matches!(record, Record::Snapshot { .. }) {
+            return;
+        }
+        let Ok(json) = serde_json::to_string(record) else {
+            return;
+        };
+        self.shared.feed.lock().unwrap().push(json, HISTORY);
+        self.shared.updated.notify_all();
+    }
+
+    /// Renders the parameters of the network, and shows them on the dashboard
+    /// in place of any previous rendering.
+    pub fn publish_network<N>(&self, network: &N, opts: &ParamVisOpts) -> std::io::Result<()>
+    where
+        N: VisualizableNetwork<DrawTarget> + VisualizableNetwork<Extent>,
+    {
+        let png = minidx_vis::render_png(network, opts)?;
+        let mut feed = self.shared.feed.lock().unwrap();
+        feed.network = Some(png);
+        feed.network_version += 1;
+        drop(feed);
+        self.shared.updated.notify_all();
+        Ok(())
+    }
+}
+
+/// Writes a complete response, closing the connection after it.
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// Serves a single request.
+fn handle(mut stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers, which don't change the response.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let (method, target) = (parts.next(), parts.next().unwrap_or("/"));
+    if method != Some("GET") {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    }
+    match target.split('?').next().unwrap() {
+        "/" => respond(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            INDEX.as_bytes(),
+        ),
+        "/network.png" => match shared.feed.lock().unwrap().network.clone() {
+            Some(png) => respond(&mut stream, "200 OK", "image/png", &png),
+            None => respond(
+                &mut stream,
+                "404 Not Found",
+                "text/plain",
+                b"no network yet",
+            ),
+        },
+        "/events" => stream_events(stream, shared),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"not found"),
+    }
+}
+
+/// Streams the records published so far (up to [HISTORY] of them) as server-sent
+/// events, followed by records as they are published.
+fn stream_events(mut stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+
+    let (mut sent, mut version) = (0, 0);
+    loop {
+        let (records, network_version) = {
+            let mut feed = shared.feed.lock().unwrap();
+            while feed.published == sent && feed.network_version == version {
+                let (f, timeout) = shared.updated.wait_timeout(feed, KEEP_ALIVE).unwrap();
+                feed = f;
+                if timeout.timed_out() {
+                    break;
+                }
+            }
+            let records = feed.since(sent);
+            sent = feed.published;
+            (records, feed.network_version)
+        };
+
+        let mut events = String::new();
+        for r in records.iter() {
+            events.push_str(&format!("event: record\ndata: {}\n\n", r));
+        }
+        if network_version != version {
+            events.push_str(&format!("event: network\ndata: {}\n\n", network_version));
+        }
+        if events.is_empty() {
+            events.push_str(": keep-alive\n\n");
+        }
+        stream.write_all(events.as_bytes())?;
+        stream.flush()?;
+
+        version = network_version;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::BatchInfo;
+    use std::io::Read;
+
+    /// Sends a GET request, returning the response once the connection closes.
+    fn get(addr: SocketAddr, path: &str) -> Vec<u8> {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_feed_history() {
+        let mut feed = Feed::default();
+        for i in 0..5 {
+            feed.push(i.to_string(), 3);
+        }
+        assert_eq!(feed.records.len(), 3);
+        assert_eq!(feed.published, 5);
+        // Records which were dropped are skipped.
+        assert_eq!(feed.since(0), vec!["2", "3", "4"]);
+        assert_eq!(feed.since(3), vec!["3", "4"]);
+        assert!(feed.since(5).is_empty());
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let dashboard = Dashboard::serve("127.0.0.1:0").unwrap();
+        let addr = dashboard.addr();
+
+        let page = String::from_utf8(get(addr, "/")).unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("EventSource"));
+        assert!(String::from_utf8(get(addr, "/network.png"))
+            .unwrap()
+            .starts_with("HTTP/1.1 404"));
+
+        let network = minidx_core::layers::Dense::<f32, 2, 3>::default();
+        dashboard
+            .publish_network(&network, &ParamVisOpts::small())
+            .unwrap();
+        let png = get(addr, "/network.png");
+        assert!(png.starts_with(b"HTTP/1.1 200 OK"));
+        assert!(png.windows(4).any(|w| w == b"\x89PNG"));
+
+        // Records published before and after connecting are both streamed.
+        let batch = |step| {
+            Record::Batch(BatchInfo {
+                step,
+                size: 1,
+                loss: 0.5,
+                time_us: 1,
+            })
+        };
+        dashboard.publish(&batch(1));
+        dashboard.publish(&Record::Snapshot {
+            step: 1,
+            params: Default::default(),
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        write!(stream, "GET /events HTTP/1.1\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut events = vec![];
+        for l in reader.by_ref().lines() {
+            let l = l.unwrap();
+            if let Some(data) = l.strip_prefix("data: ") {
+                events.push(data.to_string());
+                if events.len() == 2 {
+                    break;
+                }
+            }
+        }
+        assert_eq!(events[0], serde_json::to_string(&batch(1)).unwrap());
+        assert_eq!(events[1], "1");
+
+        dashboard.publish(&batch(2));
+        let mut line = String::new();
+        while !line.starts_with("data: ") {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+        assert_eq!(
+            line.trim_end(),
+            format!("data: {}", serde_json::to_string(&batch(2)).unwrap())
+        );
+    }
+}
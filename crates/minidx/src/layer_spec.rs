@@ -24,11 +24,13 @@
 //! ```
 //!
 use crate::Buildable;
+use minidx_core::init::Init;
 use minidx_core::layers::{
-    Activation, Bias1d, Conv1d as Conv1dL, Dense as DenseL, Diag, RMSDiv, ScalarScale,
-    Softmax as SoftmaxL, Swish as SwishL, GLU as GLUL, LR,
+    Activation, Bias1d, Conv1d as Conv1dL, Dense as DenseL, Diag, Frozen, Initialized,
+    LayerOverride, RMSDiv, ScalarScale, Softmax as SoftmaxL, Swish as SwishL, GLU as GLUL, LR,
 };
 use minidx_core::matmul::MatMulImpl;
+use minidx_core::optimizers::ParamOverride;
 use minidx_core::{Const, Dtype, Float};
 
 /// A fully-connected layer with a fixed number of inputs and outputs. No bias.
@@ -327,6 +329,125 @@ impl<
     }
 }
 
+/// Freezes the parameters of the wrapped layer(s), for instance to fine-tune
+/// a network on top of a pretrained feature extractor.
+///
+///  - **E**: The datatype of the parameters (i.e. [f32]).
+///  - **I**: The number of inputs this layer takes.
+///  - **B**: The layer(s) this layer wraps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Freeze<E: Dtype, const I: usize, B: Buildable<E>> {
+    module: B,
+    pd: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, B: Buildable<E>> Freeze<E, I, B> {
+    /// Freezes the given layer(s).
+    pub fn new(module: B) -> Self {
+        Self {
+            module,
+            pd: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        B: crate::Buildable<E, Built = M>,
+        M: Clone + Default + std::fmt::Debug + minidx_core::Module<[E; I]>,
+    > Buildable<E> for Freeze<E, I, B>
+{
+    type Built = Frozen<E, I, M>;
+    fn try_build(&self) -> Result<Self::Built, crate::Error> {
+        Ok(Frozen {
+            module: self.module.try_build()?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Overrides the learning rate, weight decay and regularization of the wrapped layer(s).
+///
+///  - **E**: The datatype of the parameters (i.e. [f32]).
+///  - **I**: The number of inputs this layer takes.
+///  - **B**: The layer(s) this layer wraps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Override<E: Dtype, const I: usize, B: Buildable<E>> {
+    module: B,
+    overrides: ParamOverride,
+    pd: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, B: Buildable<E>> Override<E, I, B> {
+    /// Applies the given overrides to the training parameters of the given layer(s).
+    pub fn new(module: B, overrides: ParamOverride) -> Self {
+        Self {
+            module,
+            overrides,
+            pd: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        B: crate::Buildable<E, Built = M>,
+        M: Clone + Default + std::fmt::Debug + minidx_core::Module<[E; I]>,
+    > Buildable<E> for Override<E, I, B>
+{
+    type Built = LayerOverride<E, I, M>;
+    fn try_build(&self) -> Result<Self::Built, crate::Error> {
+        Ok(LayerOverride {
+            module: self.module.try_build()?,
+            overrides: self.overrides,
+            ..Default::default()
+        })
+    }
+}
+
+/// Selects the initialization scheme of the wrapped layer(s), overriding the
+/// scheme otherwise chosen based on the activation which follows.
+///
+///  - **E**: The datatype of the parameters (i.e. [f32]).
+///  - **I**: The number of inputs this layer takes.
+///  - **B**: The layer(s) this layer wraps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WithInit<E: Dtype, const I: usize, B: Buildable<E>> {
+    module: B,
+    init: Init,
+    pd: std::marker::PhantomData<E>,
+}
+
+impl<E: Dtype, const I: usize, B: Buildable<E>> WithInit<E, I, B> {
+    /// Initializes the given layer(s) using the given scheme.
+    pub fn new(module: B, init: Init) -> Self {
+        Self {
+            module,
+            init,
+            pd: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<
+        E: Dtype,
+        const I: usize,
+        B: crate::Buildable<E, Built = M>,
+        M: Clone + Default + std::fmt::Debug + minidx_core::Module<[E; I]>,
+    > Buildable<E> for WithInit<E, I, B>
+{
+    type Built = Initialized<E, I, M>;
+    fn try_build(&self) -> Result<Self::Built, crate::Error> {
+        Ok(Initialized {
+            module: self.module.try_build()?,
+            init: self.init,
+            ..Default::default()
+        })
+    }
+}
+
 /// The 'Dynamic Tanh' normalization layer.
 /// See: <https://arxiv.org/abs/2503.10622>
 ///
@@ -431,6 +552,122 @@ mod tests {
         let _realized = Buildable::<f32>::build(&(Conv1d::<4, 2, 3>::default(),));
     }
 
+    #[test]
+    fn test_freeze_and_override() {
+        use crate::Buildable;
+        use minidx_core::loss::DiffLoss;
+        use minidx_core::optimizers::TrainParams;
+        use minidx_core::{LoadableModule, ResetParams};
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        let mut network = Buildable::<f32>::build(&(
+            Freeze::<f32, 2, _>::new(Linear::<2, 3>::default()),
+            Relu,
+            Override::<f32, 3, _>::new(
+                Linear::<3, 3>::default(),
+                ParamOverride::default().with_lr_scale(0.0),
+            ),
+            Linear::<3, 1>::default(),
+        ));
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+        network.rand_params(&mut rng, 1.0).unwrap();
+
+        let save = |network: &_| {
+            let mut params = HashMap::new();
+            LoadableModule::save(network, "".into(), &mut params).unwrap();
+            params
+        };
+        let before = save(&network);
+        // Wrapped layers keep the paths of the layers they wrap.
+        assert!(before.contains_key(".0.0") && before.contains_key(".2.1"));
+
+        let mut params = TrainParams::with_lr(0.1);
+        for _ in 0..3 {
+            crate::train_batch(
+                &mut params,
+                &mut network,
+                |got, want| (got.mse(want), got.mse_input_grads(want)),
+                &mut || ([1.0f32, 2.0], [0.5f32]),
+                4,
+            );
+        }
+
+        let after = save(&network);
+        for path in [".0.0", ".0.1", ".2.0", ".2.1"] {
+            assert_eq!(before[path], after[path], "{}", path);
+        }
+        assert_ne!(before[".3.0"], after[".3.0"]);
+    }
+
+    #[test]
+    fn test_init() {
+        use crate::Buildable;
+        use minidx_core::{LoadableModule, ResetParams};
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        let mut network = Buildable::<f32>::build(&(
+            Linear::<64, 64>::default(),
+            Relu,
+            Linear::<64, 64>::default(),
+            Tanh,
+            WithInit::<f32, 64, _>::new(Linear::<64, 64>::default(), Init::LeCunUniform),
+            Relu,
+        ));
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+        network.init_params(&mut rng).unwrap();
+
+        let mut params = HashMap::new();
+        network.save("".into(), &mut params).unwrap();
+        let var = |p: &Vec<f64>| p.iter().map(|v| v * v).sum::<f64>() / p.len() as f64;
+        let max = |p: &Vec<f64>| p.iter().fold(0.0f64, |m, v| m.max(v.abs()));
+
+        // Kaiming before the ReLU, Xavier before the tanh.
+        assert!((var(&params[".0.0"]) - 2.0 / 64.0).abs() < 0.004);
+        assert!((var(&params[".2.0"]) - 1.0 / 64.0).abs() < 0.002);
+        // The explicitly selected scheme takes precedence over the ReLU which follows.
+        assert!(max(&params[".4.0"]) <= (3.0f64 / 64.0).sqrt());
+        assert!((var(&params[".4.0"]) - 1.0 / 64.0).abs() < 0.002);
+    }
+
+    #[test]
+    fn test_summary() {
+        use crate::Buildable;
+        use minidx_core::Summarize;
+
+        let network = Buildable::<f32>::build(&(
+            Freeze::<f32, 2, _>::new(Linear::<2, 3>::default()),
+            Relu,
+            GLU::<3, 4>::default(),
+            Conv1d::<4, 2, 3>::default(),
+            Softmax::default(),
+        ));
+        let s = network.summary();
+        let kinds: Vec<_> = s.network.children.iter().map(|c| c.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "sequential (frozen)",
+                "activation",
+                "glu",
+                "conv1d",
+                "softmax"
+            ]
+        );
+        assert_eq!(s.network.params, 9 + 2 * (12 + 4) + 3);
+        assert_eq!(s.network.trainable, 2 * (12 + 4) + 3);
+        assert_eq!((s.network.input, s.network.output), (2, 2));
+
+        let table = s.to_string();
+        assert!(table.contains("\n  dense "), "{}", table);
+        assert!(
+            table.contains("total params: 44 (35 trainable)"),
+            "{}",
+            table
+        );
+    }
+
     #[test]
     fn test_basic_typed_composition() {
         type NetType = ((Linear<1, 3>, Relu), LeakyRelu);
@@ -45,6 +45,11 @@
 //! [`rand_params`](`core::ResetParams::rand_params`) performs sensible initialization of each layer using
 //! the given RNG. The float argument represents the max magnitude of random parameters. `0.5` to `1.0` is a good starting parameter.
 //!
+//! Alternatively, [`init_params`](`core::ResetParams::init_params`) initializes each layer using a
+//! scheme computed from its fan-in and fan-out, such as Kaiming/He initialization for layers followed
+//! by a ReLU, and Xavier/Glorot initialization for layers followed by a tanh. The scheme of a layer
+//! can be selected explicitly by wrapping it in [`layers::WithInit`](`layer_spec::WithInit`).
+//!
 //! ### Training
 //!
 //! Training a network in minidx requires two things:
@@ -113,29 +118,77 @@
 //! ```
 //!
 //! Networks can be loaded and stored using [`LoadableModule`](core::LoadableModule).
+//!
+//! ### Summarizing a network
+//!
+//! [`summary()`](`core::Summarize::summary`) lists each layer of a network, nested to match
+//! its tuples, along with its shape, parameter count and memory footprint.
+//!
+//! ```
+//! # use minidx::prelude::*;
+//! # use layers::*;
+//! # type network = (
+//! #   (Linear::<2, 3>, Relu),
+//! #   Softmax,
+//! # );
+//! # let network = Buildable::<f32>::build(&network::default());
+//! println!("{}", network.summary());
+//! ```
+//!
+//! ### Networks defined at runtime
+//!
+//! When the architecture isn't known at compile time, such as when it's read from a config
+//! file, a [`NetworkSpec`](core::dynamic::NetworkSpec) can be deserialized and built into a
+//! [`DynNetwork`](core::dynamic::DynNetwork). Its inputs and outputs are vectors, but
+//! otherwise it trains, saves and summarizes like any other network.
+//!
+//! ```
+//! # use minidx::prelude::*;
+//! use minidx::core::dynamic::NetworkSpec;
+//!
+//! let spec: NetworkSpec = serde_json::from_str(r#"{
+//!     "inputs": 2,
+//!     "layers": [
+//!         {"kind": "linear", "outputs": 3},
+//!         {"kind": "relu"},
+//!         {"kind": "softmax"}
+//!     ]
+//! }"#).unwrap();
+//! let network = Buildable::<f32>::build(&spec);
+//! let output = network.forward(&vec![1.0, 2.0]).unwrap(); // outputs Vec<f32> of length 3
+//! ```
 pub use minidx_core as core;
 
 pub mod layer_spec;
-pub use minidx_core::{train_batch, train_batch_parallel, train_step};
-use minidx_core::{Dtype, Error};
+use minidx_core::matmul::MatMulImpl;
+pub use minidx_core::{train_batch, train_batch_parallel, train_step, GradAccumulator};
+use minidx_core::{Dtype, Error, Float};
 
 /// Common types and traits needed when using minidx.
 pub mod prelude {
     pub use crate::layer_spec as layers;
     pub use crate::Buildable;
+    pub use minidx_core::init::Init;
     pub use minidx_core::loss;
-    pub use minidx_core::optimizers::TrainParams;
+    pub use minidx_core::optimizers::{ParamOverride, TrainParams};
     pub use minidx_core::{
-        BackpropModule, Error, LoadableModule, Module, ResetParams, TracedModule,
+        BackpropModule, Error, LoadableModule, Module, ResetParams, Summarize, TracedModule,
     };
 
-    pub use crate::{train_batch, train_batch_parallel, train_step};
+    pub use crate::{train_batch, train_batch_parallel, train_step, GradAccumulator};
 }
 
+pub mod metrics;
+
 pub mod problem;
 
 pub mod recorder;
 
+pub mod search;
+
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+
 /// OneHotEncoder describes the encoding of some integer value modulus N into
 /// a vector where exactly one value is set.
 #[derive(Clone, Debug, Default)]
@@ -182,6 +235,14 @@ tuple_impls!([M1, M2, M3, M4], [0, 1, 2, 3]);
 tuple_impls!([M1, M2, M3, M4, M5], [0, 1, 2, 3, 4]);
 tuple_impls!([M1, M2, M3, M4, M5, M6], [0, 1, 2, 3, 4, 5]);
 
+#[cfg(feature = "serde")]
+impl<E: Float + MatMulImpl> Buildable<E> for minidx_core::dynamic::NetworkSpec {
+    type Built = minidx_core::dynamic::DynNetwork<E>;
+    fn try_build(&self) -> Result<Self::Built, Error> {
+        minidx_core::dynamic::DynNetwork::new(self).map_err(|_| ())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,398 @@
+//! Evaluation metrics for trained networks.
+//!
+//! Metrics are accumulated one sample at a time, so they can be computed over a
+//! fixed number of samples drawn from a [Problem](crate::problem::Problem), or over
+//! every sample in a dataset:
+//!
+//! ```
+//! # use minidx::prelude::*;
+//! # use layers::*;
+//! use minidx::metrics;
+//! use minidx::problem::ModularAddition10;
+//! use rand::{SeedableRng, rngs::SmallRng};
+//!
+//! let nn = Buildable::<f32>::build(&((Linear::<20, 10>::default(), Softmax::default())));
+//! let mut problem = ModularAddition10::new(SmallRng::seed_from_u64(42));
+//!
+//! let m = metrics::eval_classification(&nn, metrics::sampled(&mut problem, 100), 3);
+//! println!("accuracy={}, top-3={}", m.accuracy(), m.top_k_accuracy());
+//! ```
+use crate::problem::Problem;
+use minidx_core::{Dtype, Module};
+use serde::{Deserialize, Serialize};
+
+/// Returns the index of the largest value in the given output.
+///
+/// If multiple values are equally the largest, the first index is returned.
+pub fn argmax<E: Dtype, const O: usize>(v: &[E; O]) -> usize {
+    let mut best = 0;
+    for (i, x) in v.iter().enumerate().skip(1) {
+        if *x > v[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Returns true if `idx` is one of the `k` largest values in the given output.
+fn in_top_k<E: Dtype, const O: usize>(v: &[E; O], idx: usize, k: usize) -> bool {
+    // The index is in the top-k if fewer than k values strictly outrank it.
+    let target = v[idx];
+    v.iter()
+        .enumerate()
+        .filter(|(i, x)| **x > target || (**x == target && *i < idx))
+        .count()
+        < k
+}
+
+/// Returns an iterator yielding `n` input-output pairs sampled from the given problem.
+pub fn sampled<P: Problem>(
+    problem: &mut P,
+    n: usize,
+) -> impl Iterator<Item = (P::Input, P::Output)> + '_ {
+    (0..n).map(|_| problem.sample())
+}
+
+/// Accumulates classification metrics over one-hot (or probability) outputs with `O` classes.
+///
+/// The predicted class of an output is its [argmax], as is the true class of
+/// the target (so the outputs of [OneHotEncoder](crate::OneHotEncoder) are suitable).
+#[derive(Clone, Debug)]
+pub struct ClassificationMetrics<const O: usize> {
+    /// Counts of samples, indexed by `[true class][predicted class]`.
+    pub confusion: [[usize; O]; O],
+    /// The `k` used when computing top-k accuracy.
+    pub top_k: usize,
+    top_k_correct: usize,
+    samples: usize,
+}
+
+impl<const O: usize> ClassificationMetrics<O> {
+    /// Constructs an empty set of metrics, which computes top-k accuracy for the given `k`.
+    pub fn new(top_k: usize) -> Self {
+        Self {
+            confusion: [[0; O]; O],
+            top_k,
+            top_k_correct: 0,
+            samples: 0,
+        }
+    }
+
+    /// Accumulates the result of a single sample.
+    pub fn push<E: Dtype>(&mut self, got: &[E; O], want: &[E; O]) {
+        let truth = argmax(want);
+        self.confusion[truth][argmax(got)] += 1;
+        if in_top_k(got, truth, self.top_k) {
+            self.top_k_correct += 1;
+        }
+        self.samples += 1;
+    }
+
+    /// The number of samples accumulated.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// The fraction of samples where the predicted class was correct.
+    pub fn accuracy(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        let correct: usize = (0..O).map(|i| self.confusion[i][i]).sum();
+        correct as f32 / self.samples as f32
+    }
+
+    /// The fraction of samples where the correct class was in the `top_k` predictions.
+    pub fn top_k_accuracy(&self) -> f32 {
+        if self.samples == 0 {
+            return 0.0;
+        }
+        self.top_k_correct as f32 / self.samples as f32
+    }
+
+    /// The fraction of predictions of the given class which were correct.
+    pub fn precision(&self, class: usize) -> f32 {
+        let predicted: usize = (0..O).map(|i| self.confusion[i][class]).sum();
+        if predicted == 0 {
+            return 0.0;
+        }
+        self.confusion[class][class] as f32 / predicted as f32
+    }
+
+    /// The fraction of samples of the given class which were predicted correctly.
+    pub fn recall(&self, class: usize) -> f32 {
+        let actual: usize = self.confusion[class].iter().sum();
+        if actual == 0 {
+            return 0.0;
+        }
+        self.confusion[class][class] as f32 / actual as f32
+    }
+
+    /// The harmonic mean of the precision and recall of the given class.
+    pub fn f1(&self, class: usize) -> f32 {
+        let (p, r) = (self.precision(class), self.recall(class));
+        if p + r == 0.0 {
+            return 0.0;
+        }
+        2.0 * p * r / (p + r)
+    }
+
+    /// The unweighted mean of the F1 score of each class.
+    pub fn macro_f1(&self) -> f32 {
+        if O == 0 {
+            return 0.0;
+        }
+        (0..O).map(|c| self.f1(c)).sum::<f32>() / O as f32
+    }
+
+    /// Returns a serializable summary of the metrics, suitable for recording.
+    pub fn report(&self) -> ClassificationReport {
+        ClassificationReport {
+            samples: self.samples,
+            accuracy: self.accuracy(),
+            top_k: self.top_k,
+            top_k_accuracy: self.top_k_accuracy(),
+            confusion: self.confusion.iter().map(|r| r.to_vec()).collect(),
+            per_class: (0..O)
+                .map(|c| ClassScores {
+                    precision: self.precision(c),
+                    recall: self.recall(c),
+                    f1: self.f1(c),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The precision, recall and F1 score of a single class.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ClassScores {
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+}
+
+/// A summary of [ClassificationMetrics], serialized during recording.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ClassificationReport {
+    pub samples: usize,
+    pub accuracy: f32,
+    pub top_k: usize,
+    pub top_k_accuracy: f32,
+    /// Counts of samples, indexed by `[true class][predicted class]`.
+    pub confusion: Vec<Vec<usize>>,
+    pub per_class: Vec<ClassScores>,
+}
+
+/// Accumulates regression metrics over outputs, treating each output element
+/// as a separate prediction.
+#[derive(Clone, Debug, Default)]
+pub struct RegressionMetrics {
+    n: usize,
+    abs_err: f64,
+    sq_err: f64,
+    target_sum: f64,
+    target_sq_sum: f64,
+}
+
+impl RegressionMetrics {
+    /// Accumulates the result of a single sample.
+    pub fn push<E: Dtype, const O: usize>(&mut self, got: &[E; O], want: &[E; O]) {
+        for (g, w) in got.iter().zip(want.iter()) {
+            let (g, w) = (g.to_f64().unwrap(), w.to_f64().unwrap());
+            let err = g - w;
+            self.abs_err += err.abs();
+            self.sq_err += err * err;
+            self.target_sum += w;
+            self.target_sq_sum += w * w;
+        }
+        self.n += O;
+    }
+
+    /// The number of predictions accumulated.
+    pub fn count(&self) -> usize {
+        self.n
+    }
+
+    /// The mean absolute error.
+    pub fn mae(&self) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+        self.abs_err / self.n as f64
+    }
+
+    /// The root of the mean squared error.
+    pub fn rmse(&self) -> f64 {
+        if self.n == 0 {
+            return 0.0;
+        }
+        (self.sq_err / self.n as f64).sqrt()
+    }
+
+    /// The coefficient of determination: the fraction of variance in the targets
+    /// which is explained by the predictions.
+    ///
+    /// Returns `None` if the targets have no variance.
+    pub fn r2(&self) -> Option<f64> {
+        if self.n == 0 {
+            return None;
+        }
+        let mean = self.target_sum / self.n as f64;
+        let ss_tot = self.target_sq_sum - self.n as f64 * mean * mean;
+        if ss_tot <= 0.0 {
+            return None;
+        }
+        Some(1.0 - self.sq_err / ss_tot)
+    }
+
+    /// Returns a serializable summary of the metrics, suitable for recording.
+    pub fn report(&self) -> RegressionReport {
+        RegressionReport {
+            count: self.n,
+            mae: self.mae(),
+            rmse: self.rmse(),
+            r2: self.r2(),
+        }
+    }
+}
+
+/// A summary of [RegressionMetrics], serialized during recording.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RegressionReport {
+    pub count: usize,
+    pub mae: f64,
+    pub rmse: f64,
+    pub r2: Option<f64>,
+}
+
+/// Evaluation results which can be saved by a [Recorder](crate::recorder::Recorder).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MetricsReport {
+    Classification(ClassificationReport),
+    Regression(RegressionReport),
+}
+
+impl<const O: usize> From<&ClassificationMetrics<O>> for MetricsReport {
+    fn from(m: &ClassificationMetrics<O>) -> Self {
+        MetricsReport::Classification(m.report())
+    }
+}
+
+impl From<&RegressionMetrics> for MetricsReport {
+    fn from(m: &RegressionMetrics) -> Self {
+        MetricsReport::Regression(m.report())
+    }
+}
+
+/// Runs the network over each input-output pair, computing classification metrics.
+pub fn eval_classification<Input, E: Dtype, const O: usize, NN: Module<Input, Output = [E; O]>>(
+    nn: &NN,
+    data: impl IntoIterator<Item = (Input, [E; O])>,
+    top_k: usize,
+) -> ClassificationMetrics<O> {
+    let mut m = ClassificationMetrics::new(top_k);
+    for (input, target) in data {
+        m.push(&nn.forward(&input).unwrap(), &target);
+    }
+    m
+}
+
+/// Runs the network over each input-output pair, computing regression metrics.
+pub fn eval_regression<Input, E: Dtype, const O: usize, NN: Module<Input, Output = [E; O]>>(
+    nn: &NN,
+    data: impl IntoIterator<Item = (Input, [E; O])>,
+) -> RegressionMetrics {
+    let mut m = RegressionMetrics::default();
+    for (input, target) in data {
+        m.push(&nn.forward(&input).unwrap(), &target);
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OneHotEncoder;
+
+    #[test]
+    fn test_argmax() {
+        assert_eq!(argmax(&[0.1f32, 0.7, 0.2]), 1);
+        assert_eq!(argmax(&[0.5f32, 0.5]), 0);
+        assert_eq!(argmax(&[-3.0f32]), 0);
+    }
+
+    #[test]
+    fn test_in_top_k() {
+        let v = [0.1f32, 0.4, 0.3, 0.2];
+        assert!(in_top_k(&v, 1, 1));
+        assert!(!in_top_k(&v, 2, 1));
+        assert!(in_top_k(&v, 2, 2));
+        assert!(!in_top_k(&v, 0, 3));
+        assert!(in_top_k(&v, 0, 4));
+    }
+
+    #[test]
+    fn test_classification() {
+        let mut m = ClassificationMetrics::<3>::new(2);
+        let cls = OneHotEncoder::<3>::value::<f32>;
+        m.push(&[0.8, 0.1, 0.1], &cls(0)); // correct
+        m.push(&[0.6, 0.3, 0.1], &cls(1)); // wrong, but in top-2
+        m.push(&[0.1, 0.2, 0.7], &cls(2)); // correct
+        m.push(&[0.1, 0.2, 0.7], &cls(0)); // wrong
+
+        assert_eq!(m.samples(), 4);
+        assert_eq!(m.accuracy(), 0.5);
+        assert_eq!(m.top_k_accuracy(), 0.75);
+        assert_eq!(m.confusion, [[1, 0, 1], [1, 0, 0], [0, 0, 1]]);
+
+        assert_eq!(m.precision(0), 0.5);
+        assert_eq!(m.recall(0), 0.5);
+        assert_eq!(m.f1(0), 0.5);
+        assert_eq!(m.precision(1), 0.0);
+        assert_eq!(m.f1(1), 0.0);
+        assert_eq!(m.precision(2), 0.5);
+        assert_eq!(m.recall(2), 1.0);
+
+        let r = m.report();
+        assert_eq!(r.confusion[0], vec![1, 0, 1]);
+        assert_eq!(r.per_class.len(), 3);
+    }
+
+    #[test]
+    fn test_regression() {
+        let mut m = RegressionMetrics::default();
+        m.push(&[1.0f32, 2.0], &[1.0, 3.0]);
+        m.push(&[3.0f32, 4.0], &[3.0, 5.0]);
+
+        assert_eq!(m.count(), 4);
+        assert_eq!(m.mae(), 0.5);
+        assert!((m.rmse() - 0.5f64.sqrt()).abs() < 1.0e-9);
+        // targets [1, 3, 3, 5]: mean 3, ss_tot = 8, ss_res = 2
+        assert!((m.r2().unwrap() - 0.75).abs() < 1.0e-9);
+
+        let mut m = RegressionMetrics::default();
+        m.push(&[1.0f32], &[1.0]);
+        assert_eq!(m.r2(), None);
+    }
+
+    #[test]
+    fn test_eval_classification() {
+        use minidx_core::layers::Dense;
+        let mut nn = Dense::<f32, 2, 2>::default();
+        let mut store = std::collections::HashMap::new();
+        // Identity: output class is whichever input is larger.
+        store.insert("".to_string(), vec![1.0, 0.0, 0.0, 1.0]);
+        use minidx_core::LoadableModule;
+        nn.load("".into(), &store).unwrap();
+
+        let data = [
+            ([1.0, 0.0], [1.0, 0.0]),
+            ([0.0, 1.0], [0.0, 1.0]),
+            ([0.2, 0.9], [1.0, 0.0]),
+        ];
+        let m = eval_classification(&nn, data, 1);
+        assert_eq!(m.samples(), 3);
+        assert_eq!(m.confusion, [[1, 1], [0, 1]]);
+    }
+}
@@ -34,6 +34,25 @@ impl<E: Dtype, RNG: rand::Rng, const I: usize, const O: usize> ImgClassification
 
         Ok(Self { rng, data })
     }
+
+    /// The number of images in the dataset.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns true if the dataset contains no images.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns an iterator over every image in the dataset, in order, along with its
+    /// one-hot encoded label.
+    pub fn iter(&self) -> impl Iterator<Item = ([E; I], [E; O])> + '_ {
+        use crate::OneHotEncoder;
+        self.data
+            .iter()
+            .map(|(input, label)| (*input, OneHotEncoder::<O>::value(*label)))
+    }
 }
 
 impl<E: Dtype, RNG: rand::Rng, const I: usize, const O: usize> Problem
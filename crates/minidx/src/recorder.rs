@@ -1,9 +1,17 @@
+use crate::metrics::MetricsReport;
+use minidx_core::guard::NonFinite;
 use minidx_core::optimizers::{TrainInfo, TrainParams};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
+use std::io::Write;
 use std::time::Instant;
 
+mod reader;
+pub use reader::{Recording, RecordingReader};
+mod stats;
+pub use stats::{LayerStats, StatsTap};
+
 /// How often something should happen, in terms of steps or time.
 #[derive(Clone, Debug)]
 pub enum Every {
@@ -23,6 +31,50 @@ impl Every {
     }
 }
 
+/// Checks if the given schedule fires, updating when it last fired if so.
+fn fire(schedule: &mut (Every, usize, Instant), step: usize, now: Instant) -> bool {
+    let (e, last_steps, last_time) = schedule;
+    if e.fires(step, now, *last_steps, *last_time) {
+        *last_steps = step;
+        *last_time = now;
+        true
+    } else {
+        false
+    }
+}
+
+/// Saves the parameters of the network, keyed by path.
+fn save_params<N: minidx_core::LoadableModule>(
+    nn: &N,
+) -> std::io::Result<HashMap<String, Vec<f64>>> {
+    let mut params = HashMap::new();
+    nn.save("".into(), &mut params)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", e)))?;
+    Ok(params)
+}
+
+/// Schedules records identified by name, each of which fires independently.
+#[derive(Debug)]
+struct NamedSchedule {
+    every: Every,
+    // name => (last_fired_step, last_fired_time)
+    last: HashMap<String, (usize, Instant)>,
+}
+
+impl NamedSchedule {
+    /// Checks if the named record is due, updating when it last fired if so.
+    fn fires(&mut self, name: &str, step: usize, now: Instant, created: Instant) -> bool {
+        let (last_steps, last_time) = self.last.entry(name.to_string()).or_insert((0, created));
+        if self.every.fires(step, now, *last_steps, *last_time) {
+            *last_steps = step;
+            *last_time = now;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Builds a recorder, which can be used to track the progress of training.
 #[derive(Clone, Debug)]
 pub struct RecorderBuilder {
@@ -30,6 +82,12 @@ pub struct RecorderBuilder {
     batch: Option<Every>,
     info: Option<Every>,
     snapshot: Option<Every>,
+    validation: Option<Every>,
+    scalar: Option<Every>,
+    histogram: Option<Every>,
+    layer_stats: Option<Every>,
+    #[cfg(feature = "dashboard")]
+    dashboard: Option<crate::dashboard::Dashboard>,
 }
 
 impl Default for RecorderBuilder {
@@ -39,6 +97,12 @@ impl Default for RecorderBuilder {
             batch: Some(Every::Steps(250)),
             info: Some(Every::Steps(2000)),
             snapshot: Some(Every::Seconds(2 * 60)),
+            validation: Some(Every::Steps(1000)),
+            scalar: Some(Every::Steps(1000)),
+            histogram: Some(Every::Steps(5000)),
+            layer_stats: Some(Every::Steps(1000)),
+            #[cfg(feature = "dashboard")]
+            dashboard: None,
         }
     }
 }
@@ -57,6 +121,19 @@ impl RecorderBuilder {
             batch: self.batch.map(|e| (e, 0, now)),
             info: self.info.map(|e| (e, 0, now)),
             snapshot: self.snapshot.map(|e| (e, 0, now)),
+            validation: self.validation.map(|e| (e, 0, now)),
+            layer_stats: self.layer_stats.map(|e| (e, 0, now)),
+            scalar: self.scalar.map(|every| NamedSchedule {
+                every,
+                last: HashMap::new(),
+            }),
+            histogram: self.histogram.map(|every| NamedSchedule {
+                every,
+                last: HashMap::new(),
+            }),
+            created: now,
+            #[cfg(feature = "dashboard")]
+            dashboard: self.dashboard,
         })
     }
 
@@ -83,6 +160,39 @@ impl RecorderBuilder {
         self.snapshot = Some(e);
         self
     }
+
+    /// Configures how often the validation loss should be recorded.
+    pub fn validation_freq(mut self, e: Every) -> Self {
+        self.validation = Some(e);
+        self
+    }
+
+    /// Configures how often each named scalar metric should be recorded.
+    pub fn scalar_freq(mut self, e: Every) -> Self {
+        self.scalar = Some(e);
+        self
+    }
+
+    /// Configures how often each named histogram should be recorded.
+    pub fn histogram_freq(mut self, e: Every) -> Self {
+        self.histogram = Some(e);
+        self
+    }
+
+    /// Configures how often per-layer statistics should be recorded.
+    ///
+    /// See [Recorder::record_layer_stats].
+    pub fn layer_stats_freq(mut self, e: Every) -> Self {
+        self.layer_stats = Some(e);
+        self
+    }
+
+    /// Configures a dashboard which records should also be published to.
+    #[cfg(feature = "dashboard")]
+    pub fn dashboard(mut self, d: &crate::dashboard::Dashboard) -> Self {
+        self.dashboard = Some(d.clone());
+        self
+    }
 }
 
 /// Tracks the training of a neural network, making snapshots and computing metrics.
@@ -96,6 +206,15 @@ pub struct Recorder {
     batch: Option<(Every, usize, Instant)>,
     info: Option<(Every, usize, Instant)>,
     snapshot: Option<(Every, usize, Instant)>,
+    validation: Option<(Every, usize, Instant)>,
+    layer_stats: Option<(Every, usize, Instant)>,
+
+    scalar: Option<NamedSchedule>,
+    histogram: Option<NamedSchedule>,
+    created: Instant,
+
+    #[cfg(feature = "dashboard")]
+    dashboard: Option<crate::dashboard::Dashboard>,
 }
 
 impl Recorder {
@@ -114,9 +233,14 @@ impl Recorder {
     }
 
     fn write(&mut self, record: Record) -> std::io::Result<()> {
-        if let Some(f) = &self.file {
+        #[cfg(feature = "dashboard")]
+        if let Some(d) = &self.dashboard {
+            d.publish(&record);
+        }
+        if let Some(mut f) = self.file.as_ref() {
             serde_json::to_writer(f, &record)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", e)))
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", e)))?;
+            f.write_all(b"\n")
         } else {
             Ok(())
         }
@@ -136,45 +260,10 @@ impl Recorder {
             self.sent_params = true;
         }
 
-        let write_info = self
-            .info
-            .as_mut()
-            .map(|(e, last_steps, last_time)| {
-                if e.fires(info.step, now, *last_steps, *last_time) {
-                    *last_steps = info.step;
-                    *last_time = now;
-                    true
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(false);
-        let write_batch = self
-            .batch
-            .as_mut()
-            .map(|(e, last_steps, last_time)| {
-                if e.fires(info.step, now, *last_steps, *last_time) {
-                    *last_steps = info.step;
-                    *last_time = now;
-                    true
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(false);
-        let write_snapshot = self
-            .snapshot
-            .as_mut()
-            .map(|(e, last_steps, last_time)| {
-                if e.fires(info.step, now, *last_steps, *last_time) {
-                    *last_steps = info.step;
-                    *last_time = now;
-                    true
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(false);
+        let step = info.step;
+        let write_info = self.info.as_mut().is_some_and(|s| fire(s, step, now));
+        let write_batch = self.batch.as_mut().is_some_and(|s| fire(s, step, now));
+        let write_snapshot = self.snapshot.as_mut().is_some_and(|s| fire(s, step, now));
 
         if write_info {
             self.write(Record::TrainInfo(params.into()))?;
@@ -183,18 +272,192 @@ impl Recorder {
             self.write(Record::Batch(info.clone()))?;
         }
         if write_snapshot {
-            let mut params = HashMap::new();
-            nn.save("".into(), &mut params)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, format!("{:?}", e)))?;
-
             self.write(Record::Snapshot {
                 step: info.step,
-                params: params,
+                params: save_params(nn)?,
             })?;
         }
 
         Ok(())
     }
+
+    /// Records the results of an evaluation, such as [ClassificationMetrics](crate::metrics::ClassificationMetrics).
+    ///
+    /// Unlike [Recorder::record_batch], metrics are always written: evaluations are
+    /// expensive, so it's up to the caller to decide how often to compute them.
+    pub fn record_metrics<M: Into<MetricsReport>>(
+        &mut self,
+        step: usize,
+        metrics: M,
+    ) -> std::io::Result<()> {
+        self.write(Record::Metrics {
+            step,
+            metrics: metrics.into(),
+        })
+    }
+
+    /// Records a training step which was discarded by a
+    /// [NonFiniteGuard](minidx_core::guard::NonFiniteGuard).
+    ///
+    /// Like [Recorder::record_metrics], these are always written.
+    pub fn record_non_finite(&mut self, event: &NonFinite) -> std::io::Result<()> {
+        self.write(Record::NonFinite(event.clone()))
+    }
+
+    /// Offers the validation loss to the recorder.
+    ///
+    /// The loss is only computed (by calling `loss`) when a validation record is due,
+    /// so an expensive evaluation can be passed in directly. Returns true if the
+    /// validation loss was recorded.
+    pub fn record_validation<F: FnOnce() -> f64>(
+        &mut self,
+        step: usize,
+        loss: F,
+    ) -> std::io::Result<bool> {
+        let now = Instant::now();
+        if !self.validation.as_mut().is_some_and(|s| fire(s, step, now)) {
+            return Ok(false);
+        }
+
+        self.write(Record::Validation { step, loss: loss() })?;
+        Ok(true)
+    }
+
+    /// Offers a named scalar metric, such as accuracy or a gradient norm, to the recorder.
+    ///
+    /// Each name is scheduled independently, and `value` is only called when a record
+    /// for that name is due. Returns true if the metric was recorded.
+    pub fn record_scalar<F: FnOnce() -> f64>(
+        &mut self,
+        step: usize,
+        name: &str,
+        value: F,
+    ) -> std::io::Result<bool> {
+        let (now, created) = (Instant::now(), self.created);
+        if !self
+            .scalar
+            .as_mut()
+            .is_some_and(|s| s.fires(name, step, now, created))
+        {
+            return Ok(false);
+        }
+
+        self.write(Record::Scalar {
+            step,
+            name: name.to_string(),
+            value: value(),
+        })?;
+        Ok(true)
+    }
+
+    /// Offers a named histogram, such as the distribution of a layers' weights, to the recorder.
+    ///
+    /// Each name is scheduled independently, and `hist` is only called when a record
+    /// for that name is due. Returns true if the histogram was recorded.
+    pub fn record_histogram<F: FnOnce() -> Histogram>(
+        &mut self,
+        step: usize,
+        name: &str,
+        hist: F,
+    ) -> std::io::Result<bool> {
+        let (now, created) = (Instant::now(), self.created);
+        if !self
+            .histogram
+            .as_mut()
+            .is_some_and(|s| s.fires(name, step, now, created))
+        {
+            return Ok(false);
+        }
+
+        self.write(Record::Histogram {
+            step,
+            name: name.to_string(),
+            hist: hist(),
+        })?;
+        Ok(true)
+    }
+
+    /// Records per-layer statistics captured by a [StatsTap] during the last training step,
+    /// such as the gradient norm and update-to-weight ratio of each parameter tensor.
+    ///
+    /// This should be called after every training step: the tap is only armed to capture
+    /// statistics when a record is due for the next step. Returns true if statistics were recorded.
+    pub fn record_layer_stats<N: minidx_core::LoadableModule, GA>(
+        &mut self,
+        step: usize,
+        nn: &N,
+        tap: &mut StatsTap<GA>,
+    ) -> std::io::Result<bool> {
+        let now = Instant::now();
+        let Some(schedule) = self.layer_stats.as_mut() else {
+            tap.arm(false);
+            return Ok(false);
+        };
+
+        let captured = tap.take();
+        if captured.is_some() {
+            (schedule.1, schedule.2) = (step, now);
+        }
+        tap.arm(schedule.0.fires(step + 1, now, schedule.1, schedule.2));
+
+        let Some(captured) = captured else {
+            return Ok(false);
+        };
+        let mut paths = Vec::new();
+        nn.trained_paths("".into(), &mut paths);
+        self.write(Record::LayerStats {
+            step,
+            layers: stats::name_layers(captured, &paths, &save_params(nn)?),
+        })?;
+        Ok(true)
+    }
+}
+
+/// Counts of values falling into equal-width bins between `min` and `max`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+    /// The number of values which were NaN or infinite, and not binned.
+    pub non_finite: usize,
+}
+
+impl Histogram {
+    /// Bins the given values into a histogram with the given number of bins.
+    pub fn new<I: IntoIterator<Item = f64>>(values: I, bins: usize) -> Self {
+        let bins = bins.max(1);
+        let (mut finite, mut non_finite) = (Vec::new(), 0);
+        for v in values {
+            if v.is_finite() {
+                finite.push(v);
+            } else {
+                non_finite += 1;
+            }
+        }
+
+        let min = finite.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = finite.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let mut counts = vec![0; bins];
+        if !finite.is_empty() {
+            let width = (max - min) / bins as f64;
+            for v in finite.iter() {
+                let idx = if width > 0.0 {
+                    (((v - min) / width) as usize).min(bins - 1)
+                } else {
+                    0
+                };
+                counts[idx] += 1;
+            }
+        }
+
+        Self {
+            min: if finite.is_empty() { 0.0 } else { min },
+            max: if finite.is_empty() { 0.0 } else { max },
+            counts,
+            non_finite,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -214,4 +477,67 @@ pub enum Record {
         step: usize,
         params: HashMap<String, Vec<f64>>,
     },
+    Metrics {
+        step: usize,
+        metrics: MetricsReport,
+    },
+    Validation {
+        step: usize,
+        loss: f64,
+    },
+    Scalar {
+        step: usize,
+        name: String,
+        value: f64,
+    },
+    Histogram {
+        step: usize,
+        name: String,
+        hist: Histogram,
+    },
+    LayerStats {
+        step: usize,
+        layers: Vec<LayerStats>,
+    },
+    NonFinite(NonFinite),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let h = Histogram::new([0.0, 1.0, 2.0, 3.0, 4.0, f64::NAN], 2);
+        assert_eq!(h.min, 0.0);
+        assert_eq!(h.max, 4.0);
+        assert_eq!(h.counts, vec![2, 3]);
+        assert_eq!(h.non_finite, 1);
+
+        let h = Histogram::new([1.5, 1.5], 3);
+        assert_eq!(h.counts, vec![2, 0, 0]);
+
+        let h = Histogram::new([], 3);
+        assert_eq!(h.counts, vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scheduling() {
+        let mut r = Recorder::new()
+            .validation_freq(Every::Steps(10))
+            .scalar_freq(Every::Steps(5))
+            .build()
+            .unwrap();
+
+        assert!(!r.record_validation(5, || unreachable!()).unwrap());
+        assert!(r.record_validation(10, || 1.0).unwrap());
+        assert!(!r.record_validation(15, || unreachable!()).unwrap());
+        assert!(r.record_validation(20, || 1.0).unwrap());
+
+        // Names are scheduled independently
+        assert!(r.record_scalar(5, "acc", || 0.5).unwrap());
+        assert!(r.record_scalar(5, "lr", || 0.1).unwrap());
+        assert!(!r.record_scalar(6, "acc", || unreachable!()).unwrap());
+        assert!(r.record_scalar(10, "acc", || 0.6).unwrap());
+    }
 }
@@ -0,0 +1,245 @@
+use super::{BatchInfo, LayerStats, Record};
+use minidx_core::guard::NonFinite;
+use minidx_core::optimizers::{TrainInfo, TrainParams};
+use minidx_core::{LoadSaveError, LoadableModule};
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+
+/// Reads the records written by a [Recorder](super::Recorder), one at a time.
+///
+/// Both newline-delimited recordings and older recordings (where records were
+/// written back-to-back) are accepted.
+pub struct RecordingReader<R: Read> {
+    inner: serde_json::StreamDeserializer<'static, serde_json::de::IoRead<BufReader<R>>, Record>,
+}
+
+impl RecordingReader<std::fs::File> {
+    /// Opens the recording at the given path.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(std::fs::File::open(path)?))
+    }
+}
+
+impl<R: Read> RecordingReader<R> {
+    /// Reads records from the given reader.
+    pub fn new(r: R) -> Self {
+        Self {
+            inner: serde_json::Deserializer::from_reader(BufReader::new(r)).into_iter(),
+        }
+    }
+
+    /// Reads all remaining records into a [Recording].
+    pub fn read_all(self) -> std::io::Result<Recording> {
+        Ok(Recording {
+            records: self.collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = std::io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|r| r.map_err(std::io::Error::from))
+    }
+}
+
+/// All the records of a training run, with helpers for extracting curves and snapshots.
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    pub records: Vec<Record>,
+}
+
+impl Recording {
+    /// Reads the recording at the given path.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        RecordingReader::open(path)?.read_all()
+    }
+
+    /// Returns the parameters the network was trained with, if recorded.
+    pub fn params(&self) -> Option<&TrainParams> {
+        self.records.iter().find_map(|r| match r {
+            Record::Params(p) => Some(p),
+            _ => None,
+        })
+    }
+
+    /// Returns the most recent optimizer state, if recorded.
+    pub fn last_train_info(&self) -> Option<&TrainInfo> {
+        self.records.iter().rev().find_map(|r| match r {
+            Record::TrainInfo(i) => Some(i),
+            _ => None,
+        })
+    }
+
+    /// Returns the recorded batches, in the order they were recorded.
+    pub fn batches(&self) -> impl Iterator<Item = &BatchInfo> + '_ {
+        self.records.iter().filter_map(|r| match r {
+            Record::Batch(b) => Some(b),
+            _ => None,
+        })
+    }
+
+    /// Returns the training loss as (step, loss) pairs.
+    pub fn loss_curve(&self) -> Vec<(usize, f64)> {
+        self.batches().map(|b| (b.step, b.loss)).collect()
+    }
+
+    /// Returns the validation loss as (step, loss) pairs.
+    pub fn validation_curve(&self) -> Vec<(usize, f64)> {
+        self.records
+            .iter()
+            .filter_map(|r| match r {
+                Record::Validation { step, loss } => Some((*step, *loss)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the values of the named scalar as (step, value) pairs.
+    pub fn scalar_curve(&self, name: &str) -> Vec<(usize, f64)> {
+        self.records
+            .iter()
+            .filter_map(|r| match r {
+                Record::Scalar {
+                    step,
+                    name: n,
+                    value,
+                } if n == name => Some((*step, *value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the names of all recorded scalars, sorted.
+    pub fn scalar_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .records
+            .iter()
+            .filter_map(|r| match r {
+                Record::Scalar { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Returns the recorded per-layer statistics as (step, stats) pairs.
+    pub fn layer_stats(&self) -> impl Iterator<Item = (usize, &[LayerStats])> + '_ {
+        self.records.iter().filter_map(|r| match r {
+            Record::LayerStats { step, layers } => Some((*step, layers.as_slice())),
+            _ => None,
+        })
+    }
+
+    /// Returns the training steps which were discarded for being non-finite.
+    pub fn non_finite(&self) -> impl Iterator<Item = &NonFinite> + '_ {
+        self.records.iter().filter_map(|r| match r {
+            Record::NonFinite(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Returns the steps at which a snapshot of the network was recorded.
+    pub fn snapshot_steps(&self) -> Vec<usize> {
+        self.snapshots().map(|(step, _)| step).collect()
+    }
+
+    fn snapshots(&self) -> impl Iterator<Item = (usize, &HashMap<String, Vec<f64>>)> + '_ {
+        self.records.iter().filter_map(|r| match r {
+            Record::Snapshot { step, params } => Some((*step, params)),
+            _ => None,
+        })
+    }
+
+    /// Loads the parameters from the snapshot at the given step into the network.
+    pub fn restore<N: LoadableModule>(&self, step: usize, nn: &mut N) -> Result<(), LoadSaveError> {
+        match self.snapshots().find(|(s, _)| *s == step) {
+            Some((_, params)) => nn.load("".into(), params),
+            None => Err(LoadSaveError {
+                path: "".into(),
+                err: format!("no snapshot at step {}", step),
+            }),
+        }
+    }
+
+    /// Loads the parameters from the most recent snapshot into the network,
+    /// returning the step of that snapshot.
+    pub fn restore_latest<N: LoadableModule>(&self, nn: &mut N) -> Result<usize, LoadSaveError> {
+        match self.snapshots().last() {
+            Some((step, params)) => nn.load("".into(), params).map(|_| step),
+            None => Err(LoadSaveError {
+                path: "".into(),
+                err: "no snapshots recorded".into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::{Every, Recorder};
+    use minidx_core::Module;
+
+    #[test]
+    fn test_round_trip() {
+        use minidx_core::layers::Dense;
+        let path = std::env::temp_dir().join(format!("minidx_reader_{}.json", std::process::id()));
+
+        let mut nn = Dense::<f32, 2, 2>::default();
+        let mut store = HashMap::new();
+        store.insert("".to_string(), vec![1.0, 2.0, 3.0, 4.0]);
+        nn.load("".into(), &store).unwrap();
+
+        let mut r = Recorder::new()
+            .save_to(path.to_str().unwrap())
+            .batch_freq(Every::Steps(1))
+            .snapshot_freq(Every::Steps(2))
+            .validation_freq(Every::Steps(1))
+            .scalar_freq(Every::Steps(1))
+            .build()
+            .unwrap();
+        let params = TrainParams::with_lr(0.1);
+        for step in 1..=4 {
+            let info = BatchInfo {
+                step,
+                size: 8,
+                loss: 1.0 / step as f64,
+                time_us: 10,
+            };
+            r.record_batch(info, &params, &nn).unwrap();
+            r.record_validation(step, || 2.0).unwrap();
+            r.record_scalar(step, "acc", || 0.5).unwrap();
+        }
+        r.flush().unwrap();
+
+        let rec = Recording::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(rec.params().is_some());
+        assert_eq!(rec.loss_curve().len(), 4);
+        assert_eq!(rec.loss_curve()[1], (2, 0.5));
+        assert_eq!(rec.validation_curve().len(), 4);
+        assert_eq!(rec.scalar_names(), vec!["acc"]);
+        assert_eq!(rec.snapshot_steps(), vec![2, 4]);
+
+        let mut restored = Dense::<f32, 2, 2>::default();
+        assert_eq!(rec.restore_latest(&mut restored).unwrap(), 4);
+        assert_eq!(
+            restored.forward(&[1.0, 1.0]).unwrap(),
+            nn.forward(&[1.0, 1.0]).unwrap()
+        );
+        assert!(rec.restore(3, &mut restored).is_err());
+    }
+
+    #[test]
+    fn test_concatenated() {
+        // Recordings written before records were newline-delimited.
+        let data = r#"{"Validation":{"step":1,"loss":0.5}}{"Validation":{"step":2,"loss":0.25}}"#;
+        let rec = RecordingReader::new(data.as_bytes()).read_all().unwrap();
+        assert_eq!(rec.validation_curve(), vec![(1, 0.5), (2, 0.25)]);
+    }
+}
@@ -0,0 +1,319 @@
+use minidx_core::optimizers::{GradAdjuster, GradApplyer, ParamOverride};
+use minidx_core::Gradients;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Statistics about a single parameter tensor of a network, computed during an update step.
+///
+/// See [Recorder::record_layer_stats](super::Recorder::record_layer_stats).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LayerStats {
+    /// The [LoadableModule](minidx_core::LoadableModule) path of the parameters, or
+    /// `#<n>` if the parameters could not be matched to a path.
+    pub path: String,
+    /// The number of parameters.
+    pub count: usize,
+    /// The L2 norm of the parameters, before the update.
+    pub param_norm: f64,
+    /// The L2 norm of the (batch-averaged) gradients, before any optimizer adjustments.
+    pub grad_norm: f64,
+    /// The L2 norm of the change in the parameters made by the update, divided by
+    /// the L2 norm of the parameters before the update.
+    pub update_ratio: f64,
+    /// The fraction of gradients which were exactly zero.
+    ///
+    /// For the bias feeding into a ReLU, this approximates the fraction of dead
+    /// units: units which were inactive for every sample in the batch.
+    pub zero_grad_frac: f64,
+    /// The number of parameters which are NaN or infinite, after the update.
+    pub non_finite_params: usize,
+    /// The number of gradients which are NaN or infinite.
+    pub non_finite_grads: usize,
+}
+
+/// Wraps an optimizer, capturing per-layer statistics when armed.
+///
+/// Pass the tap to the training functions in place of the optimizer, and
+/// call [Recorder::record_layer_stats](super::Recorder::record_layer_stats) after
+/// each step. The recorder arms the tap only when statistics are due, so the
+/// optimizer is otherwise unaffected.
+#[derive(Clone, Debug)]
+pub struct StatsTap<GA> {
+    inner: GA,
+    armed: bool,
+    grads: Vec<f64>,
+    grads_offset: usize,
+    // The stats for each parameter tensor, in the order they were updated.
+    layers: Vec<LayerStats>,
+    done: bool,
+}
+
+impl<GA> StatsTap<GA> {
+    /// Wraps the given optimizer. The tap starts armed, so the first step is captured.
+    pub fn new(inner: GA) -> Self {
+        Self {
+            inner,
+            armed: true,
+            grads: Vec::new(),
+            grads_offset: 0,
+            layers: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the wrapped optimizer.
+    pub fn inner(&self) -> &GA {
+        &self.inner
+    }
+
+    /// Returns the wrapped optimizer, mutably.
+    pub fn inner_mut(&mut self) -> &mut GA {
+        &mut self.inner
+    }
+
+    /// Unwraps the tap, returning the optimizer.
+    pub fn into_inner(self) -> GA {
+        self.inner
+    }
+
+    pub(super) fn arm(&mut self, armed: bool) {
+        self.armed = armed;
+    }
+
+    /// Takes the statistics captured during the last step, if any.
+    pub(super) fn take(&mut self) -> Option<Vec<LayerStats>> {
+        if self.done {
+            self.done = false;
+            Some(std::mem::take(&mut self.layers))
+        } else {
+            None
+        }
+    }
+}
+
+impl<G: Gradients, GA: GradAdjuster<G>> GradAdjuster<G> for StatsTap<GA> {
+    fn adjust(&mut self, gradient_updates: G, loss: f32) -> G {
+        if self.armed {
+            self.grads = to_f64(gradient_updates.grad_iter());
+            self.grads_offset = 0;
+            self.layers.clear();
+        }
+        self.inner.adjust(gradient_updates, loss)
+    }
+}
+
+impl<GA: GradApplyer> GradApplyer for StatsTap<GA> {
+    fn apply<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+    ) -> Result<(), minidx_core::Error> {
+        self.apply_with_override(gradient_updates, weights, &ParamOverride::default())
+    }
+
+    fn apply_with_override<G: Gradients>(
+        &mut self,
+        gradient_updates: G,
+        weights: &mut G,
+        overrides: &ParamOverride,
+    ) -> Result<(), minidx_core::Error> {
+        if !self.armed {
+            return self
+                .inner
+                .apply_with_override(gradient_updates, weights, overrides);
+        }
+
+        let before = to_f64(weights.grad_iter());
+        self.inner
+            .apply_with_override(gradient_updates, weights, overrides)?;
+        if before.is_empty() {
+            return Ok(());
+        }
+
+        let end = (self.grads_offset + before.len()).min(self.grads.len());
+        let grads = &self.grads[self.grads_offset.min(end)..end];
+        self.grads_offset = end;
+
+        let after = to_f64(weights.grad_iter());
+        let param_norm = norm(before.iter());
+        let step: Vec<f64> = after
+            .iter()
+            .zip(before.iter())
+            .map(|(a, b)| a - b)
+            .collect();
+        let update_norm = norm(step.iter());
+        let stats = LayerStats {
+            path: format!("#{}", self.layers.len()),
+            count: before.len(),
+            param_norm,
+            grad_norm: norm(grads.iter()),
+            update_ratio: if param_norm > 0.0 {
+                update_norm / param_norm
+            } else {
+                0.0
+            },
+            zero_grad_frac: if grads.is_empty() {
+                0.0
+            } else {
+                grads.iter().filter(|g| **g == 0.0).count() as f64 / grads.len() as f64
+            },
+            non_finite_params: after.iter().filter(|v| !v.is_finite()).count(),
+            non_finite_grads: grads.iter().filter(|g| !g.is_finite()).count(),
+        };
+        self.layers.push(stats);
+        Ok(())
+    }
+
+    fn advance_step(&mut self) {
+        self.inner.advance_step();
+        if self.armed {
+            self.armed = false;
+            self.done = true;
+        }
+    }
+}
+
+fn to_f64<'a, E: ToPrimitive + 'a>(it: impl Iterator<Item = &'a E>) -> Vec<f64> {
+    it.map(|v| v.to_f64().unwrap()).collect()
+}
+
+fn norm<'a>(it: impl Iterator<Item = &'a f64>) -> f64 {
+    it.map(|v| v * v).sum::<f64>().sqrt()
+}
+
+/// Names each captured tensor by the path of the parameters it updated, given
+/// the paths of the trained parameters in the order they are updated.
+pub(super) fn name_layers(
+    mut captured: Vec<LayerStats>,
+    paths: &[String],
+    saved: &HashMap<String, Vec<f64>>,
+) -> Vec<LayerStats> {
+    for (stats, path) in captured.iter_mut().zip(paths) {
+        // A tensor of the wrong size means the paths are out of step with the updates,
+        // such as from a module which doesn't save its parameters. The remaining
+        // tensors are left unnamed rather than mislabelled.
+        if saved.get(path).map(|v| v.len()) != Some(stats.count) {
+            break;
+        }
+        stats.path = path.clone();
+    }
+    captured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::{Every, Recorder, Recording};
+    use crate::{layer_spec, Buildable};
+    use minidx_core::loss::DiffLoss;
+    use minidx_core::optimizers::TrainParams;
+
+    #[test]
+    fn test_layer_stats() {
+        let path = std::env::temp_dir().join(format!("minidx_stats_{}.json", std::process::id()));
+        let mut network = Buildable::<f32>::build(&(
+            layer_spec::Linear::<2, 4>::default(),
+            layer_spec::Relu,
+            layer_spec::Linear::<4, 1>::default(),
+        ));
+        use minidx_core::ResetParams;
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::SmallRng::seed_from_u64(1);
+        network.rand_params(&mut rng, 1.0).unwrap();
+
+        let mut r = Recorder::new()
+            .save_to(path.to_str().unwrap())
+            .layer_stats_freq(Every::Steps(2))
+            .build()
+            .unwrap();
+        let mut tap = StatsTap::new(TrainParams::with_lr(0.1));
+
+        let mut recorded = Vec::new();
+        for step in 1..=4 {
+            crate::train_batch(
+                &mut tap,
+                &mut network,
+                |got, want| (got.mse(want), got.mse_input_grads(want)),
+                &mut || ([1.0f32, 2.0], [0.5f32]),
+                4,
+            );
+            recorded.push(r.record_layer_stats(step, &network, &mut tap).unwrap());
+        }
+        r.flush().unwrap();
+        assert_eq!(recorded, vec![true, false, true, false]);
+
+        let rec = Recording::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stats: Vec<_> = rec.layer_stats().collect();
+        assert_eq!(stats.len(), 2);
+
+        let (step, layers) = &stats[0];
+        assert_eq!(*step, 1);
+        let paths: Vec<_> = layers.iter().map(|l| l.path.as_str()).collect();
+        assert_eq!(paths, vec![".0.0", ".0.1", ".2.0", ".2.1"]);
+        for l in layers.iter() {
+            assert!(l.param_norm > 0.0);
+            assert_eq!(l.non_finite_params, 0);
+        }
+    }
+
+    #[test]
+    fn test_update_ratio() {
+        // The update ratio reflects the step which was applied, after overrides.
+        use minidx_core::optimizers::ParamOverride;
+        use minidx_core::ResetParams;
+        use rand::SeedableRng;
+        let mut network = Buildable::<f32>::build(&(
+            layer_spec::Override::<f32, 2, _>::new(
+                layer_spec::Linear::<2, 2>::default(),
+                ParamOverride::default().with_lr_scale(0.0),
+            ),
+            layer_spec::Linear::<2, 1>::default(),
+        ));
+        network
+            .rand_params(&mut rand::rngs::SmallRng::seed_from_u64(1), 1.0)
+            .unwrap();
+        let mut tap = StatsTap::new(TrainParams::with_lr(0.1));
+        crate::train_step(
+            &mut tap,
+            &mut network,
+            |got, want| (got.mse(want), got.mse_input_grads(want)),
+            [1.0f32, 2.0],
+            [0.5f32],
+        );
+
+        let ratios: Vec<_> = tap.take().unwrap().iter().map(|l| l.update_ratio).collect();
+        assert_eq!(ratios.len(), 4);
+        assert_eq!(ratios[..2], [0.0, 0.0]);
+        assert!(ratios[2..].iter().all(|r| *r > 0.0), "{:?}", ratios);
+    }
+
+    #[test]
+    fn test_identical_layers() {
+        // Every tensor is zero-initialized, so all tensors of the same size are identical,
+        // and the frozen layer saves parameters which are never updated.
+        use minidx_core::LoadableModule;
+        let mut network = Buildable::<f32>::build(&(
+            layer_spec::Freeze::<f32, 2, _>::new(layer_spec::Linear::<2, 2>::default()),
+            layer_spec::Linear::<2, 2>::default(),
+            layer_spec::Linear::<2, 2>::default(),
+        ));
+        let mut tap = StatsTap::new(TrainParams::with_lr(0.1));
+        crate::train_step(
+            &mut tap,
+            &mut network,
+            |got, want| (got.mse(want), got.mse_input_grads(want)),
+            [1.0f32, 2.0],
+            [0.5f32, 1.0],
+        );
+
+        let mut paths = Vec::new();
+        network.trained_paths("".into(), &mut paths);
+        let mut saved = HashMap::new();
+        network.save("".into(), &mut saved).unwrap();
+        let layers = name_layers(tap.take().unwrap(), &paths, &saved);
+        let paths: Vec<_> = layers.iter().map(|l| l.path.as_str()).collect();
+        assert_eq!(paths, vec![".1.0", ".1.1", ".2.0", ".2.1"]);
+    }
+}
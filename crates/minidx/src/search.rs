@@ -0,0 +1,858 @@
+//! Searches for good training hyperparameters.
+//!
+//! A [Search] trains many copies of a network on a [Problem], each with a different
+//! configuration of [TrainParams] and optimizer drawn from a [Space], and ranks
+//! the configurations by their loss on held-out samples:
+//!
+//! ```
+//! # use minidx::prelude::*;
+//! # use layers::*;
+//! use minidx::problem::Parity;
+//! use minidx::search::{Range, Search, Space, Strategy};
+//! use rand::{SeedableRng, rngs::SmallRng};
+//!
+//! let space = Space::with_lr(Range::Log { min: 1.0e-3, max: 1.0e-1 })
+//!     .and_beta(Range::Choice(vec![0.9, 0.99]));
+//! let results = Search::new(space, Strategy::SuccessiveHalving { trials: 4, eta: 2 })
+//!     .steps(25)
+//!     .run(
+//!         || Buildable::<f32>::build(&((Linear::<3, 6>::default(), Relu), Linear::<6, 2>::default())),
+//!         |seed| Parity::<f32, 3, _>::new(SmallRng::seed_from_u64(seed)),
+//!         |got, want| {
+//!             use loss::LogitLoss;
+//!             (got.logit_bce(want), got.logit_bce_input_grads(want))
+//!         },
+//!     )
+//!     .unwrap();
+//! println!("{}", results);
+//! ```
+use crate::problem::Problem;
+use crate::recorder::{BatchInfo, Recorder, RecorderBuilder};
+use minidx_core::optimizers::{GradAdjuster, GradApplyer, Momentum, RMSProp, TrainParams};
+use minidx_core::{train_batch, BackpropModule, Float, Gradients, LoadableModule, ResetParams};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+/// The values a hyperparameter may take.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Range {
+    /// Always the given value.
+    Fixed(f32),
+    /// One of the given values, which must not be empty.
+    Choice(Vec<f32>),
+    /// Any value between `min` and `max`.
+    Linear { min: f32, max: f32 },
+    /// Any value between `min` and `max`, spread evenly over orders of magnitude.
+    /// Suited to parameters like the learning rate.
+    Log { min: f32, max: f32 },
+}
+
+impl Range {
+    /// Samples a value uniformly from the range.
+    fn sample<R: Rng>(&self, rng: &mut R) -> f32 {
+        match self {
+            Range::Fixed(v) => *v,
+            Range::Choice(values) => values[rng.random_range(0..values.len())],
+            Range::Linear { min, max } if min < max => rng.random_range(*min..=*max),
+            Range::Log { min, max } if min < max => rng.random_range(min.ln()..=max.ln()).exp(),
+            Range::Linear { min, .. } | Range::Log { min, .. } => *min,
+        }
+    }
+
+    /// Returns the given number of evenly-spaced values, including both ends
+    /// of the range, or every value of a [Range::Choice].
+    fn grid(&self, points: usize) -> Vec<f32> {
+        let points = points.max(1);
+        let lerp = |min: f32, max: f32| {
+            (0..points).map(move |i| match points {
+                1 => min,
+                _ => min + (max - min) * i as f32 / (points - 1) as f32,
+            })
+        };
+        match self {
+            Range::Fixed(v) => vec![*v],
+            Range::Choice(values) => values.clone(),
+            Range::Linear { min, max } => lerp(*min, *max).collect(),
+            Range::Log { min, max } => lerp(min.ln(), max.ln()).map(f32::exp).collect(),
+        }
+    }
+}
+
+/// An optimizer which can be used to train a network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Optimizer {
+    /// SGD with momentum, see [Momentum].
+    Momentum,
+    /// RMSProp without momentum, see [RMSProp].
+    RMSProp,
+    /// RMSProp with momentum, see [RMSProp].
+    RMSPropWithMomentum,
+}
+
+impl Optimizer {
+    fn uses_momentum(&self) -> bool {
+        matches!(self, Optimizer::Momentum | Optimizer::RMSPropWithMomentum)
+    }
+
+    fn uses_beta(&self) -> bool {
+        matches!(self, Optimizer::RMSProp | Optimizer::RMSPropWithMomentum)
+    }
+}
+
+impl fmt::Display for Optimizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Optimizer::Momentum => "momentum",
+            Optimizer::RMSProp => "rmsprop",
+            Optimizer::RMSPropWithMomentum => "rmsprop+momentum",
+        })
+    }
+}
+
+/// Describes the hyperparameters to search over.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Space {
+    /// The learning rate.
+    pub lr: Range,
+    /// The L2 regularization, if any.
+    pub l2: Option<Range>,
+    /// The number of steps over which the learning rate is ramped up, if any.
+    /// Values are rounded to a whole number of steps.
+    pub soft_start: Option<Range>,
+    /// The optimizers to try.
+    pub optimizers: Vec<Optimizer>,
+    /// The momentum coefficient, for optimizers with momentum.
+    pub momentum: Range,
+    /// The RMSProp beta, for optimizers based on RMSProp.
+    pub beta: Range,
+}
+
+impl Space {
+    /// Returns a space over the given learning rates, trying each optimizer
+    /// with a momentum coefficient of 0.5 and a beta of 0.9.
+    pub fn with_lr(lr: Range) -> Self {
+        Self {
+            lr,
+            l2: None,
+            soft_start: None,
+            optimizers: vec![
+                Optimizer::Momentum,
+                Optimizer::RMSProp,
+                Optimizer::RMSPropWithMomentum,
+            ],
+            momentum: Range::Fixed(0.5),
+            beta: Range::Fixed(0.9),
+        }
+    }
+
+    /// Searches over the given L2 regularization.
+    pub fn and_l2(mut self, l2: Range) -> Self {
+        self.l2 = Some(l2);
+        self
+    }
+
+    /// Searches over the given number of soft-start steps.
+    pub fn and_soft_start(mut self, steps: Range) -> Self {
+        self.soft_start = Some(steps);
+        self
+    }
+
+    /// Searches over the given momentum coefficients.
+    pub fn and_momentum(mut self, momentum: Range) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    /// Searches over the given RMSProp betas.
+    pub fn and_beta(mut self, beta: Range) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    /// Only tries the given optimizers.
+    pub fn with_optimizers(mut self, optimizers: &[Optimizer]) -> Self {
+        self.optimizers = optimizers.to_vec();
+        self
+    }
+
+    /// Returns an error if no configuration can be drawn from the space, because
+    /// it has no optimizers or a [Range::Choice] it uses is empty.
+    pub fn check(&self) -> Result<(), String> {
+        if self.optimizers.is_empty() {
+            return Err("no optimizers to search over".into());
+        }
+        let uses = |f: fn(&Optimizer) -> bool| self.optimizers.iter().any(f);
+        let ranges = [
+            ("lr", Some(&self.lr)),
+            ("l2", self.l2.as_ref()),
+            ("soft_start", self.soft_start.as_ref()),
+            (
+                "momentum",
+                uses(Optimizer::uses_momentum).then_some(&self.momentum),
+            ),
+            ("beta", uses(Optimizer::uses_beta).then_some(&self.beta)),
+        ];
+        for (name, range) in ranges {
+            if matches!(range, Some(Range::Choice(values)) if values.is_empty()) {
+                return Err(format!("no choices for {}", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Samples a configuration uniformly from the space.
+    ///
+    /// Panics if the space is empty, see [Space::check].
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Config {
+        let optimizer = self.optimizers[rng.random_range(0..self.optimizers.len())];
+        Config {
+            optimizer,
+            lr: self.lr.sample(rng),
+            l2: self.l2.as_ref().map(|r| r.sample(rng)),
+            soft_start: self
+                .soft_start
+                .as_ref()
+                .map(|r| r.sample(rng).round() as usize),
+            momentum: optimizer.uses_momentum().then(|| self.momentum.sample(rng)),
+            beta: optimizer.uses_beta().then(|| self.beta.sample(rng)),
+        }
+    }
+
+    /// Returns every combination of values, taking the given number of
+    /// evenly-spaced points from each range.
+    pub fn grid(&self, points: usize) -> Vec<Config> {
+        let optional = |r: &Option<Range>| match r {
+            Some(r) => r.grid(points).into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+        let (l2s, soft_starts) = (optional(&self.l2), optional(&self.soft_start));
+
+        let mut out = Vec::new();
+        for &optimizer in self.optimizers.iter() {
+            let momentums = match optimizer.uses_momentum() {
+                true => optional(&Some(self.momentum.clone())),
+                false => vec![None],
+            };
+            let betas = match optimizer.uses_beta() {
+                true => optional(&Some(self.beta.clone())),
+                false => vec![None],
+            };
+
+            for lr in self.lr.grid(points) {
+                for &l2 in l2s.iter() {
+                    for &soft_start in soft_starts.iter() {
+                        for &momentum in momentums.iter() {
+                            for &beta in betas.iter() {
+                                out.push(Config {
+                                    optimizer,
+                                    lr,
+                                    l2,
+                                    soft_start: soft_start.map(|s| s.round() as usize),
+                                    momentum,
+                                    beta,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A configuration of hyperparameters.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub optimizer: Optimizer,
+    pub lr: f32,
+    pub l2: Option<f32>,
+    pub soft_start: Option<usize>,
+    /// The momentum coefficient, zero if unset. Ignored by [Optimizer::RMSProp].
+    pub momentum: Option<f32>,
+    /// The RMSProp beta, 0.9 if unset. Ignored by [Optimizer::Momentum].
+    pub beta: Option<f32>,
+}
+
+impl Config {
+    /// Returns the training parameters of the configuration.
+    pub fn train_params(&self) -> TrainParams {
+        let mut params = TrainParams::with_lr(self.lr);
+        if let Some(l2) = self.l2 {
+            params = params.and_l2(l2);
+        }
+        if let Some(steps) = self.soft_start {
+            params = params.and_soft_start(steps);
+        }
+        params
+    }
+}
+
+/// How configurations are chosen and trained.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Strategy {
+    /// Trains the given number of configurations sampled at random.
+    Random { trials: usize },
+    /// Trains every configuration on a grid, taking the given number of
+    /// evenly-spaced points from each range. See [Space::grid].
+    Grid { points: usize },
+    /// Trains the given number of configurations sampled at random, then repeatedly
+    /// keeps the best `1/eta` of them and trains those `eta` times longer, until
+    /// only one remains.
+    SuccessiveHalving { trials: usize, eta: usize },
+}
+
+/// The result of training a configuration.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrialResult {
+    /// The index of the trial, which names its recording.
+    pub trial: usize,
+    pub config: Config,
+    /// The number of steps the configuration was trained for, including the step
+    /// where its loss diverged, if it did.
+    pub steps: usize,
+    /// The average loss over the evaluation samples, or infinity if training diverged.
+    pub loss: f32,
+}
+
+/// The results of a search, ranked from best to worst.
+///
+/// Configurations trained for more steps (which survived more rounds of successive
+/// halving) are ranked first, and then by loss. Displays as a table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub trials: Vec<TrialResult>,
+}
+
+impl SearchResults {
+    /// Returns the best configuration, if any were trained.
+    pub fn best(&self) -> Option<&TrialResult> {
+        self.trials.first()
+    }
+}
+
+impl fmt::Display for SearchResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let opt = |v: Option<f32>| v.map(|v| format!("{:.3e}", v)).unwrap_or("-".into());
+        writeln!(
+            f,
+            "{:>4} {:>5}  {:<16} {:>10} {:>10} {:>10} {:>8} {:>8} {:>8} {:>12}",
+            "rank",
+            "trial",
+            "optimizer",
+            "lr",
+            "l2",
+            "soft start",
+            "momentum",
+            "beta",
+            "steps",
+            "loss"
+        )?;
+        writeln!(f, "{}", "-".repeat(100))?;
+        for (rank, t) in self.trials.iter().enumerate() {
+            let c = &t.config;
+            writeln!(
+                f,
+                "{:>4} {:>5}  {:<16} {:>10.3e} {:>10} {:>10} {:>8} {:>8} {:>8} {:>12.6}",
+                rank + 1,
+                t.trial,
+                c.optimizer,
+                c.lr,
+                opt(c.l2),
+                c.soft_start.map(|s| s.to_string()).unwrap_or("-".into()),
+                c.momentum
+                    .map(|v| format!("{:.3}", v))
+                    .unwrap_or("-".into()),
+                c.beta.map(|v| format!("{:.3}", v)).unwrap_or("-".into()),
+                t.steps,
+                t.loss,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a hyperparameter search, training each configuration in parallel.
+#[derive(Clone, Debug)]
+pub struct Search {
+    space: Space,
+    strategy: Strategy,
+    steps: usize,
+    batch_size: usize,
+    eval_samples: usize,
+    seed: u64,
+    recorder: Option<(String, RecorderBuilder)>,
+}
+
+impl Search {
+    /// Returns a search over the given space, which trains each configuration for
+    /// 1000 steps with a batch size of 8, and evaluates it over 100 samples.
+    pub fn new(space: Space, strategy: Strategy) -> Self {
+        Self {
+            space,
+            strategy,
+            steps: 1000,
+            batch_size: 8,
+            eval_samples: 100,
+            seed: 0,
+            recorder: None,
+        }
+    }
+
+    /// Configures the number of steps each configuration is trained for. When using
+    /// successive halving, this is the number of steps in the first round.
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Configures the number of samples in each training batch.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Configures the number of samples each configuration is evaluated over.
+    pub fn eval_samples(mut self, samples: usize) -> Self {
+        self.eval_samples = samples;
+        self
+    }
+
+    /// Configures the seed used to sample configurations, initialize networks and
+    /// generate problems. Every configuration starts from the same parameters and
+    /// trains on the same samples.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Records the training of each configuration to `trial-<n>.json` in the given
+    /// directory, using recorders built from the given builder.
+    ///
+    /// The evaluation loss is offered as the validation loss at the end of each round.
+    pub fn record_to<S: Into<String>>(mut self, dir: S, recorder: RecorderBuilder) -> Self {
+        self.recorder = Some((dir.into(), recorder));
+        self
+    }
+
+    /// Returns the configurations the search will train.
+    fn configs(&self) -> Vec<Config> {
+        let mut rng = SmallRng::seed_from_u64(self.seed);
+        match self.strategy {
+            Strategy::Grid { points } => self.space.grid(points),
+            Strategy::Random { trials } | Strategy::SuccessiveHalving { trials, .. } => {
+                (0..trials).map(|_| self.space.sample(&mut rng)).collect()
+            }
+        }
+    }
+
+    /// Runs the search, returning the configurations ranked from best to worst.
+    ///
+    /// Each configuration trains a network returned by `network`, with parameters
+    /// from [init_params](ResetParams::init_params). Training samples are drawn from the
+    /// problem returned by `problem` for the seed, and evaluation samples from the problem
+    /// for the seed plus one (wrapping). `loss` returns the loss and its gradients, as in
+    /// [train_batch].
+    ///
+    /// Returns an error of kind [InvalidInput](std::io::ErrorKind::InvalidInput) if
+    /// the space is empty.
+    pub fn run<P, N, NF, PF, L>(
+        &self,
+        network: NF,
+        problem: PF,
+        loss: L,
+    ) -> std::io::Result<SearchResults>
+    where
+        P: Problem + Send,
+        N: BackpropModule<P::Input, Output = P::Output> + LoadableModule + ResetParams + Send,
+        N::SelfGrads: Gradients + Send,
+        <N::SelfGrads as Gradients>::Concrete: Float,
+        NF: Fn() -> N + Sync,
+        PF: Fn(u64) -> P + Sync,
+        L: Fn(&P::Output, &P::Output) -> (f32, P::Output) + Sync,
+    {
+        self.space
+            .check()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        if let Some((dir, _)) = &self.recorder {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut trials = self
+            .configs()
+            .into_iter()
+            .enumerate()
+            .map(|(id, config)| {
+                let mut nn = network();
+                nn.init_params(&mut SmallRng::seed_from_u64(self.seed))
+                    .expect("failed to initialize network");
+
+                let params = config.train_params();
+                let momentum = config.momentum.unwrap_or_default();
+                let beta = config.beta.unwrap_or(0.9);
+                let updater = match config.optimizer {
+                    Optimizer::Momentum => Updater::Momentum(nn.new_momentum(params, momentum)),
+                    Optimizer::RMSProp => Updater::RMSProp(nn.new_rmsprop(params, beta)),
+                    Optimizer::RMSPropWithMomentum => {
+                        Updater::RMSProp(nn.new_rmsprop_with_momentum(params, momentum, beta))
+                    }
+                };
+
+                let recorder = match &self.recorder {
+                    Some((dir, builder)) => Some(
+                        builder
+                            .clone()
+                            .save_to(format!("{}/trial-{}.json", dir, id))
+                            .build()?,
+                    ),
+                    None => None,
+                };
+
+                Ok(Trial {
+                    id,
+                    config,
+                    network: nn,
+                    updater,
+                    problem: problem(self.seed),
+                    recorder,
+                    steps: 0,
+                    loss: f32::INFINITY,
+                    diverged: false,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        let eta = match self.strategy {
+            Strategy::SuccessiveHalving { eta, .. } => eta.max(2),
+            _ => 1,
+        };
+        let mut live: Vec<usize> = (0..trials.len()).collect();
+        let mut target = self.steps;
+        loop {
+            trials
+                .par_iter_mut()
+                .filter(|t| live.contains(&t.id))
+                .try_for_each(|t| {
+                    t.train_to(target, self.batch_size, &loss)?;
+                    t.evaluate(
+                        &mut problem(self.seed.wrapping_add(1)),
+                        self.eval_samples,
+                        &loss,
+                    )
+                })?;
+
+            if eta == 1 || live.len() <= 1 {
+                break;
+            }
+            live.sort_by(|a, b| trials[*a].loss.total_cmp(&trials[*b].loss));
+            live.truncate(live.len().div_ceil(eta));
+            target *= eta;
+        }
+
+        let mut results = Vec::with_capacity(trials.len());
+        for t in trials {
+            if let Some(r) = t.recorder {
+                r.flush()?;
+            }
+            results.push(TrialResult {
+                trial: t.id,
+                config: t.config,
+                steps: t.steps,
+                loss: t.loss,
+            });
+        }
+        results.sort_by(|a, b| b.steps.cmp(&a.steps).then(a.loss.total_cmp(&b.loss)));
+        Ok(SearchResults { trials: results })
+    }
+}
+
+/// The optimizer of a trial.
+enum Updater<G: Gradients>
+where
+    G::Concrete: Float,
+{
+    Momentum(Momentum<G>),
+    RMSProp(RMSProp<G>),
+}
+
+/// A configuration being trained.
+struct Trial<N, P, G: Gradients>
+where
+    G::Concrete: Float,
+{
+    id: usize,
+    config: Config,
+    network: N,
+    updater: Updater<G>,
+    problem: P,
+    recorder: Option<Recorder>,
+    steps: usize,
+    loss: f32,
+    diverged: bool,
+}
+
+impl<P, N> Trial<N, P, <N as BackpropModule<P::Input>>::SelfGrads>
+where
+    P: Problem,
+    N: BackpropModule<P::Input, Output = P::Output> + LoadableModule,
+    N::SelfGrads: Gradients,
+    <N::SelfGrads as Gradients>::Concrete: Float,
+{
+    /// Trains the network until it has been trained for the given number of steps,
+    /// stopping early if the loss diverges.
+    fn train_to(
+        &mut self,
+        steps: usize,
+        batch_size: usize,
+        loss: &impl Fn(&P::Output, &P::Output) -> (f32, P::Output),
+    ) -> std::io::Result<()> {
+        if self.diverged {
+            return Ok(());
+        }
+
+        let from = self.steps;
+        self.steps = match &mut self.updater {
+            Updater::Momentum(m) => train_steps::<P, N, _>(
+                m,
+                Momentum::train_params,
+                &mut self.network,
+                &mut self.problem,
+                &mut self.recorder,
+                from..steps,
+                batch_size,
+                loss,
+            ),
+            Updater::RMSProp(r) => train_steps::<P, N, _>(
+                r,
+                RMSProp::train_params,
+                &mut self.network,
+                &mut self.problem,
+                &mut self.recorder,
+                from..steps,
+                batch_size,
+                loss,
+            ),
+        }?;
+        self.diverged = self.steps < steps;
+        Ok(())
+    }
+
+    /// Computes the average loss of the network over samples of the given problem.
+    fn evaluate(
+        &mut self,
+        problem: &mut P,
+        samples: usize,
+        loss: &impl Fn(&P::Output, &P::Output) -> (f32, P::Output),
+    ) -> std::io::Result<()> {
+        let l = match self.diverged {
+            true => f32::INFINITY,
+            false => problem.avg_loss(&mut self.network, |got, want| loss(got, want).0, samples),
+        };
+        self.loss = if l.is_finite() { l } else { f32::INFINITY };
+        if let Some(r) = self.recorder.as_mut() {
+            r.record_validation(self.steps, || self.loss as f64)?;
+        }
+        Ok(())
+    }
+}
+
+/// Trains the network over the given range of steps, recording each batch.
+/// Returns the number of steps the network has been trained for, which falls
+/// short of the end of the range if training stopped early because the loss diverged.
+#[allow(clippy::too_many_arguments)]
+fn train_steps<P, N, GA>(
+    updater: &mut GA,
+    train_params: impl Fn(&GA) -> &TrainParams,
+    nn: &mut N,
+    problem: &mut P,
+    recorder: &mut Option<Recorder>,
+    steps: std::ops::Range<usize>,
+    batch_size: usize,
+    loss: &impl Fn(&P::Output, &P::Output) -> (f32, P::Output),
+) -> std::io::Result<usize>
+where
+    P: Problem,
+    N: BackpropModule<P::Input, Output = P::Output> + LoadableModule,
+    N::SelfGrads: Gradients,
+    GA: GradAdjuster<N::SelfGrads> + GradApplyer,
+{
+    let end = steps.end;
+    for step in steps {
+        let start = Instant::now();
+        let batch_loss = train_batch::<P::Input, f32, N, GA, _>(
+            updater,
+            nn,
+            loss,
+            &mut || problem.sample(),
+            batch_size,
+        );
+        if let Some(r) = recorder.as_mut() {
+            r.record_batch(
+                BatchInfo {
+                    step,
+                    size: batch_size,
+                    loss: batch_loss as f64,
+                    time_us: start.elapsed().as_micros() as u64,
+                },
+                train_params(updater),
+                nn,
+            )?;
+        }
+        if !batch_loss.is_finite() {
+            return Ok(step + 1);
+        }
+    }
+    Ok(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::problem::AxPlusB;
+    use crate::recorder::{Record, Recording};
+
+    #[test]
+    fn test_grid() {
+        assert_eq!(
+            Range::Linear { min: 0.0, max: 1.0 }.grid(3),
+            vec![0.0, 0.5, 1.0]
+        );
+        let log = Range::Log {
+            min: 1.0e-3,
+            max: 1.0e-1,
+        }
+        .grid(3);
+        assert!((log[1] - 1.0e-2).abs() < 1.0e-6, "{:?}", log);
+        assert_eq!(Range::Choice(vec![3.0, 1.0]).grid(5), vec![3.0, 1.0]);
+
+        let space = Space::with_lr(Range::Linear {
+            min: 0.01,
+            max: 0.1,
+        })
+        .and_l2(Range::Fixed(1.0e-6))
+        .and_momentum(Range::Choice(vec![0.5, 0.9]))
+        .and_beta(Range::Linear {
+            min: 0.9,
+            max: 0.99,
+        });
+        let grid = space.grid(2);
+        // 2 lrs each for: 2 momentums, 2 betas, and 2 momentums x 2 betas.
+        assert_eq!(grid.len(), 2 * (2 + 2 + 4));
+        assert!(grid.iter().all(|c| c.l2 == Some(1.0e-6)));
+        assert!(grid
+            .iter()
+            .filter(|c| c.optimizer == Optimizer::RMSProp)
+            .all(|c| c.momentum.is_none() && c.beta.is_some()));
+    }
+
+    #[test]
+    fn test_empty_space() {
+        let space = Space::with_lr(Range::Fixed(0.1));
+        assert_eq!(space.check(), Ok(()));
+        assert!(space.clone().with_optimizers(&[]).check().is_err());
+        assert!(Space::with_lr(Range::Choice(vec![])).check().is_err());
+        // Ranges which aren't used by any optimizer may be empty.
+        let rmsprop = space.with_optimizers(&[Optimizer::RMSProp]);
+        assert_eq!(
+            rmsprop.clone().and_momentum(Range::Choice(vec![])).check(),
+            Ok(())
+        );
+        assert!(rmsprop
+            .clone()
+            .and_beta(Range::Choice(vec![]))
+            .check()
+            .is_err());
+
+        let err = Search::new(
+            rmsprop.and_beta(Range::Choice(vec![])),
+            Strategy::Random { trials: 2 },
+        )
+        .run(
+            || Buildable::<f32>::build(&layers::Linear::<3, 1>::default()),
+            |seed| AxPlusB::default_with_rng(SmallRng::seed_from_u64(seed)),
+            |got, want| {
+                use loss::DiffLoss;
+                (got.mse(want), got.mse_input_grads(want))
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_diverged_steps() {
+        let space = Space::with_lr(Range::Fixed(1.0e12)).with_optimizers(&[Optimizer::Momentum]);
+        let results = Search::new(space, Strategy::Random { trials: 1 })
+            .steps(50)
+            .run(
+                || Buildable::<f32>::build(&layers::Linear::<3, 1>::default()),
+                |seed| AxPlusB::default_with_rng(SmallRng::seed_from_u64(seed)),
+                |got, want| {
+                    use loss::DiffLoss;
+                    (got.mse(want), got.mse_input_grads(want))
+                },
+            )
+            .unwrap();
+
+        let trial = results.best().unwrap();
+        assert_eq!(trial.loss, f32::INFINITY);
+        assert!(trial.steps > 0 && trial.steps < 50, "{}", trial.steps);
+    }
+
+    #[test]
+    fn test_successive_halving() {
+        let dir = std::env::temp_dir().join(format!("minidx_search_{}", std::process::id()));
+        let space = Space::with_lr(Range::Log {
+            min: 1.0e-4,
+            max: 1.0e-1,
+        })
+        .with_optimizers(&[Optimizer::RMSPropWithMomentum])
+        .and_momentum(Range::Linear { min: 0.3, max: 0.8 });
+
+        let results = Search::new(space, Strategy::SuccessiveHalving { trials: 4, eta: 2 })
+            .steps(50)
+            .seed(3)
+            .record_to(
+                dir.to_str().unwrap(),
+                Recorder::new().validation_freq(crate::recorder::Every::Steps(1)),
+            )
+            .run(
+                || {
+                    Buildable::<f32>::build(&(
+                        (layers::Linear::<3, 8>::default(), layers::Relu),
+                        layers::Linear::<8, 1>::default(),
+                    ))
+                },
+                |seed| AxPlusB::default_with_rng(SmallRng::seed_from_u64(seed)),
+                |got, want| {
+                    use loss::DiffLoss;
+                    (got.mse(want), got.mse_input_grads(want))
+                },
+            )
+            .unwrap();
+
+        let steps: Vec<_> = results.trials.iter().map(|t| t.steps).collect();
+        assert_eq!(steps, vec![200, 100, 50, 50]);
+        assert!(results.trials[2].loss <= results.trials[3].loss);
+        assert!(results.to_string().contains("rmsprop+momentum"));
+
+        // The best trial was recorded across every round.
+        let best = results.best().unwrap();
+        let path = dir.join(format!("trial-{}.json", best.trial));
+        let recording = Recording::open(path).unwrap();
+        let validations: Vec<_> = recording
+            .records
+            .iter()
+            .filter_map(|r| match r {
+                Record::Validation { step, .. } => Some(*step),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(validations, vec![50, 100, 200]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -12,7 +12,6 @@ use rand::SeedableRng;
 fn vis_as_video() {
     rayon::ThreadPoolBuilder::new()
         .num_threads(4)
-        .stack_size(12 * 1024 * 1024)
         .build_global()
         .unwrap();
     let mut recorder = Recorder::new()
@@ -39,7 +38,7 @@ fn vis_as_video() {
     let mut nn = Buildable::<f32>::build(&network);
 
     let mut rng = SmallRng::seed_from_u64(94356213);
-    nn.rand_params(&mut rng, 0.8).unwrap();
+    nn.init_params(&mut rng).unwrap();
 
     use minidx::problem::ModularAddition16;
     let mut problem = ModularAddition16::new(rng);
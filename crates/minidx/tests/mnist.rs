@@ -5,14 +5,13 @@ use rand::SeedableRng;
 use std::env;
 use std::fs::File;
 
-// RUST_MIN_STACK=104857600 cargo test --release -- --nocapture --include-ignored mnist_network
+// cargo test --release -- --nocapture --include-ignored mnist_network
 
 #[test]
 #[ignore]
 fn mnist_network() {
     rayon::ThreadPoolBuilder::new()
         .num_threads(4)
-        .stack_size(196 * 1024 * 1024)
         .build_global()
         .unwrap();
 
@@ -36,6 +36,10 @@ fn integration_modular_addition10() {
         );
     }
 
+    let m =
+        minidx::metrics::eval_classification(&nn, minidx::metrics::sampled(&mut problem, 500), 1);
+    assert!(m.accuracy() > 0.95, "accuracy was {}", m.accuracy());
+
     for _ in 0..30 {
         let (input, target) = problem.sample();
         let out = nn.forward(&input).unwrap();